@@ -0,0 +1,465 @@
+use std::fmt::Display;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::Utc;
+use md5::Md5;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue, WWW_AUTHENTICATE};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::logic::oauth::OAuth;
+use crate::logic::signing::{AwsSigning, HmacSigning, sign_aws, sign_hmac};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuthKind {
+    #[default]
+    None,
+    Basic,
+    Bearer,
+    ApiKey,
+    Digest,
+    OAuth2,
+    AwsSigV4,
+    Hmac,
+}
+
+impl AuthKind {
+    pub const ALL: [AuthKind; 8] = [
+        AuthKind::None,
+        AuthKind::Basic,
+        AuthKind::Bearer,
+        AuthKind::ApiKey,
+        AuthKind::Digest,
+        AuthKind::OAuth2,
+        AuthKind::AwsSigV4,
+        AuthKind::Hmac,
+    ];
+}
+
+impl Display for AuthKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AuthKind::None => "No auth",
+            AuthKind::Basic => "Basic",
+            AuthKind::Bearer => "Bearer token",
+            AuthKind::ApiKey => "API key",
+            AuthKind::Digest => "Digest",
+            AuthKind::OAuth2 => "OAuth 2.0",
+            AuthKind::AwsSigV4 => "AWS Signature V4",
+            AuthKind::Hmac => "HMAC-SHA256",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// The fields of every kind are kept, so switching back and forth doesn't lose them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Auth {
+    pub kind: AuthKind,
+    pub username: String,
+    pub password: String,
+    pub token: String,
+    pub key_name: String,
+    pub key_value: String,
+    pub key_in_query: bool,
+    pub oauth: OAuth,
+    pub aws: AwsSigning,
+    pub hmac: HmacSigning,
+}
+
+impl Auth {
+    pub fn to_json(&self) -> String {
+        let kind = match self.kind {
+            AuthKind::None => "none",
+            AuthKind::Basic => "basic",
+            AuthKind::Bearer => "bearer",
+            AuthKind::ApiKey => "apikey",
+            AuthKind::Digest => "digest",
+            AuthKind::OAuth2 => "oauth2",
+            AuthKind::AwsSigV4 => "aws",
+            AuthKind::Hmac => "hmac",
+        };
+        json!({
+            "kind": kind,
+            "username": self.username,
+            "password": self.password,
+            "token": self.token,
+            "key_name": self.key_name,
+            "key_value": self.key_value,
+            "key_in_query": self.key_in_query,
+            "oauth": self.oauth.to_json(),
+            "aws": self.aws.to_json(),
+            "hmac": self.hmac.to_json(),
+        })
+        .to_string()
+    }
+
+    pub fn from_json(text: &str) -> Auth {
+        let value: Value = serde_json::from_str(text).unwrap_or_default();
+        let field = |name: &str| value[name].as_str().unwrap_or_default().to_string();
+        Auth {
+            kind: match value["kind"].as_str() {
+                Some("basic") => AuthKind::Basic,
+                Some("bearer") => AuthKind::Bearer,
+                Some("apikey") => AuthKind::ApiKey,
+                Some("digest") => AuthKind::Digest,
+                Some("oauth2") => AuthKind::OAuth2,
+                Some("aws") => AuthKind::AwsSigV4,
+                Some("hmac") => AuthKind::Hmac,
+                _ => AuthKind::None,
+            },
+            username: field("username"),
+            password: field("password"),
+            token: field("token"),
+            key_name: field("key_name"),
+            key_value: field("key_value"),
+            key_in_query: value["key_in_query"].as_bool().unwrap_or_default(),
+            oauth: OAuth::from_json(&value["oauth"]),
+            aws: AwsSigning::from_json(&value["aws"]),
+            hmac: HmacSigning::from_json(&value["hmac"]),
+        }
+    }
+
+    // Digest is left out, it can only answer the challenge of a first request
+    pub fn apply(&self, url: &mut String, headers: &mut HeaderMap) {
+        let header = match self.kind {
+            AuthKind::Basic => Some((
+                AUTHORIZATION,
+                format!(
+                    "Basic {}",
+                    STANDARD.encode(format!("{}:{}", self.username, self.password))
+                ),
+            )),
+            AuthKind::Bearer if !self.token.is_empty() => {
+                Some((AUTHORIZATION, format!("Bearer {}", self.token)))
+            }
+            AuthKind::ApiKey if !self.key_name.is_empty() && self.key_in_query => {
+                url.push(if url.contains('?') { '&' } else { '?' });
+                url.push_str(&format!(
+                    "{}={}",
+                    urlencoding::encode(&self.key_name),
+                    urlencoding::encode(&self.key_value)
+                ));
+                None
+            }
+            AuthKind::ApiKey => HeaderName::from_bytes(self.key_name.as_bytes())
+                .ok()
+                .map(|name| (name, self.key_value.clone())),
+            // The token is fetched or refreshed before sending, see oauth::fetch_token
+            AuthKind::OAuth2 => self.oauth.token.as_ref().map(|token| {
+                let kind = match token.token_type.as_str() {
+                    it if it.eq_ignore_ascii_case("bearer") => "Bearer",
+                    it => it,
+                };
+                (AUTHORIZATION, format!("{} {}", kind, token.access_token))
+            }),
+            _ => None,
+        };
+        if let Some((name, value)) = header
+            && let Ok(value) = HeaderValue::from_str(&value)
+        {
+            headers.insert(name, value);
+        }
+    }
+
+    // The headers apply and sign add besides Authorization, they don't follow a redirect to another host
+    pub fn credential_headers(&self) -> Vec<HeaderName> {
+        let names = match self.kind {
+            AuthKind::ApiKey if !self.key_in_query => vec![self.key_name.as_str()],
+            AuthKind::AwsSigV4 => {
+                vec!["x-amz-date", "x-amz-security-token", "x-amz-content-sha256"]
+            }
+            AuthKind::Hmac => vec![
+                self.hmac.signature_header(),
+                self.hmac.timestamp_header.trim(),
+            ],
+            _ => vec![],
+        };
+        names
+            .into_iter()
+            .filter_map(|it| HeaderName::from_bytes(it.as_bytes()).ok())
+            .collect()
+    }
+
+    // Signatures cover the url, headers and body, so this runs after everything else is set
+    pub fn sign(&self, method: &str, url: &str, headers: &mut HeaderMap, body: &str) {
+        let Ok(url) = reqwest::Url::parse(url) else {
+            return;
+        };
+        match self.kind {
+            AuthKind::AwsSigV4 => sign_aws(&self.aws, method, &url, headers, body, Utc::now()),
+            AuthKind::Hmac => sign_hmac(&self.hmac, method, &url, headers, body, Utc::now()),
+            _ => {}
+        }
+    }
+
+    // Answers a `WWW-Authenticate: Digest ...` challenge (RFC 7616), None when there is nothing to answer
+    pub fn digest_response(
+        &self,
+        challenge: &HeaderMap,
+        method: &str,
+        url: &reqwest::Url,
+        body: &str,
+    ) -> Option<HeaderValue> {
+        if self.kind != AuthKind::Digest {
+            return None;
+        }
+        let params = challenge
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|it| it.to_str().ok())
+            .find_map(digest_challenge)
+            .map(parse_params)?;
+        let uri = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let cnonce: String = Sha256::digest(nanos.to_string())[..8]
+            .iter()
+            .map(|it| format!("{:02x}", it))
+            .collect();
+        self.digest_header(&params, method, &uri, body, &cnonce)
+    }
+
+    fn digest_header(
+        &self,
+        params: &[(String, String)],
+        method: &str,
+        uri: &str,
+        body: &str,
+        cnonce: &str,
+    ) -> Option<HeaderValue> {
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let realm = param("realm").unwrap_or_default();
+        let nonce = param("nonce")?;
+        let algorithm = param("algorithm").unwrap_or("MD5");
+        let hash = |data: &str| -> String {
+            let bytes = if algorithm.to_uppercase().starts_with("SHA-256") {
+                Sha256::digest(data).to_vec()
+            } else {
+                Md5::digest(data).to_vec()
+            };
+            bytes.iter().map(|it| format!("{:02x}", it)).collect()
+        };
+        let offered: Vec<&str> = param("qop")
+            .map(|it| it.split(',').map(str::trim).collect())
+            .unwrap_or_default();
+        let qop = ["auth", "auth-int"]
+            .into_iter()
+            .find(|it| offered.contains(it));
+        let mut ha1 = hash(&format!("{}:{}:{}", self.username, realm, self.password));
+        if algorithm.to_lowercase().ends_with("-sess") {
+            ha1 = hash(&format!("{}:{}:{}", ha1, nonce, cnonce));
+        }
+        let ha2 = match qop {
+            Some("auth-int") => hash(&format!("{}:{}:{}", method, uri, hash(body))),
+            _ => hash(&format!("{}:{}", method, uri)),
+        };
+        let response = match qop {
+            Some(qop) => hash(&format!(
+                "{}:{}:00000001:{}:{}:{}",
+                ha1, nonce, cnonce, qop, ha2
+            )),
+            None => hash(&format!("{}:{}:{}", ha1, nonce, ha2)),
+        };
+
+        let mut header = format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+            self.username, realm, nonce, uri, algorithm, response
+        );
+        if let Some(qop) = qop {
+            header.push_str(&format!(
+                ", qop={}, nc=00000001, cnonce=\"{}\"",
+                qop, cnonce
+            ));
+        }
+        if let Some(opaque) = param("opaque") {
+            header.push_str(&format!(", opaque=\"{}\"", opaque));
+        }
+        HeaderValue::from_str(&header).ok()
+    }
+}
+
+// The parameters of the Digest challenge in a header that may offer other schemes first,
+// e.g. `Basic realm="api", Digest realm="api", nonce="abc"`
+fn digest_challenge(header: &str) -> Option<&str> {
+    let mut quoted = false;
+    let mut previous = ',';
+    for (index, char) in header.char_indices() {
+        if !quoted
+            && (previous == ',' || previous.is_whitespace())
+            && header
+                .get(index..index + 7)
+                .is_some_and(|it| it.eq_ignore_ascii_case("digest "))
+        {
+            return Some(&header[index + 7..]);
+        }
+        if char == '"' && previous != '\\' {
+            quoted = !quoted;
+        }
+        previous = char;
+    }
+    None
+}
+
+// realm="api", qop="auth,auth-int", nonce="abc" -> [("realm", "api"), ...]
+// Stops at the next scheme of a header that offers several
+fn parse_params(input: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut chars = input.chars().peekable();
+    loop {
+        while chars
+            .next_if(|it| *it == ',' || it.is_whitespace())
+            .is_some()
+        {}
+        let key: String = chars.by_ref().take_while(|it| *it != '=').collect();
+        if key.is_empty() || key.trim().contains(char::is_whitespace) {
+            break;
+        }
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(char) = chars.next() {
+                match char {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    char => value.push(char),
+                }
+            }
+        } else {
+            while let Some(char) = chars.next_if(|it| *it != ',') {
+                value.push(char);
+            }
+        }
+        params.push((key.trim().to_lowercase(), value.trim().to_string()));
+    }
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 7616 section 3.9.1
+    const NONCE: &str = "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v";
+    const OPAQUE: &str = "FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS";
+    const CNONCE: &str = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+
+    fn mufasa() -> Auth {
+        Auth {
+            kind: AuthKind::Digest,
+            username: "Mufasa".to_string(),
+            password: "Circle of Life".to_string(),
+            ..Auth::default()
+        }
+    }
+
+    fn challenge(algorithm: &str) -> String {
+        format!(
+            r#"realm="http-auth@example.org", qop="auth, auth-int", algorithm={}, nonce="{}", opaque="{}""#,
+            algorithm, NONCE, OPAQUE
+        )
+    }
+
+    fn answer(algorithm: &str) -> String {
+        let params = parse_params(&challenge(algorithm));
+        mufasa()
+            .digest_header(&params, "GET", "/dir/index.html", "", CNONCE)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn rfc_7616_md5() {
+        assert_eq!(
+            answer("MD5"),
+            format!(
+                r#"Digest username="Mufasa", realm="http-auth@example.org", nonce="{}", uri="/dir/index.html", algorithm=MD5, response="8ca523f5e9506fed4657c9700eebdbec", qop=auth, nc=00000001, cnonce="{}", opaque="{}""#,
+                NONCE, CNONCE, OPAQUE
+            )
+        );
+    }
+
+    #[test]
+    fn rfc_7616_sha_256() {
+        assert!(answer("SHA-256").contains(
+            r#"algorithm=SHA-256, response="753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1""#
+        ));
+    }
+
+    #[test]
+    fn quoted_and_escaped_params() {
+        assert_eq!(
+            parse_params(
+                r#"realm="a \"quoted\", realm", nonce=abc ,qop="auth,auth-int",  Stale = FALSE"#
+            ),
+            [
+                ("realm".to_string(), r#"a "quoted", realm"#.to_string()),
+                ("nonce".to_string(), "abc".to_string()),
+                ("qop".to_string(), "auth,auth-int".to_string()),
+                ("stale".to_string(), "FALSE".to_string()),
+            ]
+        );
+        assert_eq!(parse_params(r#"realm="a\\b""#)[0].1, r#"a\b"#);
+        assert!(parse_params("").is_empty());
+    }
+
+    #[test]
+    fn challenge_among_other_schemes() {
+        assert_eq!(
+            digest_challenge(r#"Basic realm="api", Digest realm="api", nonce="n""#),
+            Some(r#"realm="api", nonce="n""#)
+        );
+        assert_eq!(
+            digest_challenge(r#"digest nonce="n""#),
+            Some(r#"nonce="n""#)
+        );
+        // Not a scheme inside a quoted value
+        assert_eq!(digest_challenge(r#"Basic realm="Digest here""#), None);
+        assert_eq!(digest_challenge("Bearer"), None);
+        // The parameters end where the next scheme starts
+        assert_eq!(
+            parse_params(r#"nonce="n", Basic realm="other""#),
+            [("nonce".to_string(), "n".to_string())]
+        );
+    }
+
+    #[test]
+    fn digest_response_from_a_combined_header() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            WWW_AUTHENTICATE,
+            HeaderValue::from_static(
+                r#"Basic realm="api", Digest realm="api", qop="auth", nonce="n""#,
+            ),
+        );
+        let url = reqwest::Url::parse("https://example.com/a/b?c=d").unwrap();
+        let answer = mufasa()
+            .digest_response(&headers, "POST", &url, "")
+            .unwrap();
+        let answer = answer.to_str().unwrap();
+        assert!(answer.starts_with(
+            r#"Digest username="Mufasa", realm="api", nonce="n", uri="/a/b?c=d", algorithm=MD5"#
+        ));
+        assert!(answer.contains("qop=auth, nc=00000001"));
+
+        // Only Digest is answered
+        let basic = Auth {
+            kind: AuthKind::Basic,
+            ..mufasa()
+        };
+        assert!(basic.digest_response(&headers, "POST", &url, "").is_none());
+    }
+}
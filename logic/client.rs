@@ -0,0 +1,29 @@
+use reqwest::{Client, ClientBuilder, Url};
+
+use crate::logic::common::MyErr;
+use crate::logic::proxy::ProxySettings;
+use crate::logic::tls::TlsSettings;
+
+// Every HTTP client of the app is built from these, so requests, introspection, WSDL,
+// downloads, load tests and token requests all go through the same proxy and TLS settings
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientSettings {
+    pub proxy: ProxySettings,
+    pub tls: TlsSettings,
+}
+
+impl ClientSettings {
+    // Client certificates are picked by the host of `url`
+    pub fn builder(&self, url: &str) -> Result<ClientBuilder, MyErr> {
+        let builder = self.proxy.apply(Client::builder()).map_err(MyErr::Client)?;
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|it| it.host_str().map(str::to_string))
+            .unwrap_or_default();
+        self.tls.apply(builder, &host).map_err(MyErr::Certificate)
+    }
+
+    pub fn client(&self, url: &str) -> Result<Client, MyErr> {
+        Ok(self.builder(url)?.build()?)
+    }
+}
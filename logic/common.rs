@@ -1,9 +1,40 @@
 use crate::AppTheme;
+use crate::logic::auth::{Auth, AuthKind};
+use crate::logic::connection::{ConnectionInfo, TlsDetails};
+use crate::logic::data::{DataRun, Row};
+use crate::logic::format::BodyLanguage;
+use crate::logic::graphql::Schema;
+use crate::logic::grpc::GrpcStatus;
+use crate::logic::highlight::LineRanges;
+use crate::logic::load::{LoadForm, LoadLimit, LoadProgress, LoadReport, LoadRun};
+use crate::logic::oauth::{Grant, Token};
+use crate::logic::proxy::{ProxyField, ProxyMode, ProxyScheme, ProxyScope, ProxySettings};
+use crate::logic::redirect::{Hop, RedirectMode, RedirectPolicy};
+use crate::logic::retry::{Attempt, Backoff, RetryPolicy};
+use crate::logic::script::TestResult;
+use crate::logic::signing::SigningField;
+use crate::logic::soap::Wsdl;
+use crate::logic::sse::SseEvent;
+use crate::logic::tls::{MinTls, TlsSettings, certificate_error};
 use arboard::Clipboard;
 use chrono::NaiveDateTime;
 use core::fmt;
+use iced::futures::channel::mpsc::UnboundedSender;
+use iced::task;
+use iced::widget::{image, text_editor};
+use prost_reflect::DescriptorPool;
 use reqwest::StatusCode;
-use std::{fmt::Display, io, str::FromStr, sync::Mutex};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io,
+    ops::Range,
+    path::PathBuf,
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -27,13 +58,42 @@ pub enum Message {
     ClearErrorMessage,
     ClickCopyResponse,
     SetCtrlPressed(bool),
-    GotResponse(String, StatusCode, HttpMethod, bool),
+    GotResponse(Box<Response>, HttpMethod, bool),
     DiscardDraftResponse,
     GotError(MyErr),
     Duplicate(String),
     SetDraftQuery(bool),
     SetSearch(String),
     FormatResponse,
+    MinifyResponse,
+    SaveResponseBody,
+    SetFormatLanguage(BodyLanguage),
+    SetResponseFilter(String),
+    // Sent a moment after an edit, or on Enter. Carries the edit count, later edits win.
+    ApplyResponseFilter(u64),
+    ToggleResponseWrap,
+    ToggleCertificates,
+    // Reads the version, cipher and full chain of the shown response's server
+    ProbeTls,
+    GotTlsDetails(u64, Result<TlsDetails, String>),
+    FocusFind,
+    BodySearch(MBodySearch),
+    Download(MDownload),
+    Sse(MSse),
+    Ws(MWs),
+    GraphQl(MGraphQl),
+    Grpc(MGrpc),
+    Rpc(MRpc),
+    Soap(MSoap),
+    Auth(MAuth),
+    Script(MScript),
+    Data(MData),
+    Load(MLoad),
+    Retry(MRetry),
+    Proxy(MProxy),
+    Tls(MTls),
+    Redirect(MRedirect),
+    OpenFile(String),
     Start,
     ClickMethod,
     QueryParam(MQueryParam),
@@ -58,16 +118,201 @@ pub enum MHeader {
     ToggleHeaderIsOn(u64),
 }
 
+#[derive(Debug, Clone)]
+pub enum MBodySearch {
+    SetQuery(String),
+    ToggleRegex,
+    ToggleCaseSensitive,
+    Next,
+    Previous,
+}
+
+#[derive(Debug, Clone)]
+pub enum MDownload {
+    Pick,
+    Start(PathBuf),
+    Progress(u64, Option<u64>),
+    Cancel,
+    Done(Result<Box<Response>, MyErr>),
+}
+
+#[derive(Debug, Clone)]
+pub enum MSse {
+    // Status, method, whether it is a draft and the body that was sent
+    Opened(StatusCode, HttpMethod, bool, String),
+    Event(SseEvent),
+    Disconnected,
+    Ended,
+    Stop,
+}
+
+#[derive(Debug, Clone)]
+pub enum MWs {
+    Connect,
+    Connected(StatusCode, UnboundedSender<WsMessage>),
+    Received(WsFrame),
+    Closed(Option<MyErr>),
+    Disconnect,
+    SetComposer(String),
+    ToggleBinary,
+    Send,
+}
+
+#[derive(Debug, Clone)]
+pub enum MGraphQl {
+    EditQuery(text_editor::Action),
+    EditVariables(text_editor::Action),
+    SelectOperation(String),
+    FetchSchema,
+    GotSchema(String, Schema),
+    Complete(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum MGrpc {
+    EditMessage(text_editor::Action),
+    SelectMethod(String),
+    Reflect,
+    PickProtos,
+    // Origin, descriptors and the .proto files they came from, none for reflection
+    GotPool(String, DescriptorPool, Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub enum MRpc {
+    AddCall,
+    DeleteCall(usize),
+    SetMethod(usize, String),
+    EditParams(usize, text_editor::Action),
+}
+
+#[derive(Debug, Clone)]
+pub enum MSoap {
+    SetWsdlUrl(String),
+    FetchWsdl,
+    PickWsdl,
+    GotWsdl(String, Wsdl),
+    SelectOperation(String),
+    EditEnvelope(text_editor::Action),
+}
+
+#[derive(Debug, Clone)]
+pub enum MRedirect {
+    SetMode(RedirectMode),
+    SetMaxHops(String),
+    ToggleKeepAuth,
+}
+
+#[derive(Debug, Clone)]
+pub enum MProxy {
+    SetMode(ProxyScope, ProxyMode),
+    SetScheme(ProxyScope, ProxyScheme),
+    EditField(ProxyScope, ProxyField, String),
+}
+
+#[derive(Debug, Clone)]
+pub enum MTls {
+    SetMinVersion(MinTls),
+    ToggleAcceptInvalid,
+    PickCa,
+    AddCa(String),
+    RemoveCa(usize),
+    PickClientCert,
+    AddClientCert(String),
+    PickKey(usize),
+    SetKey(usize, String),
+    SetCertHost(usize, String),
+    SetCertPassword(usize, String),
+    RemoveClientCert(usize),
+}
+
+#[derive(Debug, Clone)]
+pub enum MRetry {
+    SetMaxAttempts(String),
+    ToggleOn5xx,
+    ToggleOn429,
+    ToggleOnNetwork,
+    SetBackoff(Backoff),
+    SetDelay(String),
+    ToggleJitter,
+    ToggleRetryAfter,
+}
+
+#[derive(Debug, Clone)]
+pub enum MLoad {
+    SetLimit(LoadLimit),
+    SetAmount(String),
+    SetConcurrency(String),
+    SetRate(String),
+    Start,
+    Stop,
+    Progress(LoadReport),
+    Done(LoadReport),
+    ShowRun(u64),
+    DeleteRun(u64),
+}
+
+#[derive(Debug, Clone)]
+pub enum MData {
+    PickFile,
+    Loaded(String, Vec<Row>),
+    Run,
+    Stop,
+    RowDone(usize, Box<Result<Response, MyErr>>, Duration),
+    ShowResponse(u64),
+    Clear,
+}
+
+#[derive(Debug, Clone)]
+pub enum MScript {
+    EditPreRequest(text_editor::Action),
+    EditPostResponse(text_editor::Action),
+}
+
+#[derive(Debug, Clone)]
+pub enum MAuth {
+    SetKind(AuthKind),
+    SetUsername(String),
+    SetPassword(String),
+    SetToken(String),
+    SetKeyName(String),
+    SetKeyValue(String),
+    ToggleKeyInQuery,
+    ToggleSecrets,
+    SetGrant(Grant),
+    SetTokenUrl(String),
+    SetAuthorizeUrl(String),
+    SetClientId(String),
+    SetClientSecret(String),
+    SetScope(String),
+    FetchToken,
+    // The endpoint the token was fetched for, None for the draft, and Some(is_draft)
+    // when a send was waiting for it
+    GotToken(Option<u64>, Token, Option<bool>),
+    ClearToken,
+    SetSigning(SigningField, String),
+    ToggleHmacBase64,
+}
+
 #[derive(Debug, Clone)]
 pub enum MyErr {
     Unknown(String),
     Client(String),
+    Format(BodyLanguage, String),
+    // The server's certificate was rejected, or our own TLS files could not be used
+    Certificate(String),
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HttpMethod {
     GET,
     POST,
+    GQL,
+    GRPC,
+    RPC,
+    SOAP,
+    WS,
 }
 
 impl fmt::Display for HttpMethod {
@@ -83,6 +328,11 @@ impl FromStr for HttpMethod {
         match input {
             "GET" => Ok(HttpMethod::GET),
             "POST" => Ok(HttpMethod::POST),
+            "GQL" => Ok(HttpMethod::GQL),
+            "GRPC" => Ok(HttpMethod::GRPC),
+            "RPC" => Ok(HttpMethod::RPC),
+            "SOAP" => Ok(HttpMethod::SOAP),
+            "WS" => Ok(HttpMethod::WS),
             _ => Err(()),
         }
     }
@@ -94,6 +344,16 @@ pub struct EndpointDb {
     pub url: String,
     pub responses: Vec<Response>,
     pub method: HttpMethod,
+    pub response_filter: String,
+    pub auth: Auth,
+    pub pre_script: String,
+    pub post_script: String,
+    pub load_runs: Vec<LoadRun>,
+    pub retry: RetryPolicy,
+    pub redirect: RedirectPolicy,
+    pub proxy: ProxySettings,
+    // The .proto files picked for the endpoint, loaded again when it is opened
+    pub proto_paths: Vec<String>,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -103,13 +363,40 @@ pub struct Response {
     pub request: Request,
     pub text: String,
     pub code: StatusCode,
+    pub content_type: String,
+    pub body: Vec<u8>,
+    pub image: Option<image::Handle>,
+    pub file_path: Option<String>,
+    pub file_size: u64,
+    pub frames: Vec<WsFrame>,
+    pub grpc_status: Option<GrpcStatus>,
+    // Only known right after the request, they are not saved
+    pub headers: Vec<(String, String)>,
+    pub tests: Vec<TestResult>,
+    // Variables as they were expanded when sending, e.g. ("{{$uuid}}", "9b1d...")
+    pub variables: Vec<(String, String)>,
+    // Every try when the retry policy is on, the last one is this response
+    pub attempts: Vec<Attempt>,
+    // The redirects on the way to this response, in order
+    pub redirects: Vec<Hop>,
+    // Where the response came from and the TLS details, None for responses from before
+    pub connection: Option<ConnectionInfo>,
     pub received_time: NaiveDateTime,
 }
 
+#[derive(Debug, Clone)]
+pub struct WsFrame {
+    pub outgoing: bool,
+    pub binary: bool,
+    pub data: Vec<u8>,
+    pub time: NaiveDateTime,
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct Request {
     pub query_params: Vec<EndpointKvPair>,
     pub headers: Vec<EndpointKvPair>,
+    pub body: String,
 }
 
 #[derive(Debug, Clone)]
@@ -121,6 +408,75 @@ pub struct EndpointKvPair {
     pub on: bool,
 }
 
+// The body of the shown saved response, kept so reloading the endpoints doesn't read
+// or decode it again
+#[derive(Debug, Clone)]
+pub struct LoadedBody {
+    pub response_id: u64,
+    pub received_time: NaiveDateTime,
+    pub body: Vec<u8>,
+    pub image: Option<image::Handle>,
+    pub frames: Vec<WsFrame>,
+}
+
+// The response body as shown, highlighted once when it changes rather than on every redraw
+#[derive(Default, Debug, Clone)]
+pub struct ShownBody {
+    pub text: String,
+    pub mime_type: String,
+    pub lines: Vec<LineRanges>,
+    // The hex dump of a binary body that is no image, with the id and time of its response
+    pub hex: String,
+    pub hex_of: Option<(u64, NaiveDateTime)>,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct BodySearch {
+    pub query: String,
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub current: usize,
+    // Found in the shown body, kept until the query, a toggle or the body changes
+    pub matches: Vec<Range<usize>>,
+    pub error: Option<String>,
+}
+
+pub struct Download {
+    pub path: PathBuf,
+    pub method: HttpMethod,
+    pub received: u64,
+    pub total: Option<u64>,
+    pub started: Instant,
+}
+
+pub enum SseStatus {
+    Live,
+    Reconnecting,
+    Ended,
+}
+
+pub struct SseSession {
+    pub code: StatusCode,
+    pub method: HttpMethod,
+    pub is_draft: bool,
+    pub status: SseStatus,
+    pub events: Vec<SseEvent>,
+    // Saved with the events like the body of any other response
+    pub request_body: String,
+}
+
+#[derive(Default)]
+pub struct RpcCall {
+    pub method: String,
+    pub params: text_editor::Content,
+}
+
+pub struct WsConnection {
+    pub response_id: u64,
+    pub sender: UnboundedSender<WsMessage>,
+    pub frames: Vec<WsFrame>,
+}
+
 pub struct State {
     pub can_send: bool,
     pub endpoints: Vec<EndpointDb>,
@@ -128,11 +484,54 @@ pub struct State {
     pub draft: String,
     pub copy_request: Option<Request>,
     pub draft_request: Request,
-    pub draft_response: Option<(StatusCode, String, NaiveDateTime)>,
+    pub draft_response: Option<Response>,
     pub draft_method: HttpMethod,
     pub endp_search: String,
     pub selected_response_index: usize,
     pub formatted_response: Option<String>,
+    pub format_language: Option<BodyLanguage>,
+    pub response_filter: String,
+    pub response_filter_edits: u64,
+    pub auth: Auth,
+    pub show_secrets: bool,
+    pub pre_script: text_editor::Content,
+    pub post_script: text_editor::Content,
+    pub variables: HashMap<String, String>,
+    pub data_run: Option<DataRun>,
+    pub load_form: LoadForm,
+    pub load: Option<LoadProgress>,
+    pub selected_load_run: Option<u64>,
+    pub retry: RetryPolicy,
+    pub redirect: RedirectPolicy,
+    // The global proxy settings and the override of the current endpoint
+    pub proxy: ProxySettings,
+    pub endpoint_proxy: ProxySettings,
+    pub tls: TlsSettings,
+    pub filtered_response: Option<Result<String, String>>,
+    pub wrap_response: bool,
+    pub show_certificates: bool,
+    pub shown_body: ShownBody,
+    pub loaded_body: Option<LoadedBody>,
+    pub probing_tls: bool,
+    pub body_search: BodySearch,
+    pub download: Option<Download>,
+    pub sse: Option<SseSession>,
+    pub request_handle: Option<task::Handle>,
+    pub ws: Option<WsConnection>,
+    pub ws_composer: String,
+    pub ws_binary: bool,
+    pub graphql_query: text_editor::Content,
+    pub graphql_variables: text_editor::Content,
+    pub graphql_operation: Option<String>,
+    pub graphql_schemas: HashMap<String, Schema>,
+    pub grpc_message: text_editor::Content,
+    pub grpc_pools: HashMap<String, DescriptorPool>,
+    pub proto_paths: Vec<String>,
+    pub rpc_calls: Vec<RpcCall>,
+    pub soap_wsdl_url: String,
+    pub soap_services: HashMap<String, Wsdl>,
+    pub soap_operation: Option<String>,
+    pub soap_envelope: text_editor::Content,
     pub error_message: Option<String>,
     pub ctrl_pressed: bool,
     pub filter_method: Option<HttpMethod>,
@@ -142,7 +541,9 @@ pub struct State {
 
 impl From<reqwest::Error> for MyErr {
     fn from(err: reqwest::Error) -> Self {
-        if err.is_builder() {
+        if let Some(reason) = certificate_error(&err) {
+            Self::Certificate(reason)
+        } else if err.is_builder() {
             Self::Client("Invalid URL scheme.".to_string())
         } else {
             Self::Unknown(err.to_string())
@@ -154,6 +555,8 @@ impl Display for MyErr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Client(msg) => write!(f, "{}", msg),
+            Self::Format(language, msg) => write!(f, "Could not parse as {}: {}", language, msg),
+            Self::Certificate(msg) => write!(f, "TLS certificate error: {}", msg),
             Self::Unknown(msg) => {
                 write!(f, "Unknown error, please report to the developer: {}", msg)
             }
@@ -166,3 +569,14 @@ impl From<io::Error> for MyErr {
         Self::Unknown(value.to_string())
     }
 }
+
+impl From<tokio_tungstenite::tungstenite::Error> for MyErr {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        if let tokio_tungstenite::tungstenite::Error::Tls(_) = &err
+            && let Some(reason) = certificate_error(&err)
+        {
+            return Self::Certificate(reason);
+        }
+        Self::Client(format!("WebSocket error: {}", err))
+    }
+}
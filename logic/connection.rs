@@ -0,0 +1,291 @@
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use openssl::hash::MessageDigest;
+use openssl::ssl::{SslConnector, SslConnectorBuilder, SslMethod, SslVerifyMode, SslVersion};
+use openssl::x509::{X509, X509NameRef, X509Ref};
+use reqwest::tls::TlsInfo;
+use serde_json::{Value, json};
+
+use crate::logic::tls::{ClientCert, LoadedCert, MinTls, TlsSettings};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectionInfo {
+    // The address the response came from, the proxy's when there is one
+    pub remote_addr: Option<String>,
+    pub http_version: String,
+    // None for plain HTTP, an error when the TLS details could not be read
+    pub tls: Option<Result<TlsDetails, String>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TlsDetails {
+    // host:port the request went to, the probe connects there again
+    pub server: String,
+    // reqwest only exposes the server's certificate, the rest is known once the server was probed
+    pub version: Option<String>,
+    pub cipher: Option<String>,
+    pub alpn: Option<String>,
+    // The server's certificate first, then the intermediates it sent
+    pub chain: Vec<CertInfo>,
+}
+
+impl TlsDetails {
+    pub fn is_probed(&self) -> bool {
+        self.version.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CertInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub sans: Vec<String>,
+    pub not_before: Option<NaiveDateTime>,
+    pub not_after: Option<NaiveDateTime>,
+    pub sha256: String,
+    pub sha1: String,
+}
+
+// What the response itself tells, the client is built with tls_info(true)
+pub fn connection_info(resp: &reqwest::Response) -> ConnectionInfo {
+    let url = resp.url();
+    let tls = (url.scheme() == "https").then(|| {
+        let cert = resp
+            .extensions()
+            .get::<TlsInfo>()
+            .and_then(|it| it.peer_certificate())
+            .ok_or("The server sent no certificate")?;
+        let cert = X509::from_der(cert).map_err(|it| it.to_string())?;
+        Ok(TlsDetails {
+            server: format!(
+                "{}:{}",
+                url.host_str().unwrap_or_default(),
+                url.port_or_known_default().unwrap_or(443)
+            ),
+            chain: vec![cert_info(&cert)],
+            ..TlsDetails::default()
+        })
+    });
+    ConnectionInfo {
+        remote_addr: resp.remote_addr().map(|it| it.to_string()),
+        http_version: format!("{:?}", resp.version()),
+        tls,
+    }
+}
+
+// The version, cipher and full chain come from a second handshake with the server, offering
+// the same protocols and client certificate. It does not verify anything, the request did.
+pub async fn probe_tls(server: String, settings: TlsSettings) -> Result<TlsDetails, String> {
+    tokio::task::spawn_blocking(move || handshake(&server, &settings))
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+fn handshake(server: &str, settings: &TlsSettings) -> Result<TlsDetails, String> {
+    let host = server
+        .rsplit_once(':')
+        .map_or(server, |(host, _)| host)
+        .trim_matches(['[', ']']);
+    let mut builder =
+        SslConnector::builder(SslMethod::tls_client()).map_err(|it| it.to_string())?;
+    builder.set_verify(SslVerifyMode::NONE);
+    builder
+        .set_alpn_protos(b"\x02h2\x08http/1.1")
+        .map_err(|it| it.to_string())?;
+    builder
+        .set_min_proto_version(match settings.min_version {
+            MinTls::Default => None,
+            MinTls::Tls10 => Some(SslVersion::TLS1),
+            MinTls::Tls11 => Some(SslVersion::TLS1_1),
+            MinTls::Tls12 => Some(SslVersion::TLS1_2),
+        })
+        .map_err(|it| it.to_string())?;
+    if let Some(cert) = settings.client_cert(host) {
+        set_identity(&mut builder, cert)?;
+    }
+    let config = builder
+        .build()
+        .configure()
+        .map_err(|it| it.to_string())?
+        .verify_hostname(false);
+    let addr = server
+        .to_socket_addrs()
+        .map_err(|it| it.to_string())?
+        .next()
+        .ok_or_else(|| format!("Could not resolve {}", server))?;
+    let stream = TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).map_err(|it| it.to_string())?;
+    stream
+        .set_read_timeout(Some(PROBE_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(PROBE_TIMEOUT)))
+        .map_err(|it| it.to_string())?;
+    let stream = config.connect(host, stream).map_err(|it| it.to_string())?;
+    let ssl = stream.ssl();
+    Ok(TlsDetails {
+        server: server.to_string(),
+        version: Some(ssl.version_str().to_string()),
+        cipher: ssl.current_cipher().map(|it| it.name().to_string()),
+        alpn: ssl
+            .selected_alpn_protocol()
+            .map(|it| String::from_utf8_lossy(it).to_string()),
+        chain: ssl
+            .peer_cert_chain()
+            .map(|chain| chain.iter().map(cert_info).collect())
+            .unwrap_or_default(),
+    })
+}
+
+// The same certificate and key the request presented, see TlsSettings::apply
+fn set_identity(builder: &mut SslConnectorBuilder, cert: &ClientCert) -> Result<(), String> {
+    let LoadedCert { cert, key, chain } = cert.load()?;
+    for ca in chain {
+        builder
+            .add_extra_chain_cert(ca)
+            .map_err(|it| it.to_string())?;
+    }
+    builder
+        .set_certificate(&cert)
+        .and_then(|_| builder.set_private_key(&key))
+        .map_err(|it| it.to_string())
+}
+
+fn cert_info(cert: &X509Ref) -> CertInfo {
+    let fingerprint = |digest: MessageDigest| {
+        cert.digest(digest)
+            .map(|bytes| {
+                bytes
+                    .iter()
+                    .map(|it| format!("{:02X}", it))
+                    .collect::<Vec<_>>()
+                    .join(":")
+            })
+            .unwrap_or_default()
+    };
+    // OpenSSL prints dates as "Jan  1 00:00:00 2026 GMT"
+    let date = |it: String| NaiveDateTime::parse_from_str(&it, "%b %e %H:%M:%S %Y GMT").ok();
+    CertInfo {
+        subject: name(cert.subject_name()),
+        issuer: name(cert.issuer_name()),
+        sans: cert
+            .subject_alt_names()
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|it| {
+                        it.dnsname().map(str::to_string).or_else(|| {
+                            let ip: IpAddr = match it.ipaddress()? {
+                                &[a, b, c, d] => [a, b, c, d].into(),
+                                bytes => <[u8; 16]>::try_from(bytes).ok()?.into(),
+                            };
+                            Some(ip.to_string())
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        not_before: date(cert.not_before().to_string()),
+        not_after: date(cert.not_after().to_string()),
+        sha256: fingerprint(MessageDigest::sha256()),
+        sha1: fingerprint(MessageDigest::sha1()),
+    }
+}
+
+// "CN=example.com, O=Example Inc, C=US"
+fn name(name: &X509NameRef) -> String {
+    name.entries()
+        .map(|entry| {
+            format!(
+                "{}={}",
+                entry.object().nid().short_name().unwrap_or("?"),
+                entry
+                    .data()
+                    .as_utf8()
+                    .map(|it| it.to_string())
+                    .unwrap_or_default()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl ConnectionInfo {
+    pub fn to_json(&self) -> String {
+        let date = |it: Option<NaiveDateTime>| it.map(|it| it.format(DATE_FORMAT).to_string());
+        json!({
+            "remote_addr": self.remote_addr,
+            "http_version": self.http_version,
+            "tls": match &self.tls {
+                None => Value::Null,
+                Some(Err(err)) => json!({ "error": err }),
+                Some(Ok(tls)) => json!({
+                    "server": tls.server,
+                    "version": tls.version,
+                    "cipher": tls.cipher,
+                    "alpn": tls.alpn,
+                    "chain": tls
+                        .chain
+                        .iter()
+                        .map(|it| {
+                            json!({
+                                "subject": it.subject,
+                                "issuer": it.issuer,
+                                "sans": it.sans,
+                                "not_before": date(it.not_before),
+                                "not_after": date(it.not_after),
+                                "sha256": it.sha256,
+                                "sha1": it.sha1,
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                }),
+            },
+        })
+        .to_string()
+    }
+
+    // Responses saved before this was recorded have no connection info
+    pub fn from_json(text: &str) -> Option<ConnectionInfo> {
+        let value: Value = serde_json::from_str(text).ok()?;
+        let string = |it: &Value| it.as_str().unwrap_or_default().to_string();
+        let date = |it: &Value| NaiveDateTime::parse_from_str(it.as_str()?, DATE_FORMAT).ok();
+        let tls = &value["tls"];
+        Some(ConnectionInfo {
+            remote_addr: value["remote_addr"].as_str().map(str::to_string),
+            http_version: string(&value["http_version"]),
+            tls: match tls["error"].as_str() {
+                _ if tls.is_null() => None,
+                Some(err) => Some(Err(err.to_string())),
+                None => Some(Ok(TlsDetails {
+                    server: string(&tls["server"]),
+                    version: tls["version"].as_str().map(str::to_string),
+                    cipher: tls["cipher"].as_str().map(str::to_string),
+                    alpn: tls["alpn"].as_str().map(str::to_string),
+                    chain: tls["chain"]
+                        .as_array()
+                        .map(|chain| {
+                            chain
+                                .iter()
+                                .map(|it| CertInfo {
+                                    subject: string(&it["subject"]),
+                                    issuer: string(&it["issuer"]),
+                                    sans: it["sans"]
+                                        .as_array()
+                                        .map(|sans| sans.iter().map(string).collect())
+                                        .unwrap_or_default(),
+                                    not_before: date(&it["not_before"]),
+                                    not_after: date(&it["not_after"]),
+                                    sha256: string(&it["sha256"]),
+                                    sha1: string(&it["sha1"]),
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                })),
+            },
+        })
+    }
+}
@@ -1,7 +1,11 @@
 use crate::EndpointDb as Endpoint;
+use crate::logic::auth::Auth;
 use crate::logic::crud::header::create_header_with_tx;
 use crate::logic::crud::query::create_query_param_with_tx;
 use crate::logic::crud::response::create_response_with_tx;
+use crate::logic::proxy::ProxySettings;
+use crate::logic::redirect::RedirectPolicy;
+use crate::logic::retry::RetryPolicy;
 use rusqlite::{Connection, Result as RusqliteResult};
 
 // ============================================================================
@@ -22,13 +26,24 @@ pub fn create_endpoint_full(conn: &Connection, endpoint: &Endpoint) -> RusqliteR
     let tx = conn.unchecked_transaction()?;
 
     tx.execute(
-        "INSERT INTO endpoint (url, method) VALUES (?, ?)",
-        [&endpoint.url, &endpoint.method.to_string()],
+        "INSERT INTO endpoint (url, method, response_filter, auth, pre_script, post_script, retry, redirect, proxy, proto_paths) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        [
+            &endpoint.url,
+            &endpoint.method.to_string(),
+            &endpoint.response_filter,
+            &endpoint.auth.to_json(),
+            &endpoint.pre_script,
+            &endpoint.post_script,
+            &endpoint.retry.to_json(),
+            &endpoint.redirect.to_json(),
+            &endpoint.proxy.to_json(),
+            &endpoint.proto_paths.join("\n"),
+        ],
     )?;
     let endpoint_id = tx.last_insert_rowid() as u64;
 
     for response in &endpoint.responses {
-        create_response_with_tx(&tx, endpoint_id, &response.text, response.code)?;
+        create_response_with_tx(&tx, endpoint_id, response)?;
         let response_id = tx.last_insert_rowid() as u64;
 
         for qp in &response.request.query_params {
@@ -55,3 +70,84 @@ pub fn update_endpoint_url(conn: &Connection, id: u64, url: &str) -> RusqliteRes
     )?;
     Ok(())
 }
+
+pub fn update_endpoint_response_filter(
+    conn: &Connection,
+    id: u64,
+    response_filter: &str,
+) -> RusqliteResult<()> {
+    conn.execute(
+        "UPDATE endpoint SET response_filter = ? WHERE id = ?",
+        rusqlite::params![response_filter, id],
+    )?;
+    Ok(())
+}
+
+pub fn update_endpoint_auth(conn: &Connection, id: u64, auth: &Auth) -> RusqliteResult<()> {
+    conn.execute(
+        "UPDATE endpoint SET auth = ? WHERE id = ?",
+        rusqlite::params![auth.to_json(), id],
+    )?;
+    Ok(())
+}
+
+pub fn update_endpoint_scripts(
+    conn: &Connection,
+    id: u64,
+    pre_script: &str,
+    post_script: &str,
+) -> RusqliteResult<()> {
+    conn.execute(
+        "UPDATE endpoint SET pre_script = ?, post_script = ? WHERE id = ?",
+        rusqlite::params![pre_script, post_script, id],
+    )?;
+    Ok(())
+}
+
+pub fn update_endpoint_retry(
+    conn: &Connection,
+    id: u64,
+    retry: &RetryPolicy,
+) -> RusqliteResult<()> {
+    conn.execute(
+        "UPDATE endpoint SET retry = ? WHERE id = ?",
+        rusqlite::params![retry.to_json(), id],
+    )?;
+    Ok(())
+}
+
+pub fn update_endpoint_redirect(
+    conn: &Connection,
+    id: u64,
+    redirect: &RedirectPolicy,
+) -> RusqliteResult<()> {
+    conn.execute(
+        "UPDATE endpoint SET redirect = ? WHERE id = ?",
+        rusqlite::params![redirect.to_json(), id],
+    )?;
+    Ok(())
+}
+
+pub fn update_endpoint_proxy(
+    conn: &Connection,
+    id: u64,
+    proxy: &ProxySettings,
+) -> RusqliteResult<()> {
+    conn.execute(
+        "UPDATE endpoint SET proxy = ? WHERE id = ?",
+        rusqlite::params![proxy.to_json(), id],
+    )?;
+    Ok(())
+}
+
+pub fn update_endpoint_proto_paths(
+    conn: &Connection,
+    id: u64,
+    paths: &[String],
+) -> RusqliteResult<()> {
+    conn.execute(
+        "UPDATE endpoint SET proto_paths = ? WHERE id = ?",
+        rusqlite::params![paths.join("\n"), id],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use crate::logic::load::LoadRun;
+use rusqlite::{Connection, Result as RusqliteResult};
+
+pub fn create_load_run(
+    conn: &Connection,
+    parent_endpoint_id: u64,
+    run: &LoadRun,
+) -> RusqliteResult<u64> {
+    conn.execute(
+        "INSERT INTO load_run (parent_endpoint_id, settings, report, started_time) VALUES (?, ?, ?, ?)",
+        rusqlite::params![
+            parent_endpoint_id,
+            run.settings.to_json(),
+            run.report.to_json(),
+            run.started_time
+        ],
+    )?;
+    Ok(conn.last_insert_rowid() as u64)
+}
+
+pub fn delete_load_run(conn: &Connection, id: u64) -> RusqliteResult<()> {
+    conn.execute("DELETE FROM load_run WHERE id = ?", [id])?;
+    Ok(())
+}
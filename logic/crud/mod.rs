@@ -1,4 +1,7 @@
 pub mod endpoint;
 pub mod header;
+pub mod load_run;
 pub mod query;
 pub mod response;
+pub mod setting;
+pub mod ws_frame;
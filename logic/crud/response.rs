@@ -1,20 +1,60 @@
-use chrono::NaiveDateTime;
-use reqwest::StatusCode;
+use crate::logic::common::Response;
+use crate::logic::connection::ConnectionInfo;
+use crate::logic::redirect::hops_to_json;
+use crate::logic::retry::attempts_to_json;
+use crate::logic::script::tests_to_json;
+use crate::logic::variables::resolved_to_json;
 use rusqlite::{Connection, Result as RusqliteResult};
 
 pub fn create_response(
     conn: &Connection,
     parent_endpoint_id: u64,
-    text: &str,
-    code: StatusCode,
+    response: &Response,
 ) -> RusqliteResult<u64> {
     conn.execute(
-        "INSERT INTO response (parent_endpoint_id, text, code) VALUES (?, ?, ?)",
-        rusqlite::params![parent_endpoint_id, text, code.as_u16() as i64],
+        "INSERT INTO response (parent_endpoint_id, text, code, content_type, body, file_path, file_size, request_body, grpc_code, grpc_message, tests, variables, attempts, redirects, connection, received_time) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            parent_endpoint_id,
+            response.text,
+            response.code.as_u16() as i64,
+            response.content_type,
+            stored_body(response),
+            response.file_path,
+            response.file_size,
+            response.request.body,
+            response.grpc_status.as_ref().map(|it| it.code),
+            response.grpc_status.as_ref().map(|it| it.message.as_str()),
+            tests_to_json(&response.tests),
+            resolved_to_json(&response.variables),
+            attempts_to_json(&response.attempts),
+            hops_to_json(&response.redirects),
+            response
+                .connection
+                .as_ref()
+                .map_or(String::new(), ConnectionInfo::to_json),
+            response.received_time
+        ],
     )?;
     Ok(conn.last_insert_rowid() as u64)
 }
 
+// Textual bodies are kept as text only, the body column is for what the text can't hold
+fn stored_body(response: &Response) -> Option<&[u8]> {
+    (response.body != response.text.as_bytes()).then_some(response.body.as_slice())
+}
+
+// The body of a saved response, which load_endpoints leaves out
+pub fn get_response_body(conn: &Connection, id: u64) -> RusqliteResult<Vec<u8>> {
+    conn.query_row(
+        "SELECT body, text FROM response WHERE id = ?",
+        [id],
+        |row| match row.get::<_, Option<Vec<u8>>>(0)? {
+            Some(body) => Ok(body),
+            None => Ok(row.get::<_, String>(1)?.into_bytes()),
+        },
+    )
+}
+
 pub fn response_count_by_endpoint_id(
     conn: &rusqlite::Connection,
     id: u64,
@@ -32,16 +72,47 @@ pub fn response_count_by_endpoint_id(
 pub fn create_response_with_tx(
     tx: &rusqlite::Transaction,
     parent_endpoint_id: u64,
-    text: &str,
-    code: StatusCode,
+    response: &Response,
 ) -> RusqliteResult<u64> {
     tx.execute(
-        "INSERT INTO response (parent_endpoint_id, text, code) VALUES (?, ?, ?)",
-        rusqlite::params![parent_endpoint_id, text, code.as_u16() as i64],
+        "INSERT INTO response (parent_endpoint_id, text, code, content_type, body, file_path, file_size, request_body, grpc_code, grpc_message, tests, variables, attempts, redirects, connection, received_time) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            parent_endpoint_id,
+            response.text,
+            response.code.as_u16() as i64,
+            response.content_type,
+            stored_body(response),
+            response.file_path,
+            response.file_size,
+            response.request.body,
+            response.grpc_status.as_ref().map(|it| it.code),
+            response.grpc_status.as_ref().map(|it| it.message.as_str()),
+            tests_to_json(&response.tests),
+            resolved_to_json(&response.variables),
+            attempts_to_json(&response.attempts),
+            hops_to_json(&response.redirects),
+            response
+                .connection
+                .as_ref()
+                .map_or(String::new(), ConnectionInfo::to_json),
+            response.received_time
+        ],
     )?;
     Ok(tx.last_insert_rowid() as u64)
 }
 
+pub fn update_response_connection(
+    conn: &Connection,
+    id: u64,
+    connection: &ConnectionInfo,
+) -> RusqliteResult<()> {
+    conn.execute(
+        "UPDATE response SET connection = ? WHERE id = ?",
+        rusqlite::params![connection.to_json(), id],
+    )?;
+    Ok(())
+}
+
 pub fn delete_response(conn: &Connection, id: u64) -> RusqliteResult<()> {
     conn.execute("DELETE FROM response WHERE id = ?", [id])?;
     Ok(())
@@ -58,16 +129,30 @@ pub fn delete_responses_by_endpoint(
     Ok(())
 }
 
-pub fn update_response(
-    conn: &Connection,
-    id: u64,
-    text: &str,
-    code: StatusCode,
-    received_time: NaiveDateTime,
-) -> RusqliteResult<()> {
+pub fn update_response(conn: &Connection, id: u64, response: &Response) -> RusqliteResult<()> {
     conn.execute(
-        "UPDATE response SET text = ?, code = ?, received_time = ? WHERE id = ?",
-        rusqlite::params![text, code.as_u16() as i64, received_time, id],
+        "UPDATE response SET text = ?, code = ?, content_type = ?, body = ?, file_path = ?, file_size = ?, request_body = ?, grpc_code = ?, grpc_message = ?, tests = ?, variables = ?, attempts = ?, redirects = ?, connection = ?, received_time = ? WHERE id = ?",
+        rusqlite::params![
+            response.text,
+            response.code.as_u16() as i64,
+            response.content_type,
+            stored_body(response),
+            response.file_path,
+            response.file_size,
+            response.request.body,
+            response.grpc_status.as_ref().map(|it| it.code),
+            response.grpc_status.as_ref().map(|it| it.message.as_str()),
+            tests_to_json(&response.tests),
+            resolved_to_json(&response.variables),
+            attempts_to_json(&response.attempts),
+            hops_to_json(&response.redirects),
+            response
+                .connection
+                .as_ref()
+                .map_or(String::new(), ConnectionInfo::to_json),
+            response.received_time,
+            id
+        ],
     )?;
     Ok(())
 }
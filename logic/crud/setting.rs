@@ -0,0 +1,16 @@
+use rusqlite::{Connection, OptionalExtension, Result as RusqliteResult};
+
+pub fn get_setting(conn: &Connection, key: &str) -> RusqliteResult<Option<String>> {
+    conn.query_row("SELECT value FROM setting WHERE key = ?", [key], |row| {
+        row.get(0)
+    })
+    .optional()
+}
+
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> RusqliteResult<()> {
+    conn.execute(
+        "INSERT INTO setting (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        [key, value],
+    )?;
+    Ok(())
+}
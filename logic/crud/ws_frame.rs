@@ -0,0 +1,35 @@
+use crate::logic::common::WsFrame;
+use rusqlite::{Connection, Result as RusqliteResult};
+
+pub fn create_ws_frame(
+    conn: &Connection,
+    parent_response_id: u64,
+    frame: &WsFrame,
+) -> RusqliteResult<u64> {
+    conn.execute(
+        "INSERT INTO ws_frame (parent_response_id, outgoing, binary, data, sent_time) VALUES (?, ?, ?, ?, ?)",
+        rusqlite::params![
+            parent_response_id,
+            frame.outgoing,
+            frame.binary,
+            frame.data,
+            frame.time
+        ],
+    )?;
+    Ok(conn.last_insert_rowid() as u64)
+}
+
+pub fn get_ws_frames(conn: &Connection, parent_response_id: u64) -> RusqliteResult<Vec<WsFrame>> {
+    let mut stmt = conn.prepare(
+        "SELECT outgoing, binary, data, sent_time FROM ws_frame WHERE parent_response_id = ? ORDER BY id",
+    )?;
+    stmt.query_map([parent_response_id], |row| {
+        Ok(WsFrame {
+            outgoing: row.get(0)?,
+            binary: row.get(1)?,
+            data: row.get(2)?,
+            time: row.get(3)?,
+        })
+    })?
+    .collect()
+}
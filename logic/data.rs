@@ -0,0 +1,88 @@
+use std::path::Path;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::Value;
+
+// Column names with the values of one row, in file order
+pub type Row = Vec<(String, String)>;
+
+#[derive(Debug, Clone)]
+pub struct RowResult {
+    pub response_id: Option<u64>,
+    pub status: Result<StatusCode, String>,
+    pub duration: Duration,
+    pub tests_passed: usize,
+    pub tests_total: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DataRun {
+    pub file: String,
+    pub rows: Vec<Row>,
+    pub results: Vec<RowResult>,
+    pub running: bool,
+}
+
+// JSON files are an array of objects, anything else is read as CSV with a header line
+pub fn parse_data(path: &Path, text: &str) -> Result<Vec<Row>, String> {
+    let rows = match path.extension().and_then(|it| it.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("json") => parse_json(text)?,
+        _ => parse_csv(text)?,
+    };
+    if rows.is_empty() {
+        return Err("The data file has no rows".to_string());
+    }
+    Ok(rows)
+}
+
+fn parse_csv(text: &str) -> Result<Vec<Row>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::Headers)
+        .from_reader(text.as_bytes());
+    let columns: Vec<String> = reader
+        .headers()
+        .map_err(|it| it.to_string())?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|it| it.to_string())?;
+            Ok(columns
+                .iter()
+                .cloned()
+                .zip(record.iter().map(str::to_string))
+                .collect())
+        })
+        .collect()
+}
+
+fn parse_json(text: &str) -> Result<Vec<Row>, String> {
+    let value: Value = serde_json::from_str(text).map_err(|it| it.to_string())?;
+    let Value::Array(items) = value else {
+        return Err("A JSON data file must be an array of objects".to_string());
+    };
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| match item {
+            Value::Object(fields) => Ok(fields
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Value::String(it) => it,
+                        Value::Null => String::new(),
+                        it => it.to_string(),
+                    };
+                    (key, value)
+                })
+                .collect()),
+            _ => Err(format!(
+                "Item #{} of the data file is not an object",
+                index + 1
+            )),
+        })
+        .collect()
+}
@@ -4,9 +4,22 @@ use std::{
 };
 
 use reqwest::StatusCode;
-use rusqlite::{Connection, Result};
+use rusqlite::{Connection, Result, Transaction};
 
-use crate::{EndpointDb, EndpointKvPair, Request, Response, logic::common::HttpMethod};
+use crate::{
+    EndpointDb, EndpointKvPair, Request, Response,
+    logic::auth::Auth,
+    logic::common::HttpMethod,
+    logic::connection::ConnectionInfo,
+    logic::grpc::GrpcStatus,
+    logic::load::{LoadReport, LoadRun, LoadSettings},
+    logic::mime::detect_content_type,
+    logic::proxy::ProxySettings,
+    logic::redirect::{RedirectPolicy, hops_from_json},
+    logic::retry::{RetryPolicy, attempts_from_json},
+    logic::script::tests_from_json,
+    logic::variables::resolved_from_json,
+};
 
 static DB: OnceLock<Mutex<Connection>> = OnceLock::new();
 
@@ -61,10 +74,104 @@ pub fn init() -> Result<()> {
          )",
         (),
     )?;
+    tx.execute(
+        "create table if not exists ws_frame (
+             id integer primary key,
+             parent_response_id integer not null,
+             outgoing integer not null,
+             binary integer not null,
+             data BLOB,
+             sent_time DATETIME,
+             FOREIGN KEY (parent_response_id) references response(id) ON DELETE CASCADE
+         )",
+        (),
+    )?;
+    tx.execute(
+        "create table if not exists load_run (
+             id integer primary key,
+             parent_endpoint_id integer not null,
+             settings TEXT NOT NULL,
+             report TEXT NOT NULL,
+             started_time DATETIME,
+             FOREIGN KEY (parent_endpoint_id) references endpoint(id) ON DELETE CASCADE
+         )",
+        (),
+    )?;
+
+    // Settings that belong to the app rather than to an endpoint, values are JSON
+    tx.execute(
+        "create table if not exists setting (
+             key varchar(128) primary key,
+             value TEXT NOT NULL
+         )",
+        (),
+    )?;
+
+    add_column_if_missing(
+        &tx,
+        "endpoint",
+        "response_filter",
+        "varchar(512) NOT NULL DEFAULT ''",
+    )?;
+    add_column_if_missing(&tx, "endpoint", "auth", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(&tx, "endpoint", "pre_script", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(&tx, "endpoint", "post_script", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(&tx, "endpoint", "retry", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(&tx, "endpoint", "redirect", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(&tx, "endpoint", "proxy", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(&tx, "endpoint", "proto_paths", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(
+        &tx,
+        "response",
+        "content_type",
+        "varchar(256) NOT NULL DEFAULT ''",
+    )?;
+    add_column_if_missing(&tx, "response", "body", "BLOB")?;
+    add_column_if_missing(&tx, "response", "file_path", "varchar(1024)")?;
+    add_column_if_missing(&tx, "response", "file_size", "integer NOT NULL DEFAULT 0")?;
+    add_column_if_missing(&tx, "response", "request_body", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(&tx, "response", "grpc_code", "integer")?;
+    add_column_if_missing(&tx, "response", "grpc_message", "TEXT")?;
+    add_column_if_missing(&tx, "response", "tests", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(&tx, "response", "variables", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(&tx, "response", "attempts", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(&tx, "response", "redirects", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(&tx, "response", "connection", "TEXT NOT NULL DEFAULT ''")?;
+    // One-time migrations, user_version counts the ones that ran
+    let version: u32 = tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version < 1 {
+        // Textual bodies used to be stored in both columns, the text is enough
+        tx.execute(
+            "UPDATE response SET body = NULL WHERE body = CAST(text AS BLOB)",
+            [],
+        )?;
+    }
+    tx.execute("PRAGMA user_version = 1", [])?;
 
     tx.commit()
 }
 
+fn add_column_if_missing(
+    tx: &Transaction,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let exists = tx
+        .prepare(&format!(
+            "SELECT 1 FROM pragma_table_info('{}') WHERE name = ?",
+            table
+        ))?
+        .exists([column])?;
+    if !exists {
+        tx.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            (),
+        )?;
+    }
+    Ok(())
+}
+
 pub fn load_endpoints(
     conn: &rusqlite::Connection,
     query: Option<&str>,
@@ -79,22 +186,42 @@ pub fn load_endpoints(
         None => "%".to_string(),
     };
     let mut stmt =
-        conn.prepare("SELECT id, url, method FROM endpoint WHERE (url LIKE (?) OR EXISTS (SELECT 1 FROM response WHERE parent_endpoint_id = endpoint.id AND text LIKE (?))) AND method LIKE (?) ORDER BY id DESC")?;
+        conn.prepare("SELECT id, url, method, response_filter, auth, pre_script, post_script, retry, redirect, proxy, proto_paths FROM endpoint WHERE (url LIKE (?) OR EXISTS (SELECT 1 FROM response WHERE parent_endpoint_id = endpoint.id AND text LIKE (?))) AND method LIKE (?) ORDER BY id DESC")?;
     let endpoint_rows = stmt.query_map([&search, &search, &search_method], |row| {
         Ok((
             row.get::<_, u64>(0)?,
             row.get::<_, String>(1)?,
             row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, String>(5)?,
+            row.get::<_, String>(6)?,
+            row.get::<_, String>(7)?,
+            row.get::<_, String>(8)?,
+            row.get::<_, String>(9)?,
+            row.get::<_, String>(10)?,
         ))
     })?;
 
     let mut endpoints = Vec::new();
 
     for endpoint_result in endpoint_rows {
-        let (id, url, method) = endpoint_result?;
+        let (
+            id,
+            url,
+            method,
+            response_filter,
+            auth,
+            pre_script,
+            post_script,
+            retry,
+            redirect,
+            proxy,
+            proto_paths,
+        ) = endpoint_result?;
 
         let mut resp_stmt = conn.prepare(
-            "SELECT id, parent_endpoint_id, text, code, received_time
+            "SELECT id, parent_endpoint_id, text, code, received_time, content_type, file_path, file_size, request_body, grpc_code, grpc_message, tests, variables, attempts, redirects, connection
              FROM response
              WHERE parent_endpoint_id = ?",
         )?;
@@ -136,20 +263,66 @@ pub fn load_endpoints(
                     })?
                     .collect::<Result<_, _>>()?;
 
+                let text: String = row.get(2)?;
+                // Responses saved before the type was stored are sniffed on load
+                let content_type: String = row.get(5)?;
+                let content_type = if content_type.is_empty() {
+                    detect_content_type(None, &url, text.as_bytes())
+                } else {
+                    content_type
+                };
+                let grpc_message: Option<String> = row.get(10)?;
+
                 Ok(Response {
                     id: resp_id,
                     parent_endpoint_id: row.get(1)?,
-                    text: row.get(2)?,
+                    text,
+                    content_type,
+                    // Only the shown response reads its body, see load_response_body
+                    body: Vec::new(),
+                    image: None,
+                    file_path: row.get(6)?,
+                    file_size: row.get(7)?,
+                    // Read with the body, see load_response_body
+                    frames: vec![],
+                    grpc_status: row.get::<_, Option<i32>>(9)?.map(|code| GrpcStatus {
+                        code,
+                        message: grpc_message.unwrap_or_default(),
+                    }),
+                    headers: vec![],
+                    tests: tests_from_json(&row.get::<_, String>(11)?),
+                    variables: resolved_from_json(&row.get::<_, String>(12)?),
+                    attempts: attempts_from_json(&row.get::<_, String>(13)?),
+                    redirects: hops_from_json(&row.get::<_, String>(14)?),
+                    connection: ConnectionInfo::from_json(&row.get::<_, String>(15)?),
                     code: StatusCode::from_u16(row.get(3).unwrap()).unwrap(),
                     received_time: row.get(4)?,
                     request: Request {
                         query_params,
                         headers,
+                        body: row.get(8)?,
                     },
                 })
             })?
             .collect::<Result<_, _>>()?;
 
+        let mut run_stmt = conn.prepare(
+            "SELECT id, settings, report, started_time
+             FROM load_run
+             WHERE parent_endpoint_id = ?
+             ORDER BY id DESC",
+        )?;
+        let load_runs: Vec<LoadRun> = run_stmt
+            .query_map([id], |row| {
+                Ok(LoadRun {
+                    id: row.get(0)?,
+                    settings: LoadSettings::from_json(&row.get::<_, String>(1)?),
+                    report: LoadReport::from_json(&row.get::<_, String>(2)?),
+                    started_time: row.get(3)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
         let method_enum = method
             .as_str()
             .parse()
@@ -160,6 +333,15 @@ pub fn load_endpoints(
             url,
             responses,
             method: method_enum,
+            response_filter,
+            auth: Auth::from_json(&auth),
+            pre_script,
+            post_script,
+            load_runs,
+            retry: RetryPolicy::from_json(&retry),
+            redirect: RedirectPolicy::from_json(&redirect),
+            proxy: ProxySettings::from_json(&proxy, ProxySettings::default()),
+            proto_paths: proto_paths.lines().map(str::to_string).collect(),
         });
     }
 
@@ -0,0 +1,131 @@
+use jaq_core::load::{self, Arena, File, Loader};
+use jaq_core::{Compiler, Ctx, Native, RcIter};
+use jaq_json::Val;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+// Expressions starting with `$` are treated as JSONPath, everything else as jq.
+pub fn filter_response(body: &str, expression: &str) -> Result<String, String> {
+    let value: Value =
+        serde_json::from_str(body).map_err(|it| format!("Response is not valid JSON: {}", it))?;
+    let expression = expression.trim();
+    if expression.starts_with('$') {
+        filter_json_path(&value, expression)
+    } else {
+        filter_jq(value, expression)
+    }
+}
+
+fn filter_json_path(value: &Value, expression: &str) -> Result<String, String> {
+    let path = JsonPath::parse(expression).map_err(|it| it.to_string())?;
+    let nodes: Vec<&Value> = path.query(value).all();
+    serde_json::to_string_pretty(&nodes).map_err(|it| it.to_string())
+}
+
+fn filter_jq(value: Value, expression: &str) -> Result<String, String> {
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let arena = Arena::default();
+    let modules = loader
+        .load(
+            &arena,
+            File {
+                code: expression,
+                path: (),
+            },
+        )
+        .map_err(|errors| match errors.into_iter().next() {
+            Some((_, load::Error::Lex(errs))) => match errs.first() {
+                Some((expect, found)) => expected(expect.as_str(), found),
+                None => "Invalid jq expression".to_string(),
+            },
+            Some((_, load::Error::Parse(errs))) => match errs.first() {
+                Some((expect, found)) => expected(expect.as_str(), found),
+                None => "Invalid jq expression".to_string(),
+            },
+            _ => "Invalid jq expression".to_string(),
+        })?;
+    let filter = Compiler::<_, Native<Val>>::default()
+        .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+        .compile(modules)
+        .map_err(|errors| {
+            match errors
+                .into_iter()
+                .next()
+                .and_then(|(_, errs)| errs.into_iter().next())
+            {
+                Some((name, undefined)) => format!("Undefined {} `{}`", undefined.as_str(), name),
+                None => "Invalid jq expression".to_string(),
+            }
+        })?;
+
+    let inputs = RcIter::new(core::iter::empty());
+    let mut outputs = Vec::new();
+    for output in filter.run((Ctx::new([], &inputs), Val::from(value))) {
+        let output = output.map_err(|it| it.to_string())?;
+        outputs
+            .push(serde_json::to_string_pretty(&Value::from(output)).map_err(|it| it.to_string())?);
+    }
+    Ok(outputs.join("\n"))
+}
+
+fn expected(expect: &str, found: &str) -> String {
+    if found.is_empty() {
+        format!("Expected {} at the end of the expression", expect)
+    } else {
+        format!("Expected {} at `{}`", expect, found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &str =
+        r#"{"items": [{"id": 1, "tags": ["a"]}, {"id": 2, "tags": []}], "total": 2}"#;
+
+    #[test]
+    fn json_path() {
+        assert_eq!(
+            filter_response(BODY, "$.items[*].id").unwrap(),
+            "[\n  1,\n  2\n]"
+        );
+        // Leading and trailing spaces don't make it jq
+        assert_eq!(filter_response(BODY, "  $.total ").unwrap(), "[\n  2\n]");
+        assert_eq!(filter_response(BODY, "$.missing").unwrap(), "[]");
+    }
+
+    #[test]
+    fn jq() {
+        assert_eq!(filter_response(BODY, ".total").unwrap(), "2");
+        assert_eq!(filter_response(BODY, ".items | length").unwrap(), "2");
+        // Every output on its own line
+        assert_eq!(filter_response(BODY, ".items[].id").unwrap(), "1\n2");
+        assert_eq!(
+            filter_response(BODY, "[.items[] | select(.tags | length > 0) | .id]").unwrap(),
+            "[\n  1\n]"
+        );
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        assert!(filter_response(BODY, "$.items[").is_err());
+        assert!(
+            filter_response(BODY, ".items |")
+                .unwrap_err()
+                .starts_with("Expected ")
+        );
+        assert_eq!(
+            filter_response(BODY, "nope").unwrap_err(),
+            "Undefined filter `nope`"
+        );
+        // A valid expression can still fail on the data
+        assert!(filter_response(BODY, ".total | keys").is_err());
+    }
+
+    #[test]
+    fn non_json_body() {
+        let err = filter_response("<html></html>", "$.a").unwrap_err();
+        assert!(err.starts_with("Response is not valid JSON: "));
+        assert!(filter_response("", ".a").is_err());
+    }
+}
@@ -0,0 +1,731 @@
+use std::fmt;
+
+use graphql_parser::query::parse_query;
+use graphql_parser::schema::parse_schema;
+use markup_fmt::{Language, format_text};
+use minify_js::{Session, TopLevelMode, minify};
+use quick_xml::events::Event;
+use quick_xml::{Reader, Writer};
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyLanguage {
+    Json,
+    Html,
+    Xml,
+    Yaml,
+    Css,
+    JavaScript,
+    GraphQl,
+    Form,
+    Plain,
+}
+
+impl BodyLanguage {
+    pub const ALL: [BodyLanguage; 9] = [
+        BodyLanguage::Json,
+        BodyLanguage::Html,
+        BodyLanguage::Xml,
+        BodyLanguage::Yaml,
+        BodyLanguage::Css,
+        BodyLanguage::JavaScript,
+        BodyLanguage::GraphQl,
+        BodyLanguage::Form,
+        BodyLanguage::Plain,
+    ];
+
+    pub fn from_mime(mime_type: &str) -> BodyLanguage {
+        match mime_type {
+            it if it.contains("json") => BodyLanguage::Json,
+            it if it.contains("html") => BodyLanguage::Html,
+            it if it.contains("xml") => BodyLanguage::Xml,
+            it if it.contains("yaml") => BodyLanguage::Yaml,
+            it if it.contains("css") => BodyLanguage::Css,
+            it if it.contains("javascript") || it.contains("ecmascript") => {
+                BodyLanguage::JavaScript
+            }
+            it if it.contains("graphql") => BodyLanguage::GraphQl,
+            it if it.contains("x-www-form-urlencoded") => BodyLanguage::Form,
+            _ => BodyLanguage::Plain,
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            BodyLanguage::Json => "application/json",
+            BodyLanguage::Html => "text/html",
+            BodyLanguage::Xml => "application/xml",
+            BodyLanguage::Yaml => "text/yaml",
+            BodyLanguage::Css => "text/css",
+            BodyLanguage::JavaScript => "text/javascript",
+            BodyLanguage::GraphQl => "application/graphql",
+            BodyLanguage::Form => "application/x-www-form-urlencoded",
+            BodyLanguage::Plain => "text/plain",
+        }
+    }
+}
+
+impl fmt::Display for BodyLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                BodyLanguage::Json => "JSON",
+                BodyLanguage::Html => "HTML",
+                BodyLanguage::Xml => "XML",
+                BodyLanguage::Yaml => "YAML",
+                BodyLanguage::Css => "CSS",
+                BodyLanguage::JavaScript => "JavaScript",
+                BodyLanguage::GraphQl => "GraphQL",
+                BodyLanguage::Form => "Form",
+                BodyLanguage::Plain => "Plain text",
+            }
+        )
+    }
+}
+
+pub fn format_body(body: &str, language: BodyLanguage, minify: bool) -> Result<String, String> {
+    match (language, minify) {
+        (BodyLanguage::Json, _) => format_json(body, minify),
+        (BodyLanguage::Html, false) => format_html(body),
+        (BodyLanguage::Html, true) => Ok(minify_html(body)),
+        (BodyLanguage::Xml, _) => format_xml(body, minify),
+        (BodyLanguage::Yaml, _) => format_yaml(body, minify),
+        (BodyLanguage::Css, false) => pretty_braces(body, false),
+        (BodyLanguage::Css, true) => minify_css(body),
+        (BodyLanguage::JavaScript, false) => {
+            minify_javascript(body)?;
+            pretty_braces(body, true)
+        }
+        (BodyLanguage::JavaScript, true) => minify_javascript(body),
+        (BodyLanguage::GraphQl, _) => format_graphql(body, minify),
+        (BodyLanguage::Form, _) => format_form(body, minify),
+        (BodyLanguage::Plain, _) => Err("Plain text has no formatter".to_string()),
+    }
+}
+
+fn format_json(body: &str, minify: bool) -> Result<String, String> {
+    let value: Value = serde_json::from_str(body).map_err(|it| it.to_string())?;
+    if minify {
+        serde_json::to_string(&value)
+    } else {
+        serde_json::to_string_pretty(&value)
+    }
+    .map_err(|it| it.to_string())
+}
+
+fn format_html(html: &str) -> Result<String, String> {
+    format_text(html, Language::Html, &Default::default(), |s, _| {
+        Ok::<_, std::convert::Infallible>(s.into())
+    })
+    .map_err(|it| it.to_string())
+}
+
+// Elements that start a new line anyway, whitespace next to their tags never shows
+const BLOCK_TAGS: &str = "address article aside blockquote body br dd div dl dt fieldset \
+    figcaption figure footer form h1 h2 h3 h4 h5 h6 head header hr html li link main meta nav \
+    ol p pre script section style table tbody td tfoot th thead title tr ul";
+
+// A tag whose name is not a plain word, like a doctype, counts as a block
+fn is_block_tag(tag: &str) -> bool {
+    let name: String = tag
+        .trim_start_matches(['<', '/'])
+        .chars()
+        .take_while(|it| it.is_ascii_alphanumeric())
+        .collect();
+    name.is_empty()
+        || BLOCK_TAGS
+            .split_whitespace()
+            .any(|it| it.eq_ignore_ascii_case(&name))
+}
+
+// Collapses whitespace between tags, leaving preformatted content untouched. A single space
+// stays between inline elements, `<b>a</b> <i>b</i>` would read "ab" without it.
+fn minify_html(html: &str) -> String {
+    const RAW_TAGS: [&str; 4] = ["pre", "textarea", "script", "style"];
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut pending_space = false;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+        if c.is_whitespace() {
+            pending_space = true;
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let between_tags = out.ends_with('>') && c == '<';
+        if pending_space
+            && !out.is_empty()
+            && !(between_tags
+                && (is_block_tag(rest) || is_block_tag(&out[out.rfind('<').unwrap_or(0)..])))
+        {
+            out.push(' ');
+        }
+        pending_space = false;
+        let raw_tag = RAW_TAGS.iter().find(|tag| {
+            c == '<'
+                && rest.len() > tag.len() + 1
+                && rest.as_bytes()[1..tag.len() + 1].eq_ignore_ascii_case(tag.as_bytes())
+                && !rest.as_bytes()[tag.len() + 1].is_ascii_alphanumeric()
+        });
+        match raw_tag {
+            Some(tag) => {
+                let closing = format!("</{}", tag);
+                let end = rest
+                    .to_ascii_lowercase()
+                    .find(&closing)
+                    .unwrap_or(rest.len());
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+                if !rest.is_empty() {
+                    out.push_str(&rest[..closing.len()]);
+                    rest = &rest[closing.len()..];
+                }
+            }
+            None => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    out
+}
+
+fn format_xml(xml: &str, minify: bool) -> Result<String, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut writer = if minify {
+        Writer::new(Vec::new())
+    } else {
+        Writer::new_with_indent(Vec::new(), b' ', 2)
+    };
+    let mut depth = 0;
+    loop {
+        let event = reader.read_event().map_err(|it| {
+            format!(
+                "{} at line {}",
+                it,
+                line_at(xml, reader.error_position() as usize)
+            )
+        })?;
+        match &event {
+            Event::Eof => break,
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+        writer.write_event(event).map_err(|it| it.to_string())?;
+    }
+    if depth > 0 {
+        return Err("Unexpected end of document, some elements are not closed".to_string());
+    }
+    String::from_utf8(writer.into_inner()).map_err(|it| it.to_string())
+}
+
+fn format_yaml(yaml: &str, minify: bool) -> Result<String, String> {
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(yaml) {
+        let value = serde_yaml::Value::deserialize(document).map_err(|it| it.to_string())?;
+        documents.push(if minify {
+            serde_json::to_string(&value).map_err(|it| it.to_string())?
+        } else {
+            serde_yaml::to_string(&value).map_err(|it| it.to_string())?
+        });
+    }
+    Ok(documents.join(if minify { "\n---\n" } else { "---\n" }))
+}
+
+fn minify_javascript(source: &str) -> Result<String, String> {
+    let session = Session::new();
+    let mut output = Vec::new();
+    minify(
+        &session,
+        TopLevelMode::Global,
+        source.as_bytes(),
+        &mut output,
+    )
+    .map_err(|it| {
+        format!(
+            "{:?} at line {}",
+            it.typ,
+            line_at(source, it.source.start())
+        )
+    })?;
+    String::from_utf8(output).map_err(|it| it.to_string())
+}
+
+fn format_graphql(source: &str, minify: bool) -> Result<String, String> {
+    let pretty = match parse_query::<&str>(source) {
+        Ok(document) => document.to_string(),
+        Err(query_error) => match parse_schema::<&str>(source) {
+            Ok(document) => document.to_string(),
+            Err(_) => return Err(query_error.to_string()),
+        },
+    };
+    Ok(if minify {
+        minify_graphql(source)
+    } else {
+        pretty
+    })
+}
+
+// Commas and whitespace are insignificant in GraphQL, a separator is only
+// needed between two names.
+fn minify_graphql(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut rest = source;
+    let mut pending_space = false;
+    while let Some(c) = rest.chars().next() {
+        if c == '#' {
+            rest = rest.find('\n').map_or("", |end| &rest[end..]);
+            continue;
+        }
+        if c.is_whitespace() || c == ',' {
+            pending_space = true;
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if pending_space
+            && out.ends_with(|it: char| it.is_alphanumeric() || it == '_')
+            && (c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            out.push(' ');
+        }
+        pending_space = false;
+        let len = if let Some(block) = rest.strip_prefix("\"\"\"") {
+            block.find("\"\"\"").map_or(rest.len(), |end| end + 6)
+        } else if c == '"' {
+            quoted_len(rest)
+        } else {
+            c.len_utf8()
+        };
+        out.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+    out
+}
+
+fn format_form(body: &str, minify: bool) -> Result<String, String> {
+    let mut pairs = Vec::new();
+    for pair in body.split(['&', '\n']).filter(|it| !it.trim().is_empty()) {
+        let (key, value) = pair.trim().split_once('=').unwrap_or((pair.trim(), ""));
+        let (key, value) = (decode_form(key)?, decode_form(value)?);
+        pairs.push(if minify {
+            format!(
+                "{}={}",
+                urlencoding::encode(&key),
+                urlencoding::encode(&value)
+            )
+        } else {
+            format!("{}={}", key, value)
+        });
+    }
+    Ok(pairs.join(if minify { "&" } else { "\n" }))
+}
+
+fn decode_form(s: &str) -> Result<String, String> {
+    urlencoding::decode(&s.replace('+', " "))
+        .map(|it| it.into_owned())
+        .map_err(|_| format!("`{}` is not valid percent-encoded UTF-8", s))
+}
+
+fn minify_css(css: &str) -> Result<String, String> {
+    const TIGHT: &str = "{};,>";
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    let mut pending_space = false;
+    let mut depth = 0;
+    // The last character was the colon of a declaration rather than of a selector like `a:hover`
+    let mut after_colon = false;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("/*") {
+            let end = rest[2..]
+                .find("*/")
+                .ok_or_else(|| format!("Unterminated comment at line {}", line_of(css, rest)))?;
+            rest = &rest[end + 4..];
+            pending_space = true;
+            continue;
+        }
+        if c.is_whitespace() {
+            pending_space = true;
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        // Inside a block, a colon before the next `{` belongs to a nested selector
+        let colon = c == ':'
+            && depth > 0
+            && !rest[1..]
+                .trim_start_matches(|it| !"{;}".contains(it))
+                .starts_with('{');
+        if pending_space
+            && !out.is_empty()
+            && !out.ends_with(|it| TIGHT.contains(it))
+            && !TIGHT.contains(c)
+            && !after_colon
+            && !colon
+        {
+            out.push(' ');
+        }
+        pending_space = false;
+        after_colon = colon;
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth < 0 {
+                    return Err(format!("Unexpected `}}` at line {}", line_of(css, rest)));
+                }
+                if out.ends_with(';') {
+                    out.pop();
+                }
+            }
+            _ => {}
+        }
+        let len = if c == '"' || c == '\'' {
+            string_len(css, rest)?
+        } else {
+            c.len_utf8()
+        };
+        out.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+    if depth > 0 {
+        return Err("Unexpected end of stylesheet, missing `}`".to_string());
+    }
+    Ok(out)
+}
+
+// Re-indents brace-delimited sources (CSS, JavaScript) by breaking lines
+// after `{`, `;` and `}`. Existing line breaks are kept, so automatic
+// semicolon insertion in JavaScript is never affected.
+fn pretty_braces(source: &str, is_js: bool) -> Result<String, String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    let mut line_depth = 0;
+    let mut depth: i32 = 0;
+    let mut parens: i32 = 0;
+    let mut rest = source;
+
+    let mut source_line_empty = false;
+
+    fn end_line(lines: &mut Vec<String>, line: &mut String, line_depth: i32) {
+        let trimmed = line.trim();
+        if !trimmed.is_empty() {
+            lines.push(format!("{}{}", "  ".repeat(line_depth as usize), trimmed));
+        }
+        line.clear();
+    }
+
+    while let Some(c) = rest.chars().next() {
+        if line.trim().is_empty() && !c.is_whitespace() {
+            line_depth = depth;
+        }
+        if !c.is_whitespace() {
+            source_line_empty = false;
+        }
+        let len = match c {
+            '\n' => {
+                // Keeps a single empty line wherever the source had one.
+                if source_line_empty && lines.last().is_some_and(|it| !it.is_empty()) {
+                    lines.push(String::new());
+                }
+                end_line(&mut lines, &mut line, line_depth);
+                source_line_empty = true;
+                rest = &rest[1..];
+                continue;
+            }
+            it if it.is_whitespace() => {
+                if !line.ends_with(' ') {
+                    line.push(' ');
+                }
+                rest = &rest[it.len_utf8()..];
+                continue;
+            }
+            '"' | '\'' => string_len(source, rest)?,
+            '`' if is_js => string_len(source, rest)?,
+            '/' if rest.starts_with("/*") => rest[2..]
+                .find("*/")
+                .map(|end| end + 4)
+                .ok_or_else(|| format!("Unterminated comment at line {}", line_of(source, rest)))?,
+            '/' if is_js && rest.starts_with("//") => rest.find('\n').unwrap_or(rest.len()),
+            '/' if is_js && starts_regex(&line, &lines) => regex_len(rest),
+            '(' | '[' => {
+                parens += 1;
+                1
+            }
+            ')' | ']' => {
+                parens -= 1;
+                if parens < 0 {
+                    return Err(format!(
+                        "Unexpected `{}` at line {}",
+                        c,
+                        line_of(source, rest)
+                    ));
+                }
+                1
+            }
+            '{' => {
+                if !line.trim().is_empty() && !line.ends_with([' ', '(', '[']) {
+                    line.push(' ');
+                }
+                line.push('{');
+                end_line(&mut lines, &mut line, line_depth);
+                depth += 1;
+                rest = &rest[1..];
+                continue;
+            }
+            '}' => {
+                end_line(&mut lines, &mut line, line_depth);
+                depth -= 1;
+                if depth < 0 {
+                    return Err(format!("Unexpected `}}` at line {}", line_of(source, rest)));
+                }
+                line_depth = depth;
+                line.push('}');
+                rest = &rest[1..];
+                let next = rest.trim_start_matches([' ', '\t']);
+                let continues = next.starts_with([';', ',', ')', ']', '.'])
+                    || ["else", "catch", "finally", "while"]
+                        .iter()
+                        .any(|it| next.starts_with(it));
+                if !continues {
+                    end_line(&mut lines, &mut line, line_depth);
+                } else if !next.starts_with([';', ',', ')', ']', '.']) {
+                    line.push(' ');
+                }
+                rest = if continues { next } else { rest };
+                continue;
+            }
+            ';' if parens == 0 => {
+                line.push(';');
+                end_line(&mut lines, &mut line, line_depth);
+                rest = &rest[1..];
+                continue;
+            }
+            it => it.len_utf8(),
+        };
+        line.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+    end_line(&mut lines, &mut line, line_depth);
+    if depth > 0 {
+        return Err("Unexpected end of input, missing `}`".to_string());
+    }
+    while lines.last().is_some_and(|it| it.is_empty()) {
+        lines.pop();
+    }
+    Ok(lines.join("\n"))
+}
+
+// A slash starts a regular expression literal when it can't be a division,
+// that is when it doesn't follow a value.
+fn starts_regex(line: &str, lines: &[String]) -> bool {
+    let previous = match line.trim_end() {
+        "" => lines
+            .iter()
+            .rev()
+            .find(|it| !it.is_empty())
+            .map_or("", |it| it.trim_end()),
+        it => it,
+    };
+    match previous.chars().last() {
+        None => true,
+        Some(c) if "(,=:[!&|?{};+-*%<>~^".contains(c) => true,
+        Some(_) => ["return", "typeof", "case", "do", "else", "in", "of"]
+            .iter()
+            .any(|it| {
+                previous.ends_with(it)
+                    && !previous[..previous.len() - it.len()]
+                        .ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '$')
+            }),
+    }
+}
+
+fn regex_len(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let mut index = 1;
+    let mut in_class = false;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 1,
+            b'[' => in_class = true,
+            b']' => in_class = false,
+            b'/' if !in_class => break,
+            b'\n' => return index,
+            _ => {}
+        }
+        index += 1;
+    }
+    index = (index + 1).min(bytes.len());
+    index
+        + rest[index..]
+            .bytes()
+            .take_while(|it| it.is_ascii_alphabetic())
+            .count()
+}
+
+fn string_len(source: &str, rest: &str) -> Result<usize, String> {
+    let len = quoted_len(rest);
+    let quote = rest.as_bytes()[0];
+    if len < 2 || rest.as_bytes()[len - 1] != quote {
+        return Err(format!(
+            "Unterminated string at line {}",
+            line_of(source, rest)
+        ));
+    }
+    Ok(len)
+}
+
+fn quoted_len(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let quote = bytes[0];
+    let mut index = 1;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 2,
+            it if it == quote => return index + 1,
+            _ => index += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn line_of(source: &str, rest: &str) -> usize {
+    line_at(source, source.len() - rest.len())
+}
+
+fn line_at(source: &str, position: usize) -> usize {
+    source.as_bytes()[..position.min(source.len())]
+        .iter()
+        .filter(|it| **it == b'\n')
+        .count()
+        + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pretty(body: &str, language: BodyLanguage) -> String {
+        format_body(body, language, false).unwrap()
+    }
+
+    fn minified(body: &str, language: BodyLanguage) -> String {
+        format_body(body, language, true).unwrap()
+    }
+
+    #[test]
+    fn json() {
+        let body = r#"{ "a": [1, 2], "b": {"c": null} }"#;
+        assert_eq!(
+            pretty(body, BodyLanguage::Json),
+            "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {\n    \"c\": null\n  }\n}"
+        );
+        assert_eq!(
+            minified(body, BodyLanguage::Json),
+            r#"{"a":[1,2],"b":{"c":null}}"#
+        );
+        assert!(format_body("{\"a\":", BodyLanguage::Json, false).is_err());
+    }
+
+    #[test]
+    fn xml() {
+        let body = "<a>\n  <b x=\"1\">text</b>\n  <c/>\n</a>";
+        assert_eq!(
+            minified(body, BodyLanguage::Xml),
+            "<a><b x=\"1\">text</b><c/></a>"
+        );
+        assert_eq!(
+            pretty("<a><b>text</b><c/></a>", BodyLanguage::Xml),
+            "<a>\n  <b>text</b>\n  <c/>\n</a>"
+        );
+        assert_eq!(
+            format_body("<a><b></b>", BodyLanguage::Xml, false),
+            Err("Unexpected end of document, some elements are not closed".to_string())
+        );
+    }
+
+    #[test]
+    fn html_minify_keeps_preformatted_content() {
+        let body = "<div>\n  <p>a   b</p>\n  <!-- note -->\n  <pre>  x\n  y</pre>\n</div>";
+        assert_eq!(
+            minified(body, BodyLanguage::Html),
+            "<div><p>a b</p><pre>  x\n  y</pre></div>"
+        );
+        assert_eq!(
+            minified(
+                "<ul>\n  <li><b>a</b> <i>b</i>\n    <span>c</span></li>\n</ul>",
+                BodyLanguage::Html
+            ),
+            "<ul><li><b>a</b> <i>b</i> <span>c</span></li></ul>"
+        );
+    }
+
+    #[test]
+    fn css() {
+        let body = "a > b {\n  color: red;\n  /* note */\n  margin: 0 auto;\n}\n";
+        assert_eq!(
+            minified(body, BodyLanguage::Css),
+            "a>b{color:red;margin:0 auto}"
+        );
+        // Colons of selectors keep their spaces, they change what is selected
+        assert_eq!(
+            minified(
+                "a :hover { color : red }\n@media (x) {\n  b :first-child { top: 0 }\n}",
+                BodyLanguage::Css
+            ),
+            "a :hover{color:red}@media (x){b :first-child{top:0}}"
+        );
+        assert_eq!(
+            format_body("a { color: red;", BodyLanguage::Css, true),
+            Err("Unexpected end of stylesheet, missing `}`".to_string())
+        );
+        assert_eq!(
+            format_body("a {}\n}", BodyLanguage::Css, true),
+            Err("Unexpected `}` at line 2".to_string())
+        );
+    }
+
+    #[test]
+    fn graphql_minify() {
+        let body =
+            "query Q($id: ID!) {\n  # comment\n  user(id: $id) {\n    name,\n    email\n  }\n}";
+        assert_eq!(
+            minified(body, BodyLanguage::GraphQl),
+            "query Q($id:ID!){user(id:$id){name email}}"
+        );
+        assert!(format_body("query {", BodyLanguage::GraphQl, false).is_err());
+    }
+
+    #[test]
+    fn form() {
+        assert_eq!(
+            pretty("a=1&name=Ann+Lee&q=%C3%A9", BodyLanguage::Form),
+            "a=1\nname=Ann Lee\nq=\u{e9}"
+        );
+        assert_eq!(
+            minified("a=1\nname=Ann Lee\nflag", BodyLanguage::Form),
+            "a=1&name=Ann%20Lee&flag="
+        );
+        assert!(format_body("a=%FF", BodyLanguage::Form, false).is_err());
+    }
+
+    #[test]
+    fn yaml() {
+        assert_eq!(
+            minified("a: 1\nb: [x, y]\n---\nc: true\n", BodyLanguage::Yaml),
+            "{\"a\":1,\"b\":[\"x\",\"y\"]}\n---\n{\"c\":true}"
+        );
+        assert_eq!(pretty("{a: 1}", BodyLanguage::Yaml), "a: 1\n");
+    }
+
+    #[test]
+    fn plain_text_has_no_formatter() {
+        assert!(format_body("text", BodyLanguage::Plain, false).is_err());
+    }
+}
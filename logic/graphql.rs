@@ -0,0 +1,349 @@
+use std::collections::{HashMap, HashSet};
+
+use graphql_parser::query::{
+    Definition, OperationDefinition, Selection, SelectionSet, TypeCondition, parse_query,
+};
+use serde_json::{Map, Value};
+
+pub const INTROSPECTION_QUERY: &str = "query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types {
+      kind
+      name
+      fields(includeDeprecated: true) {
+        name
+        type { ...TypeRef }
+      }
+    }
+  }
+}
+
+fragment TypeRef on __Type {
+  name
+  ofType { name ofType { name ofType { name ofType { name } } } }
+}";
+
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    pub query: String,
+    pub mutation: Option<String>,
+    pub subscription: Option<String>,
+    pub types: HashMap<String, Vec<Field>>,
+    // Scalars and enums, which can't have a selection set
+    pub leaves: HashSet<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    pub type_name: String,
+}
+
+impl Schema {
+    fn field(&self, type_name: &str, field: &str) -> Option<&Field> {
+        self.types
+            .get(type_name)?
+            .iter()
+            .find(|it| it.name == field)
+    }
+}
+
+pub fn parse_schema(body: &str) -> Result<Schema, String> {
+    let value: Value = serde_json::from_str(body)
+        .map_err(|it| format!("Introspection response is not valid JSON: {}", it))?;
+    let Some(schema) = value.pointer("/data/__schema") else {
+        return Err(response_errors(body)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| "Introspection response has no schema".to_string()));
+    };
+    let root = |kind: &str| {
+        schema
+            .pointer(&format!("/{}/name", kind))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    let types = schema["types"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let leaves = types
+        .iter()
+        .filter(|it| matches!(it["kind"].as_str(), Some("SCALAR" | "ENUM")))
+        .filter_map(|it| Some(it["name"].as_str()?.to_string()))
+        .collect();
+    let types = types
+        .iter()
+        .filter_map(|it| {
+            let fields = it["fields"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|field| {
+                    Some(Field {
+                        name: field["name"].as_str()?.to_string(),
+                        type_name: named_type(&field["type"])?,
+                    })
+                })
+                .collect();
+            Some((it["name"].as_str()?.to_string(), fields))
+        })
+        .collect();
+    Ok(Schema {
+        query: root("queryType").unwrap_or_else(|| "Query".to_string()),
+        mutation: root("mutationType"),
+        subscription: root("subscriptionType"),
+        types,
+        leaves,
+    })
+}
+
+// Lists and non-null wrappers have no name, the named type sits in ofType
+fn named_type(value: &Value) -> Option<String> {
+    match value["name"].as_str() {
+        Some(name) => Some(name.to_string()),
+        None => named_type(value.get("ofType")?),
+    }
+}
+
+pub fn request_body(
+    query: &str,
+    variables: &str,
+    operation: Option<&str>,
+) -> Result<String, String> {
+    let mut body = Map::new();
+    body.insert("query".to_string(), Value::from(query.trim()));
+    if !variables.trim().is_empty() {
+        let variables: Value = serde_json::from_str(variables)
+            .map_err(|it| format!("Variables are not valid JSON: {}", it))?;
+        body.insert("variables".to_string(), variables);
+    }
+    if let Some(operation) = operation {
+        body.insert("operationName".to_string(), Value::from(operation));
+    }
+    serde_json::to_string_pretty(&body).map_err(|it| it.to_string())
+}
+
+// Reverse of request_body, used to load a saved request back into the editors
+pub fn parse_request_body(body: &str) -> (String, String, Option<String>) {
+    let value: Value = serde_json::from_str(body).unwrap_or_default();
+    let query = value["query"].as_str().unwrap_or_default().to_string();
+    let variables = match value.get("variables") {
+        Some(variables) if !variables.is_null() => {
+            serde_json::to_string_pretty(variables).unwrap_or_default()
+        }
+        _ => String::new(),
+    };
+    let operation = value["operationName"].as_str().map(str::to_string);
+    (query, variables, operation)
+}
+
+pub fn operation_names(query: &str) -> Vec<String> {
+    let Ok(document) = parse_query::<String>(query) else {
+        return vec![];
+    };
+    document
+        .definitions
+        .into_iter()
+        .filter_map(|it| match it {
+            Definition::Operation(OperationDefinition::Query(it)) => it.name,
+            Definition::Operation(OperationDefinition::Mutation(it)) => it.name,
+            Definition::Operation(OperationDefinition::Subscription(it)) => it.name,
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn validate(schema: &Schema, query: &str) -> Vec<String> {
+    if query.trim().is_empty() {
+        return vec![];
+    }
+    let document = match parse_query::<String>(query) {
+        Ok(document) => document,
+        Err(err) => return vec![err.to_string().trim().to_string()],
+    };
+    let mut errors = Vec::new();
+    for definition in &document.definitions {
+        let (root, selection_set) = match definition {
+            Definition::Operation(OperationDefinition::SelectionSet(set)) => {
+                (Some(&schema.query), set)
+            }
+            Definition::Operation(OperationDefinition::Query(it)) => {
+                (Some(&schema.query), &it.selection_set)
+            }
+            Definition::Operation(OperationDefinition::Mutation(it)) => {
+                (schema.mutation.as_ref(), &it.selection_set)
+            }
+            Definition::Operation(OperationDefinition::Subscription(it)) => {
+                (schema.subscription.as_ref(), &it.selection_set)
+            }
+            Definition::Fragment(it) => {
+                let TypeCondition::On(name) = &it.type_condition;
+                (Some(name), &it.selection_set)
+            }
+        };
+        match root {
+            Some(root) => validate_selection(schema, root, selection_set, &mut errors),
+            None => errors.push("The schema does not support this operation type".to_string()),
+        }
+    }
+    errors
+}
+
+fn validate_selection(
+    schema: &Schema,
+    type_name: &str,
+    selection_set: &SelectionSet<String>,
+    errors: &mut Vec<String>,
+) {
+    for selection in &selection_set.items {
+        match selection {
+            Selection::Field(field) if field.name.starts_with("__") => {}
+            Selection::Field(field) => match schema.field(type_name, &field.name) {
+                Some(found) => {
+                    if !field.selection_set.items.is_empty()
+                        && schema.leaves.contains(&found.type_name)
+                    {
+                        errors.push(format!(
+                            "Line {}: `{}` of type `{}` has no fields to select",
+                            field.position.line, field.name, found.type_name
+                        ));
+                    }
+                    validate_selection(schema, &found.type_name, &field.selection_set, errors);
+                }
+                // Unions have no fields of their own, their members are selected with fragments
+                None if schema.types.get(type_name).is_none_or(Vec::is_empty) => {}
+                None => errors.push(format!(
+                    "Line {}: unknown field `{}` on type `{}`",
+                    field.position.line, field.name, type_name
+                )),
+            },
+            Selection::InlineFragment(fragment) => {
+                let type_name = match &fragment.type_condition {
+                    Some(TypeCondition::On(name)) => name,
+                    None => type_name,
+                };
+                validate_selection(schema, type_name, &fragment.selection_set, errors);
+            }
+            Selection::FragmentSpread(_) => {}
+        }
+    }
+}
+
+// Walks the query up to the cursor to find the type whose selection set the cursor is in.
+// Returns the partially typed field name and the fields that complete it.
+pub fn completions(schema: &Schema, before_cursor: &str) -> Option<(String, Vec<String>)> {
+    let mut stack: Vec<Option<String>> = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    let mut parens = 0;
+    let mut chars = before_cursor.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '#' => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                while let Some(next) = chars.next() {
+                    match next {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '(' => parens += 1,
+            ')' => parens -= 1,
+            '{' if parens == 0 => {
+                let type_name = match stack.last() {
+                    None => match names.first().map(String::as_str) {
+                        Some("mutation") => schema.mutation.clone(),
+                        Some("subscription") => schema.subscription.clone(),
+                        Some("fragment") => names.last().cloned(),
+                        _ => Some(schema.query.clone()),
+                    },
+                    Some(current) => match names.as_slice() {
+                        [.., on, type_name] if on == "on" => Some(type_name.clone()),
+                        [.., field] => current
+                            .as_deref()
+                            .and_then(|it| schema.field(it, field))
+                            .map(|it| it.type_name.clone()),
+                        [] => None,
+                    },
+                };
+                stack.push(type_name);
+                names.clear();
+            }
+            '}' if parens == 0 => {
+                stack.pop();
+                names.clear();
+            }
+            it if (it.is_alphanumeric() || it == '_') && parens == 0 => {
+                let mut name = it.to_string();
+                while let Some(next) = chars.next_if(|it| it.is_alphanumeric() || *it == '_') {
+                    name.push(next);
+                }
+                names.push(name);
+            }
+            _ => {}
+        }
+    }
+    if parens > 0 {
+        return None;
+    }
+    let type_name = stack.last()?.as_deref()?;
+    let prefix: String = before_cursor
+        .chars()
+        .rev()
+        .take_while(|it| it.is_alphanumeric() || *it == '_')
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    let fields = schema
+        .types
+        .get(type_name)?
+        .iter()
+        .filter(|it| it.name.starts_with(&prefix) && it.name != prefix)
+        .map(|it| it.name.clone())
+        .take(12)
+        .collect();
+    Some((prefix, fields))
+}
+
+pub fn response_errors(body: &str) -> Vec<String> {
+    let value: Value = serde_json::from_str(body).unwrap_or_default();
+    value["errors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|error| {
+            let message = error["message"].as_str().unwrap_or("Unknown error");
+            let path = error["path"]
+                .as_array()
+                .map(|it| {
+                    it.iter()
+                        .map(|part| match part {
+                            Value::String(it) => it.clone(),
+                            it => it.to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(".")
+                })
+                .unwrap_or_default();
+            if path.is_empty() {
+                message.to_string()
+            } else {
+                format!("{}: {}", path, message)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,344 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use iced::futures::channel::mpsc;
+use prost::Message;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, MessageDescriptor, MethodDescriptor, SerializeOptions,
+};
+use prost_types::FileDescriptorProto;
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::MetadataMap;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Status};
+use tonic_reflection::pb::v1::{
+    ServerReflectionRequest, server_reflection_client::ServerReflectionClient,
+    server_reflection_request::MessageRequest, server_reflection_response::MessageResponse,
+};
+
+use crate::logic::common::MyErr;
+use crate::logic::tls::{TlsSettings, certificate_error};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrpcStatus {
+    pub code: i32,
+    pub message: String,
+}
+
+impl GrpcStatus {
+    pub fn is_ok(&self) -> bool {
+        self.code == 0
+    }
+
+    pub fn name(&self) -> String {
+        format!("{:?}", Code::from_i32(self.code))
+    }
+}
+
+// The method is part of the URL like on the wire:
+// "http://localhost:50051/pkg.Service/Method" -> ("http://localhost:50051", "pkg.Service/Method")
+pub fn split_url(url: &str) -> (String, String) {
+    let url = url.trim();
+    let start = url.find("://").map(|it| it + 3).unwrap_or(0);
+    match url[start..].find('/') {
+        Some(slash) => (
+            url[..start + slash].to_string(),
+            url[start + slash + 1..].trim_matches('/').to_string(),
+        ),
+        None => (url.to_string(), String::new()),
+    }
+}
+
+pub fn method_names(pool: &DescriptorPool) -> Vec<String> {
+    pool.services()
+        .filter(|it| !it.full_name().starts_with("grpc.reflection."))
+        .flat_map(|it| it.methods().collect::<Vec<_>>())
+        .map(|it| format!("{}/{}", it.parent_service().full_name(), it.name()))
+        .collect()
+}
+
+pub fn find_method(pool: &DescriptorPool, path: &str) -> Option<MethodDescriptor> {
+    let (service, method) = path.split_once('/')?;
+    pool.get_service_by_name(service)?
+        .methods()
+        .find(|it| it.name() == method)
+}
+
+// Every field of the input message with its default value, as a starting point for editing
+pub fn template(method: &MethodDescriptor) -> String {
+    let message = DynamicMessage::new(method.input());
+    serde_json::to_string_pretty(&to_json(&message)).unwrap_or_default()
+}
+
+fn to_json(message: &DynamicMessage) -> Value {
+    message
+        .serialize_with_options(
+            serde_json::value::Serializer,
+            &SerializeOptions::new().skip_default_fields(false),
+        )
+        .unwrap_or_default()
+}
+
+pub fn load_protos(paths: &[PathBuf]) -> Result<DescriptorPool, String> {
+    let mut includes: Vec<&Path> = paths.iter().filter_map(|it| it.parent()).collect();
+    includes.dedup();
+    let mut compiler = protox::Compiler::new(includes).map_err(|it| it.to_string())?;
+    compiler
+        .include_imports(true)
+        .open_files(paths)
+        .map_err(|it| it.to_string())?;
+    Ok(compiler.descriptor_pool())
+}
+
+async fn connect(origin: &str, tls: &TlsSettings) -> Result<Channel, MyErr> {
+    let origin = if origin.contains("://") {
+        origin.to_string()
+    } else {
+        format!("http://{}", origin)
+    };
+    let mut endpoint = Endpoint::from_shared(origin.clone())
+        .map_err(|it| MyErr::Client(format!("Invalid address {}: {}", origin, it)))?;
+    if origin.starts_with("https://") {
+        let host = endpoint.uri().host().unwrap_or_default().to_string();
+        endpoint = endpoint
+            .tls_config(tls.tonic_config(&host).map_err(MyErr::Certificate)?)
+            .map_err(|it| MyErr::Certificate(describe(&it)))?;
+    }
+    endpoint
+        .connect()
+        .await
+        .map_err(|err| match certificate_error(&err) {
+            Some(reason) if tls.accept_invalid => MyErr::Certificate(format!(
+                "{}. gRPC calls always check certificates, add the server's CA file instead",
+                reason
+            )),
+            Some(reason) => MyErr::Certificate(reason),
+            None => MyErr::Client(format!(
+                "Could not connect to {}: {}",
+                origin,
+                describe(&err)
+            )),
+        })
+}
+
+// Transport errors only say "transport error" at the top, the cause is further down
+fn describe(err: &dyn Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(it) = source {
+        message.push_str(": ");
+        message.push_str(&it.to_string());
+        source = it.source();
+    }
+    message
+}
+
+// Asks the server for its services, then for the files defining them and their imports
+pub async fn reflect(origin: &str, tls: &TlsSettings) -> Result<DescriptorPool, MyErr> {
+    let mut client = ServerReflectionClient::new(connect(origin, tls).await?);
+    reflect_with(&mut client).await.map_err(MyErr::Client)
+}
+
+async fn reflect_with(
+    client: &mut ServerReflectionClient<Channel>,
+) -> Result<DescriptorPool, String> {
+    let (sender, receiver) = mpsc::unbounded();
+    let send = |message| {
+        let _ = sender.unbounded_send(ServerReflectionRequest {
+            host: String::new(),
+            message_request: Some(message),
+        });
+    };
+    send(MessageRequest::ListServices(String::new()));
+    let mut responses = client
+        .server_reflection_info(receiver)
+        .await
+        .map_err(|it| format!("Server reflection is not available: {}", it.message()))?
+        .into_inner();
+
+    // Well-known types are bundled, so they don't have to be requested
+    let mut pool = DescriptorPool::global();
+    let mut files: HashMap<String, FileDescriptorProto> = HashMap::new();
+    let mut requested = HashSet::new();
+    let mut pending = 1;
+    while pending > 0 {
+        let Some(response) = responses
+            .message()
+            .await
+            .map_err(|it| it.message().to_string())?
+        else {
+            break;
+        };
+        pending -= 1;
+        match response.message_response {
+            Some(MessageResponse::ListServicesResponse(list)) => {
+                for service in list.service {
+                    if !service.name.starts_with("grpc.reflection.") {
+                        send(MessageRequest::FileContainingSymbol(service.name));
+                        pending += 1;
+                    }
+                }
+            }
+            Some(MessageResponse::FileDescriptorResponse(response)) => {
+                for bytes in response.file_descriptor_proto {
+                    let file = FileDescriptorProto::decode(bytes.as_slice())
+                        .map_err(|it| it.to_string())?;
+                    files.insert(file.name().to_string(), file);
+                }
+                let missing: Vec<String> = files
+                    .values()
+                    .flat_map(|it| it.dependency.iter())
+                    .filter(|it| !files.contains_key(*it) && pool.get_file_by_name(it).is_none())
+                    .cloned()
+                    .collect();
+                for dependency in missing {
+                    if requested.insert(dependency.clone()) {
+                        send(MessageRequest::FileByFilename(dependency));
+                        pending += 1;
+                    }
+                }
+            }
+            Some(MessageResponse::ErrorResponse(error)) => {
+                return Err(format!("Server reflection failed: {}", error.error_message));
+            }
+            _ => {}
+        }
+    }
+    pool.add_file_descriptor_protos(files.into_values())
+        .map_err(|it| it.to_string())?;
+    Ok(pool)
+}
+
+// Encodes and decodes messages by their descriptor instead of generated types
+#[derive(Clone)]
+struct DynamicCodec(MessageDescriptor);
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicCodec;
+    type Decoder = DynamicCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        self.clone()
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        self.clone()
+    }
+}
+
+impl Encoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(dst)
+            .map_err(|it| Status::internal(it.to_string()))
+    }
+}
+
+impl Decoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        DynamicMessage::decode(self.0.clone(), src)
+            .map(Some)
+            .map_err(|it| Status::internal(it.to_string()))
+    }
+}
+
+// Unary calls give the response message, server streaming calls an array of all messages
+pub async fn call(
+    url: &str,
+    pool: Option<DescriptorPool>,
+    body: &str,
+    headers: HeaderMap,
+    tls: &TlsSettings,
+) -> Result<(String, GrpcStatus), MyErr> {
+    let (origin, path) = split_url(url);
+    let pool = match pool {
+        Some(pool) => pool,
+        None => reflect(&origin, tls).await?,
+    };
+    let channel = connect(&origin, tls).await?;
+    call_with(channel, &pool, &path, &origin, body, headers)
+        .await
+        .map_err(MyErr::Client)
+}
+
+async fn call_with(
+    channel: Channel,
+    pool: &DescriptorPool,
+    path: &str,
+    origin: &str,
+    body: &str,
+    headers: HeaderMap,
+) -> Result<(String, GrpcStatus), String> {
+    let method = find_method(pool, path)
+        .ok_or_else(|| format!("Method `{}` was not found on {}", path, origin))?;
+    if method.is_client_streaming() {
+        return Err("Client and bidirectional streaming calls are not supported".to_string());
+    }
+    let body = if body.trim().is_empty() { "{}" } else { body };
+    let mut deserializer = serde_json::Deserializer::from_str(body);
+    let message = DynamicMessage::deserialize(method.input(), &mut deserializer).map_err(|it| {
+        format!(
+            "Request is not a valid {}: {}",
+            method.input().full_name(),
+            it
+        )
+    })?;
+    let mut request = tonic::Request::new(message);
+    *request.metadata_mut() = MetadataMap::from_headers(headers);
+    let path = PathAndQuery::try_from(format!("/{}", path)).map_err(|it| it.to_string())?;
+    let codec = DynamicCodec(method.output());
+
+    let mut grpc = tonic::client::Grpc::new(channel);
+    grpc.ready().await.map_err(|it| describe(&it))?;
+    let mut messages = Vec::new();
+    let result = if method.is_server_streaming() {
+        match grpc.server_streaming(request, path, codec).await {
+            Ok(response) => {
+                let mut stream = response.into_inner();
+                loop {
+                    match stream.message().await {
+                        Ok(Some(message)) => messages.push(to_json(&message)),
+                        Ok(None) => break Ok(()),
+                        Err(status) => break Err(status),
+                    }
+                }
+            }
+            Err(status) => Err(status),
+        }
+    } else {
+        grpc.unary(request, path, codec)
+            .await
+            .map(|it| messages.push(to_json(it.get_ref())))
+    };
+
+    let status = match result {
+        Ok(()) => GrpcStatus {
+            code: 0,
+            message: String::new(),
+        },
+        Err(status) => GrpcStatus {
+            code: status.code() as i32,
+            message: status.message().to_string(),
+        },
+    };
+    let text = if method.is_server_streaming() {
+        serde_json::to_string_pretty(&messages).unwrap_or_default()
+    } else {
+        match messages.pop() {
+            Some(message) => serde_json::to_string_pretty(&message).unwrap_or_default(),
+            None => String::new(),
+        }
+    };
+    Ok((text, status))
+}
@@ -0,0 +1,98 @@
+const BYTES_PER_LINE: usize = 16;
+// Bigger bodies are cut off, the full body can still be saved to a file
+const MAX_BYTES: usize = 64 * 1024;
+
+// 00000000  89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 52  |.PNG........IHDR|
+pub fn hex_dump(body: &[u8]) -> String {
+    let mut dump = String::new();
+    for (index, chunk) in body[..body.len().min(MAX_BYTES)]
+        .chunks(BYTES_PER_LINE)
+        .enumerate()
+    {
+        dump.push_str(&format!("{:08x} ", index * BYTES_PER_LINE));
+        for position in 0..BYTES_PER_LINE {
+            if position % 8 == 0 {
+                dump.push(' ');
+            }
+            match chunk.get(position) {
+                Some(byte) => dump.push_str(&format!("{:02x} ", byte)),
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push('|');
+        dump.extend(chunk.iter().map(|it| {
+            if it.is_ascii_graphic() || *it == b' ' {
+                *it as char
+            } else {
+                '.'
+            }
+        }));
+        dump.push_str("|\n");
+    }
+    if body.len() > MAX_BYTES {
+        dump.push_str(&format!(
+            "... {} more bytes, save the body to see all of it",
+            body.len() - MAX_BYTES
+        ));
+    }
+    dump
+}
+
+// "de ad be ef", as typed into the binary frame composer
+pub fn hex_string(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|it| format!("{:02x}", it))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn parse_hex(input: &str) -> Result<Vec<u8>, String> {
+    let digits: String = input
+        .split_whitespace()
+        .map(|it| it.trim_start_matches("0x"))
+        .collect();
+    // Checked first, a multi-byte character would otherwise count as several digits
+    if let Some(invalid) = digits.chars().find(|it| !it.is_ascii_hexdigit()) {
+        return Err(format!("Invalid hex digit `{}`", invalid));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err("Hex input must have an even number of digits".to_string());
+    }
+    // Only ASCII is left, so every pair is a valid str
+    digits
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).unwrap_or_default();
+            u8::from_str_radix(pair, 16).map_err(|_| format!("Invalid hex byte `{}`", pair))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_input() {
+        assert_eq!(parse_hex("de ad be ef"), Ok(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(parse_hex("DEAD\n\tbeef "), Ok(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(parse_hex("0x01 0x0a"), Ok(vec![0x01, 0x0a]));
+        assert_eq!(parse_hex("  "), Ok(vec![]));
+        // Digits pair up across spaces
+        assert_eq!(parse_hex("a bc d"), Ok(vec![0xab, 0xcd]));
+        assert_eq!(hex_string(&parse_hex("00ff10").unwrap()), "00 ff 10");
+    }
+
+    #[test]
+    fn parse_hex_errors() {
+        assert_eq!(
+            parse_hex("abc"),
+            Err("Hex input must have an even number of digits".to_string())
+        );
+        assert_eq!(parse_hex("0a 0g"), Err("Invalid hex digit `g`".to_string()));
+        assert_eq!(parse_hex("zz"), Err("Invalid hex digit `z`".to_string()));
+        assert_eq!(parse_hex("é1"), Err("Invalid hex digit `é`".to_string()));
+    }
+}
@@ -0,0 +1,271 @@
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Plain,
+    Key,
+    String,
+    Number,
+    Literal,
+    Punctuation,
+    Tag,
+    Attribute,
+    Comment,
+}
+
+pub type HighlightedLine<'a> = Vec<(Token, &'a str)>;
+
+// A highlighted line that doesn't borrow the body, segments are byte ranges of it
+pub type LineRanges = Vec<(Token, Range<usize>)>;
+
+// Splits the body into lines of colored segments. Works on a best-effort basis,
+// so that broken or truncated bodies are still highlighted up to the error.
+pub fn highlight<'a>(content: &'a str, mime_type: &str) -> Vec<HighlightedLine<'a>> {
+    let mut highlighter = Highlighter {
+        content,
+        pos: 0,
+        segments: Vec::new(),
+    };
+    if mime_type.contains("json") {
+        highlighter.json();
+    } else if mime_type.contains("html") || mime_type.contains("xml") {
+        highlighter.markup();
+    } else if mime_type.contains("yaml") {
+        highlighter.yaml();
+    } else {
+        highlighter.push(Token::Plain, content.len());
+    }
+    highlighter.into_lines()
+}
+
+pub fn highlight_ranges(content: &str, mime_type: &str) -> Vec<LineRanges> {
+    highlight(content, mime_type)
+        .into_iter()
+        .map(|line| {
+            line.into_iter()
+                .map(|(token, segment)| {
+                    let start = segment.as_ptr() as usize - content.as_ptr() as usize;
+                    (token, start..start + segment.len())
+                })
+                .collect()
+        })
+        .collect()
+}
+
+struct Highlighter<'a> {
+    content: &'a str,
+    pos: usize,
+    segments: Vec<(Token, &'a str)>,
+}
+
+impl<'a> Highlighter<'a> {
+    fn rest(&self) -> &'a str {
+        &self.content[self.pos..]
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.content.as_bytes().get(self.pos).copied()
+    }
+
+    fn push(&mut self, token: Token, len: usize) {
+        if len == 0 {
+            return;
+        }
+        let segment = &self.content[self.pos..self.pos + len];
+        self.pos += len;
+        match self.segments.last_mut() {
+            Some((last, text)) if *last == token => {
+                let start = text.as_ptr() as usize - self.content.as_ptr() as usize;
+                *text = &self.content[start..start + text.len() + len];
+            }
+            _ => self.segments.push((token, segment)),
+        }
+    }
+
+    fn push_char(&mut self, token: Token) {
+        let len = self.rest().chars().next().map_or(0, char::len_utf8);
+        self.push(token, len);
+    }
+
+    fn len_while(&self, pred: impl Fn(u8) -> bool) -> usize {
+        self.rest().bytes().take_while(|it| pred(*it)).count()
+    }
+
+    fn len_until(&self, pattern: &str) -> usize {
+        match self.rest().find(pattern) {
+            Some(index) => index + pattern.len(),
+            None => self.rest().len(),
+        }
+    }
+
+    fn quoted_len(&self) -> usize {
+        let bytes = self.rest().as_bytes();
+        let quote = bytes[0];
+        let mut index = 1;
+        while index < bytes.len() {
+            match bytes[index] {
+                b'\\' => index += 2,
+                it if it == quote => return index + 1,
+                _ => index += 1,
+            }
+        }
+        bytes.len()
+    }
+
+    fn json(&mut self) {
+        while let Some(byte) = self.peek() {
+            match byte {
+                b'"' => {
+                    let len = self.quoted_len();
+                    let is_key = self.rest()[len..].trim_start().starts_with(':');
+                    self.push(if is_key { Token::Key } else { Token::String }, len);
+                }
+                b'{' | b'}' | b'[' | b']' | b',' | b':' => self.push(Token::Punctuation, 1),
+                b'-' | b'0'..=b'9' => {
+                    let len = self.len_while(|it| {
+                        it.is_ascii_digit() || matches!(it, b'-' | b'+' | b'.' | b'e' | b'E')
+                    });
+                    self.push(Token::Number, len);
+                }
+                it if it.is_ascii_alphabetic() => {
+                    let len = self.len_while(|it| it.is_ascii_alphanumeric());
+                    let token = match &self.rest()[..len] {
+                        "true" | "false" | "null" => Token::Literal,
+                        _ => Token::Plain,
+                    };
+                    self.push(token, len);
+                }
+                _ => self.push_char(Token::Plain),
+            }
+        }
+    }
+
+    fn markup(&mut self) {
+        while let Some(byte) = self.peek() {
+            if self.rest().starts_with("<!--") {
+                let len = self.len_until("-->");
+                self.push(Token::Comment, len);
+            } else if byte == b'<' {
+                self.markup_tag();
+            } else {
+                let len = self.len_while(|it| it != b'<');
+                self.push(Token::Plain, len);
+            }
+        }
+    }
+
+    fn markup_tag(&mut self) {
+        let len = 1 + self.rest()[1..]
+            .bytes()
+            .take_while(|it| matches!(it, b'/' | b'?' | b'!'))
+            .count();
+        self.push(Token::Punctuation, len);
+        let len = self.len_while(is_name_byte);
+        self.push(Token::Tag, len);
+        while let Some(byte) = self.peek() {
+            match byte {
+                b'>' => {
+                    self.push(Token::Punctuation, 1);
+                    return;
+                }
+                b'/' | b'?' | b'=' => self.push(Token::Punctuation, 1),
+                b'"' | b'\'' => {
+                    let len = self.quoted_len();
+                    self.push(Token::String, len);
+                }
+                it if is_name_byte(it) => {
+                    let len = self.len_while(is_name_byte);
+                    self.push(Token::Attribute, len);
+                }
+                _ => self.push_char(Token::Plain),
+            }
+        }
+    }
+
+    fn yaml(&mut self) {
+        while self.peek().is_some() {
+            let line_len = self.len_while(|it| it != b'\n');
+            self.yaml_line(line_len);
+            if self.peek() == Some(b'\n') {
+                self.push(Token::Plain, 1);
+            }
+        }
+    }
+
+    fn yaml_line(&mut self, line_len: usize) {
+        let end = self.pos + line_len;
+        let indent = self.len_while(|it| it == b' ' || it == b'\t').min(line_len);
+        self.push(Token::Plain, indent);
+
+        let line = &self.content[self.pos..end];
+        if line.starts_with('#') {
+            self.push(Token::Comment, line.len());
+            return;
+        }
+        if line.starts_with("---") || line.starts_with("...") {
+            self.push(Token::Punctuation, line.len());
+            return;
+        }
+        while self.rest().starts_with("- ") {
+            self.push(Token::Punctuation, 2);
+        }
+
+        let line = &self.content[self.pos..end];
+        if let Some(colon) = yaml_key_end(line) {
+            self.push(Token::Key, colon);
+            self.push(Token::Punctuation, 1);
+        }
+
+        let value = &self.content[self.pos..end];
+        let (value, comment) = match value.find(" #") {
+            Some(index) if !value.starts_with(['"', '\'']) => value.split_at(index),
+            _ => (value, ""),
+        };
+        let leading = value.len() - value.trim_start().len();
+        self.push(Token::Plain, leading);
+        let scalar = value.trim();
+        let token = match scalar {
+            "" => Token::Plain,
+            "true" | "false" | "null" | "~" | "yes" | "no" => Token::Literal,
+            it if it.parse::<f64>().is_ok() => Token::Number,
+            it if it.starts_with(['{', '[', '|', '>']) => Token::Punctuation,
+            _ => Token::String,
+        };
+        self.push(token, scalar.len());
+        self.push(Token::Plain, value.len() - leading - scalar.len());
+        self.push(Token::Comment, comment.len());
+    }
+
+    fn into_lines(self) -> Vec<HighlightedLine<'a>> {
+        let mut lines = vec![Vec::new()];
+        for (token, segment) in self.segments {
+            for (index, part) in segment.split('\n').enumerate() {
+                if index > 0 {
+                    lines.push(Vec::new());
+                }
+                if !part.is_empty() {
+                    lines.last_mut().unwrap().push((token, part));
+                }
+            }
+        }
+        lines
+    }
+}
+
+fn is_name_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b':' | b'.') || byte >= 0x80
+}
+
+fn yaml_key_end(line: &str) -> Option<usize> {
+    if line.starts_with(['"', '\'', '{', '[']) {
+        return None;
+    }
+    let colon = line
+        .find(": ")
+        .or_else(|| line.strip_suffix(':').map(str::len))?;
+    if line[..colon].contains(" #") {
+        None
+    } else {
+        Some(colon)
+    }
+}
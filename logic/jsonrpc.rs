@@ -0,0 +1,120 @@
+use serde_json::{Map, Value, json};
+
+#[derive(Debug, Clone)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Reply {
+    pub id: String,
+    pub method: Option<String>,
+    pub result: Result<String, RpcError>,
+}
+
+// Builds the request envelope, ids are the position of the call starting from 1.
+// More than one call is sent as a batch.
+pub fn request_body(calls: &[(String, String)]) -> Result<String, String> {
+    let mut envelopes = Vec::new();
+    for (index, (method, params)) in calls.iter().enumerate() {
+        let method = method.trim();
+        if method.is_empty() {
+            return Err(format!("Call #{} has no method name", index + 1));
+        }
+        let mut envelope = Map::new();
+        envelope.insert("jsonrpc".to_string(), Value::from("2.0"));
+        envelope.insert("method".to_string(), Value::from(method));
+        if !params.trim().is_empty() {
+            let params: Value = serde_json::from_str(params)
+                .map_err(|it| format!("Params of `{}` are not valid JSON: {}", method, it))?;
+            if !params.is_array() && !params.is_object() {
+                return Err(format!(
+                    "Params of `{}` must be an array or an object",
+                    method
+                ));
+            }
+            envelope.insert("params".to_string(), params);
+        }
+        envelope.insert("id".to_string(), Value::from(index + 1));
+        envelopes.push(Value::Object(envelope));
+    }
+    let body = match envelopes.len() {
+        1 => envelopes.remove(0),
+        _ => Value::Array(envelopes),
+    };
+    serde_json::to_string_pretty(&body).map_err(|it| it.to_string())
+}
+
+// Reverse of request_body, used to load a saved request back into the editors
+pub fn parse_request_body(body: &str) -> Vec<(String, String)> {
+    let value: Value = serde_json::from_str(body).unwrap_or_default();
+    let envelopes = match value {
+        Value::Array(it) => it,
+        Value::Object(_) => vec![value],
+        _ => vec![],
+    };
+    envelopes
+        .iter()
+        .map(|it| {
+            let method = it["method"].as_str().unwrap_or_default().to_string();
+            let params = match it.get("params") {
+                Some(params) => serde_json::to_string_pretty(params).unwrap_or_default(),
+                None => String::new(),
+            };
+            (method, params)
+        })
+        .collect()
+}
+
+// Pairs every response object with the call it answers, in the order of the calls
+pub fn parse_replies(request: &str, response: &str) -> Vec<Reply> {
+    let Ok(value) = serde_json::from_str::<Value>(response) else {
+        return vec![];
+    };
+    let calls = parse_request_body(request);
+    let mut replies: Vec<&Value> = match &value {
+        Value::Array(it) => it.iter().collect(),
+        Value::Object(it) if it.contains_key("jsonrpc") => vec![&value],
+        _ => vec![],
+    };
+    replies.sort_by_key(|it| it["id"].as_u64().unwrap_or(u64::MAX));
+    replies
+        .into_iter()
+        .map(|it| {
+            let method = it["id"]
+                .as_u64()
+                .and_then(|id| calls.get((id as usize).checked_sub(1)?))
+                .map(|(method, _)| method.clone());
+            let result = match it.get("error") {
+                Some(error) if !error.is_null() => Err(RpcError {
+                    code: error["code"].as_i64().unwrap_or_default(),
+                    message: error["message"].as_str().unwrap_or_default().to_string(),
+                    data: error.get("data").map(Value::to_string),
+                }),
+                _ => Ok(it.get("result").unwrap_or(&json!(null)).to_string()),
+            };
+            Reply {
+                id: match &it["id"] {
+                    Value::String(it) => it.clone(),
+                    it => it.to_string(),
+                },
+                method,
+                result,
+            }
+        })
+        .collect()
+}
+
+pub fn error_name(code: i64) -> &'static str {
+    match code {
+        -32700 => "Parse error",
+        -32600 => "Invalid request",
+        -32601 => "Method not found",
+        -32602 => "Invalid params",
+        -32603 => "Internal error",
+        -32099..=-32000 => "Server error",
+        _ => "Application error",
+    }
+}
@@ -0,0 +1,507 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::NaiveDateTime;
+use iced::futures::future::join_all;
+use reqwest::header::HeaderName;
+use reqwest::{Client, Request, StatusCode};
+use serde_json::{Value, json};
+
+use crate::logic::redirect::{RedirectPolicy, execute};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+const HISTOGRAM_BUCKETS: usize = 20;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadLimit {
+    #[default]
+    Requests,
+    Seconds,
+}
+
+impl LoadLimit {
+    pub const ALL: [LoadLimit; 2] = [LoadLimit::Requests, LoadLimit::Seconds];
+}
+
+impl Display for LoadLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            LoadLimit::Requests => "Total requests",
+            LoadLimit::Seconds => "Duration, s",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadSettings {
+    pub limit: LoadLimit,
+    pub amount: u64,
+    pub concurrency: u64,
+    // Requests per second over all workers, None is as fast as the server answers
+    pub rate: Option<u64>,
+}
+
+impl LoadSettings {
+    pub fn to_json(&self) -> String {
+        json!({
+            "limit": match self.limit {
+                LoadLimit::Requests => "requests",
+                LoadLimit::Seconds => "seconds",
+            },
+            "amount": self.amount,
+            "concurrency": self.concurrency,
+            "rate": self.rate,
+        })
+        .to_string()
+    }
+
+    pub fn from_json(text: &str) -> LoadSettings {
+        let value: Value = serde_json::from_str(text).unwrap_or_default();
+        LoadSettings {
+            limit: match value["limit"].as_str() {
+                Some("seconds") => LoadLimit::Seconds,
+                _ => LoadLimit::Requests,
+            },
+            amount: value["amount"].as_u64().unwrap_or_default(),
+            concurrency: value["concurrency"].as_u64().unwrap_or(1),
+            rate: value["rate"].as_u64(),
+        }
+    }
+}
+
+// The settings as typed, they are checked when the run starts
+#[derive(Debug, Clone)]
+pub struct LoadForm {
+    pub limit: LoadLimit,
+    pub amount: String,
+    pub concurrency: String,
+    pub rate: String,
+}
+
+impl Default for LoadForm {
+    fn default() -> Self {
+        LoadForm {
+            limit: LoadLimit::Requests,
+            amount: "100".to_string(),
+            concurrency: "10".to_string(),
+            rate: String::new(),
+        }
+    }
+}
+
+impl LoadForm {
+    pub fn settings(&self) -> Result<LoadSettings, String> {
+        let number = |text: &str, name: &str| match text.trim().parse::<u64>() {
+            Ok(it) if it > 0 => Ok(it),
+            _ => Err(format!("{} must be a whole number above 0", name)),
+        };
+        Ok(LoadSettings {
+            limit: self.limit,
+            amount: number(&self.amount, &self.limit.to_string())?,
+            concurrency: number(&self.concurrency, "Concurrency")?.min(1000),
+            rate: match self.rate.trim() {
+                "" => None,
+                it => Some(number(it, "Rate")?),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadReport {
+    pub sent: u64,
+    pub errors: u64,
+    pub elapsed: Duration,
+    // Status code or kind of failure -> count
+    pub statuses: Vec<(String, u64)>,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+    // Upper bound of each bucket -> count, the buckets split min..max evenly
+    pub histogram: Vec<(Duration, u64)>,
+}
+
+impl LoadReport {
+    pub fn throughput(&self) -> f64 {
+        match self.elapsed.as_secs_f64() {
+            0.0 => 0.0,
+            secs => self.sent as f64 / secs,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let ms = |it: Duration| it.as_secs_f64() * 1000.0;
+        json!({
+            "sent": self.sent,
+            "errors": self.errors,
+            "elapsed_ms": ms(self.elapsed),
+            "statuses": self.statuses,
+            "p50_ms": ms(self.p50),
+            "p90_ms": ms(self.p90),
+            "p99_ms": ms(self.p99),
+            "max_ms": ms(self.max),
+            "histogram": self
+                .histogram
+                .iter()
+                .map(|(upper, count)| json!([ms(*upper), count]))
+                .collect::<Vec<_>>(),
+        })
+        .to_string()
+    }
+
+    pub fn from_json(text: &str) -> LoadReport {
+        let value: Value = serde_json::from_str(text).unwrap_or_default();
+        let duration =
+            |it: &Value| Duration::from_secs_f64(it.as_f64().unwrap_or_default() / 1000.0);
+        let pairs = |name: &str| -> Vec<(Value, u64)> {
+            value[name]
+                .as_array()
+                .map(|items| {
+                    items
+                        .iter()
+                        .map(|it| (it[0].clone(), it[1].as_u64().unwrap_or_default()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        LoadReport {
+            sent: value["sent"].as_u64().unwrap_or_default(),
+            errors: value["errors"].as_u64().unwrap_or_default(),
+            elapsed: duration(&value["elapsed_ms"]),
+            statuses: pairs("statuses")
+                .into_iter()
+                .map(|(status, count)| (status.as_str().unwrap_or_default().to_string(), count))
+                .collect(),
+            p50: duration(&value["p50_ms"]),
+            p90: duration(&value["p90_ms"]),
+            p99: duration(&value["p99_ms"]),
+            max: duration(&value["max_ms"]),
+            histogram: pairs("histogram")
+                .into_iter()
+                .map(|(upper, count)| (duration(&upper), count))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadRun {
+    pub id: u64,
+    pub settings: LoadSettings,
+    pub report: LoadReport,
+    pub started_time: NaiveDateTime,
+}
+
+// A run in progress, the workers look at `stop` before every request
+#[derive(Debug, Clone)]
+pub struct LoadProgress {
+    pub endpoint_id: u64,
+    pub settings: LoadSettings,
+    pub stop: Arc<AtomicBool>,
+    pub report: LoadReport,
+    pub started_time: NaiveDateTime,
+}
+
+// How the request goes out, it is prepared anew for every send so dynamic variables, scripts
+// and signatures are fresh each time
+pub struct LoadRequest {
+    // Built like any other send, so it has the same default headers. None when a pre-request
+    // script or the url fails, the first request is checked before the run starts
+    pub prepare: Box<dyn Fn() -> Option<Request> + Send + Sync>,
+    pub redirect: RedirectPolicy,
+    pub credentials: Vec<HeaderName>,
+}
+
+#[derive(Default)]
+struct Samples {
+    // Latency -> count, a report walks it in order instead of sorting every sample again
+    latencies: BTreeMap<Duration, u64>,
+    sent: u64,
+    max: Duration,
+    statuses: BTreeMap<String, u64>,
+    errors: u64,
+}
+
+impl Samples {
+    fn record(&mut self, outcome: Result<StatusCode, &str>, latency: Duration) {
+        let status = match outcome {
+            Ok(code) if code.is_client_error() || code.is_server_error() => {
+                self.errors += 1;
+                code.as_u16().to_string()
+            }
+            Ok(code) => code.as_u16().to_string(),
+            Err(failure) => {
+                self.errors += 1;
+                failure.to_string()
+            }
+        };
+        *self.statuses.entry(status).or_default() += 1;
+        *self.latencies.entry(significant(latency)).or_default() += 1;
+        self.sent += 1;
+        self.max = self.max.max(latency);
+    }
+
+    fn report(&self, elapsed: Duration) -> LoadReport {
+        // Nearest-rank percentile
+        let percentile = |p: f64| {
+            let rank = ((p / 100.0 * self.sent as f64).ceil() as u64).max(1);
+            let mut seen = 0;
+            self.latencies
+                .iter()
+                .find(|(_, count)| {
+                    seen += *count;
+                    seen >= rank
+                })
+                .map(|(latency, _)| *latency)
+                .unwrap_or_default()
+        };
+        LoadReport {
+            sent: self.sent,
+            errors: self.errors,
+            elapsed,
+            statuses: self
+                .statuses
+                .iter()
+                .map(|(status, count)| (status.clone(), *count))
+                .collect(),
+            p50: percentile(50.0),
+            p90: percentile(90.0),
+            p99: percentile(99.0),
+            max: self.max,
+            histogram: histogram(&self.latencies),
+        }
+    }
+}
+
+// Keeps three significant digits of the microseconds, which bounds the number of distinct
+// latencies a long run collects
+fn significant(latency: Duration) -> Duration {
+    let micros = latency.as_micros() as u64;
+    let mut step = 1;
+    while micros / step >= 1000 {
+        step *= 10;
+    }
+    Duration::from_micros(micros / step * step)
+}
+
+fn histogram(latencies: &BTreeMap<Duration, u64>) -> Vec<(Duration, u64)> {
+    let (Some((min, _)), Some((max, _))) =
+        (latencies.first_key_value(), latencies.last_key_value())
+    else {
+        return vec![];
+    };
+    let width = (*max - *min) / HISTOGRAM_BUCKETS as u32;
+    if width.is_zero() {
+        return vec![(*max, latencies.values().sum())];
+    }
+    let mut buckets: Vec<(Duration, u64)> = (1..=HISTOGRAM_BUCKETS)
+        .map(|it| (*min + width * it as u32, 0))
+        .collect();
+    for (latency, count) in latencies {
+        let index = ((*latency - *min).as_secs_f64() / width.as_secs_f64()) as usize;
+        buckets[index.min(HISTOGRAM_BUCKETS - 1)].1 += count;
+    }
+    buckets
+}
+
+fn failure(err: &reqwest::Error) -> &'static str {
+    if err.is_timeout() {
+        "Timeout"
+    } else if err.is_connect() {
+        "Connection error"
+    } else {
+        "Request error"
+    }
+}
+
+// Sends the request from `concurrency` workers until the limit is reached or `stop` is set,
+// `progress` gets a snapshot of the report a few times a second
+pub async fn run(
+    client: Client,
+    request: LoadRequest,
+    settings: LoadSettings,
+    stop: Arc<AtomicBool>,
+    mut progress: impl FnMut(LoadReport),
+) -> LoadReport {
+    let start = Instant::now();
+    let interval = settings
+        .rate
+        .map(|it| Duration::from_secs_f64(1.0 / it as f64));
+    let issued = AtomicU64::new(0);
+    let next_slot = Mutex::new(start);
+    let samples = Mutex::new(Samples::default());
+    let done = AtomicBool::new(false);
+    let (request, settings, stop, issued, next_slot, samples, client) = (
+        &request, &settings, &stop, &issued, &next_slot, &samples, &client,
+    );
+
+    let worker = move || async move {
+        loop {
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            let more = match settings.limit {
+                LoadLimit::Requests => issued.fetch_add(1, Ordering::Relaxed) < settings.amount,
+                LoadLimit::Seconds => start.elapsed() < Duration::from_secs(settings.amount),
+            };
+            if !more {
+                break;
+            }
+            // Every request takes the next free slot, so the rate holds across workers
+            if let Some(interval) = interval {
+                let slot = {
+                    let mut next = next_slot.lock().unwrap();
+                    let slot = (*next).max(Instant::now());
+                    *next = slot + interval;
+                    slot
+                };
+                tokio::time::sleep_until(slot.into()).await;
+            }
+            let sent = Instant::now();
+            let outcome = match (request.prepare)() {
+                Some(prepared) => match execute(
+                    client,
+                    prepared,
+                    &request.redirect,
+                    &request.credentials,
+                    &mut vec![],
+                )
+                .await
+                {
+                    Ok(resp) => {
+                        let code = resp.status();
+                        // The latency includes reading the whole body
+                        resp.bytes().await.map(|_| code).map_err(|it| failure(&it))
+                    }
+                    Err(err) => Err(failure(&err)),
+                },
+                None => Err("Prepare error"),
+            };
+            samples.lock().unwrap().record(outcome, sent.elapsed());
+        }
+    };
+
+    let workers = async {
+        join_all((0..settings.concurrency).map(|_| worker())).await;
+        done.store(true, Ordering::Relaxed);
+        start.elapsed()
+    };
+    let ticker = async {
+        while !done.load(Ordering::Relaxed) {
+            tokio::time::sleep(PROGRESS_INTERVAL).await;
+            let report = samples.lock().unwrap().report(start.elapsed());
+            progress(report);
+        }
+    };
+    let (elapsed, _) = tokio::join!(workers, ticker);
+    samples.lock().unwrap().report(elapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(it: u64) -> Duration {
+        Duration::from_millis(it)
+    }
+
+    fn samples(latencies: impl IntoIterator<Item = u64>) -> Samples {
+        let mut samples = Samples::default();
+        for latency in latencies {
+            samples.record(Ok(StatusCode::OK), ms(latency));
+        }
+        samples
+    }
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let report = samples((1..=100).rev()).report(ms(1000));
+        assert_eq!(report.sent, 100);
+        assert_eq!(
+            (report.p50, report.p90, report.p99),
+            (ms(50), ms(90), ms(99))
+        );
+        assert_eq!(report.max, ms(100));
+
+        // The rank rounds up, so a percentile is always one of the samples
+        let report = samples([10, 20, 30]).report(ms(1000));
+        assert_eq!(
+            (report.p50, report.p90, report.p99),
+            (ms(20), ms(30), ms(30))
+        );
+
+        let report = samples([7]).report(ms(1000));
+        assert_eq!((report.p50, report.p99, report.max), (ms(7), ms(7), ms(7)));
+
+        let report = Samples::default().report(ms(1000));
+        assert_eq!(
+            (report.sent, report.p50, report.max),
+            (0, Duration::ZERO, Duration::ZERO)
+        );
+        assert!(report.histogram.is_empty());
+    }
+
+    #[test]
+    fn statuses_and_errors() {
+        let mut samples = samples([1, 2]);
+        samples.record(Ok(StatusCode::NOT_FOUND), ms(3));
+        samples.record(Err("Timeout"), ms(4));
+        let report = samples.report(ms(1000));
+        assert_eq!((report.sent, report.errors), (4, 2));
+        assert_eq!(
+            report.statuses,
+            [
+                ("200".to_string(), 2),
+                ("404".to_string(), 1),
+                ("Timeout".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn histogram_buckets() {
+        // 0..=200 ms split in 10 ms buckets, the upper bound belongs to the next bucket
+        let report = samples([0, 9, 10, 19, 100, 200]).report(ms(1000));
+        assert_eq!(report.histogram.len(), HISTOGRAM_BUCKETS);
+        assert_eq!(report.histogram[0], (ms(10), 2));
+        assert_eq!(report.histogram[1], (ms(20), 2));
+        assert_eq!(report.histogram[10], (ms(110), 1));
+        // The maximum falls into the last bucket
+        assert_eq!(report.histogram[19], (ms(200), 1));
+        assert_eq!(report.histogram.iter().map(|it| it.1).sum::<u64>(), 6);
+
+        // All the same, a single bucket
+        let report = samples([5, 5, 5]).report(ms(1000));
+        assert_eq!(report.histogram, [(ms(5), 3)]);
+    }
+
+    #[test]
+    fn latencies_keep_three_digits() {
+        assert_eq!(
+            significant(Duration::from_micros(999)),
+            Duration::from_micros(999)
+        );
+        assert_eq!(
+            significant(Duration::from_micros(1234)),
+            Duration::from_micros(1230)
+        );
+        assert_eq!(
+            significant(Duration::from_micros(98_765)),
+            Duration::from_micros(98_700)
+        );
+        assert_eq!(
+            significant(Duration::from_nanos(1500)),
+            Duration::from_micros(1)
+        );
+
+        // The maximum stays exact
+        let mut samples = Samples::default();
+        samples.record(Ok(StatusCode::OK), Duration::from_micros(1234));
+        assert_eq!(samples.report(ms(1)).max, Duration::from_micros(1234));
+    }
+}
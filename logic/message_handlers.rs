@@ -1,13 +1,55 @@
+use chrono::Utc;
 use iced::Task;
+use iced::widget::scrollable::{self, RelativeOffset};
+use iced::widget::text_editor::{self, Action, Edit};
+use iced::widget::text_input::focus;
+use reqwest::Url;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
 
-use crate::{EndpointKvPair, MHeader, MQueryParam, Message, Request, State, update};
+use crate::{
+    Download, EndpointKvPair, MAuth, MBodySearch, MData, MDownload, MGraphQl, MGrpc, MHeader,
+    MLoad, MProxy, MQueryParam, MRedirect, MRetry, MRpc, MScript, MSoap, MSse, MTls, MWs, Message,
+    MyErr, Request, Response, RpcCall, SseSession, SseStatus, State, WsConnection, WsFrame,
+    authorized_request, body_from_state, client_settings, connect_ws, create_ws_session,
+    current_endpoint, download_to_file, fetch_oauth_token, fetch_schema, fetch_wsdl,
+    format_url_from_state, http_method, method_from_state, pick_data_file, pick_download_file,
+    pick_protos, pick_tls_file, pick_wsdl, prepare_request, request_from_state, schema_key,
+    script_text, send_now, send_row, send_settings, set_request_header, start_load_test, update,
+    with_row,
+};
 
 use super::{
+    auth::AuthKind,
     crud::{
-        header::{delete_header, update_header_key, update_header_value},
-        query::{delete_query_param, update_query_param_key, update_query_param_value},
+        endpoint::{
+            update_endpoint_auth, update_endpoint_proto_paths, update_endpoint_proxy,
+            update_endpoint_redirect, update_endpoint_retry, update_endpoint_scripts,
+            update_endpoint_url,
+        },
+        header::{create_header, delete_header, update_header_key, update_header_value},
+        load_run::{create_load_run, delete_load_run},
+        query::{
+            create_query_param, delete_query_param, update_query_param_key,
+            update_query_param_value,
+        },
+        response::create_response,
+        setting::set_setting,
+        ws_frame::create_ws_frame,
     },
+    data::{DataRun, RowResult},
     db::get_db,
+    grpc::{find_method, reflect, split_url, template},
+    hex::parse_hex,
+    load::LoadRun,
+    proxy::ProxyScope,
+    script::run_post_response,
+    search::refresh_matches,
+    signing::SigningField,
+    sse::event_log,
+    tls::ClientCert,
 };
 
 pub fn message_query_param(state: &mut State, message: MQueryParam) -> Task<Message> {
@@ -36,6 +78,7 @@ pub fn message_query_param(state: &mut State, message: MQueryParam) -> Task<Mess
                             }]
                             .to_vec(),
                             headers: [].to_vec(),
+                            body: String::new(),
                         });
                     }
                     None => {
@@ -184,6 +227,7 @@ pub fn message_header(state: &mut State, message: MHeader) -> Task<Message> {
                             }]
                             .to_vec(),
                             query_params: [].to_vec(),
+                            body: String::new(),
                         });
                     }
                     None => {
@@ -305,3 +349,863 @@ pub fn message_header(state: &mut State, message: MHeader) -> Task<Message> {
         }
     }
 }
+
+pub fn message_body_search(state: &mut State, message: MBodySearch) -> Task<Message> {
+    let changed = !matches!(message, MBodySearch::Next | MBodySearch::Previous);
+    let search = &mut state.body_search;
+    match message {
+        MBodySearch::SetQuery(query) => {
+            search.query = query;
+            search.current = 0;
+        }
+        MBodySearch::ToggleRegex => {
+            search.regex = !search.regex;
+            search.current = 0;
+        }
+        MBodySearch::ToggleCaseSensitive => {
+            search.case_sensitive = !search.case_sensitive;
+            search.current = 0;
+        }
+        MBodySearch::Next => search.current += 1,
+        MBodySearch::Previous => search.current = search.current.wrapping_sub(1),
+    }
+    if changed {
+        refresh_matches(&mut state.body_search, &state.shown_body.text);
+    }
+    scroll_to_current_match(state)
+}
+
+fn scroll_to_current_match(state: &mut State) -> Task<Message> {
+    let body = &state.shown_body.text;
+    let matches = &state.body_search.matches;
+    if matches.is_empty() {
+        return Task::none();
+    }
+    let current = match state.body_search.current {
+        usize::MAX => matches.len() - 1,
+        it => it % matches.len(),
+    };
+    let line = body[..matches[current].start].matches('\n').count();
+    let line_count = body.matches('\n').count();
+    state.body_search.current = current;
+
+    scrollable::snap_to(
+        scrollable::Id::new("response_body"),
+        RelativeOffset {
+            x: 0.0,
+            y: if line_count == 0 {
+                0.0
+            } else {
+                line as f32 / line_count as f32
+            },
+        },
+    )
+}
+
+pub fn message_download(state: &mut State, message: MDownload) -> Task<Message> {
+    match message {
+        MDownload::Pick => {
+            let url = format_url_from_state(state);
+            Task::perform(pick_download_file(url), |path| match path {
+                Some(path) => Message::Download(MDownload::Start(path)),
+                None => Message::ClearErrorMessage,
+            })
+        }
+        MDownload::Start(path) => {
+            if state.auth.kind == AuthKind::OAuth2 && state.auth.oauth.fresh_token().is_none() {
+                return update(
+                    state,
+                    Message::GotError(MyErr::Client(
+                        "Get an OAuth token before downloading".to_string(),
+                    )),
+                );
+            }
+            let body = match body_from_state(state) {
+                Ok(body) => body,
+                Err(err) => return update(state, Message::GotError(err)),
+            };
+            let (url, mut headers, body, variables) = match prepare_request(state, body, &[]) {
+                Ok(request) => request,
+                Err(err) => return update(state, Message::GotError(err)),
+            };
+            let method = method_from_state(state);
+            let settings = send_settings(state);
+            settings
+                .auth
+                .sign(http_method(method).as_str(), &url, &mut headers, &body);
+            state.can_send = false;
+            state.download = Some(Download {
+                path: path.clone(),
+                method,
+                received: 0,
+                total: None,
+                started: Instant::now(),
+            });
+            let (task, handle) = Task::run(
+                download_to_file(url, headers, method, body, variables, settings, path),
+                Message::Download,
+            )
+            .abortable();
+            state.request_handle = Some(handle);
+            task
+        }
+        MDownload::Progress(received, total) => {
+            if let Some(download) = &mut state.download {
+                download.received = received;
+                download.total = total;
+            }
+            Task::none()
+        }
+        // The aborted stream can't clean up after itself, so the partial file is removed here
+        MDownload::Cancel => {
+            if let Some(handle) = state.request_handle.take() {
+                handle.abort();
+            }
+            state.can_send = true;
+            match state.download.take() {
+                Some(download) => Task::perform(
+                    async move { tokio::fs::remove_file(download.path).await },
+                    |_| Message::ClearErrorMessage,
+                ),
+                None => Task::none(),
+            }
+        }
+        MDownload::Done(result) => match (state.download.take(), result) {
+            (Some(download), Ok(response)) => update(
+                state,
+                Message::GotResponse(response, download.method, false),
+            ),
+            (_, Err(err)) => update(state, Message::GotError(err)),
+            (None, Ok(_)) => Task::none(),
+        },
+    }
+}
+
+pub fn message_sse(state: &mut State, message: MSse) -> Task<Message> {
+    match message {
+        MSse::Opened(code, method, is_draft, request_body) => match &mut state.sse {
+            Some(sse) => sse.status = SseStatus::Live,
+            None => {
+                state.sse = Some(SseSession {
+                    code,
+                    method,
+                    is_draft,
+                    status: SseStatus::Live,
+                    events: vec![],
+                    request_body,
+                })
+            }
+        },
+        MSse::Event(event) => {
+            if let Some(sse) = &mut state.sse {
+                sse.events.push(event);
+            }
+        }
+        MSse::Disconnected => {
+            if let Some(sse) = &mut state.sse {
+                sse.status = SseStatus::Reconnecting;
+            }
+        }
+        MSse::Ended => {
+            if let Some(sse) = &mut state.sse {
+                sse.status = SseStatus::Ended;
+            }
+        }
+        MSse::Stop => {
+            if let Some(handle) = state.request_handle.take() {
+                handle.abort();
+            }
+            if let Some(sse) = state.sse.take() {
+                let text = event_log(&sse.events);
+                let response = Response {
+                    body: text.as_bytes().to_vec(),
+                    text,
+                    code: sse.code,
+                    content_type: "text/event-stream".to_string(),
+                    received_time: Utc::now().naive_utc(),
+                    request: Request {
+                        body: sse.request_body,
+                        ..Request::default()
+                    },
+                    ..Response::default()
+                };
+                return update(
+                    state,
+                    Message::GotResponse(Box::new(response), sse.method, sse.is_draft),
+                );
+            }
+            state.can_send = true;
+        }
+    }
+    Task::none()
+}
+
+pub fn message_ws(state: &mut State, message: MWs) -> Task<Message> {
+    match message {
+        MWs::Connect => {
+            state.can_send = false;
+            let (url, headers) = authorized_request(state);
+            Task::run(connect_ws(url, headers, state.tls.clone()), |message| {
+                message
+            })
+        }
+        MWs::Connected(code, sender) => {
+            state.can_send = true;
+            let (endpoint_id, response_id) = create_ws_session(state, code);
+            state.ws = Some(WsConnection {
+                response_id,
+                sender,
+                frames: vec![],
+            });
+            state.draft = "".to_string();
+            Task::batch([
+                update(state, Message::ClickEndpoint(endpoint_id)),
+                focus("ws_composer"),
+            ])
+        }
+        MWs::Received(frame) => {
+            save_ws_frame(state, frame);
+            Task::none()
+        }
+        MWs::Closed(err) => {
+            state.can_send = true;
+            state.ws = None;
+            // Frames read while the session was live are incomplete
+            state.loaded_body = None;
+            if let Some(err) = err {
+                state.error_message = Some(err.to_string());
+            }
+            update(state, Message::RefetchDb)
+        }
+        MWs::Disconnect => {
+            if let Some(ws) = &state.ws {
+                let _ = ws.sender.unbounded_send(WsMessage::Close(None));
+            }
+            Task::none()
+        }
+        MWs::SetComposer(content) => {
+            state.ws_composer = content;
+            Task::none()
+        }
+        MWs::ToggleBinary => {
+            state.ws_binary = !state.ws_binary;
+            Task::none()
+        }
+        MWs::Send => {
+            let Some(ws) = &state.ws else {
+                return Task::none();
+            };
+            let (message, data) = if state.ws_binary {
+                match parse_hex(&state.ws_composer) {
+                    Ok(data) => (WsMessage::Binary(data.clone().into()), data),
+                    Err(err) => return update(state, Message::GotError(MyErr::Client(err))),
+                }
+            } else {
+                (
+                    WsMessage::Text(state.ws_composer.clone().into()),
+                    state.ws_composer.as_bytes().to_vec(),
+                )
+            };
+            if ws.sender.unbounded_send(message).is_ok() {
+                let frame = WsFrame {
+                    outgoing: true,
+                    binary: state.ws_binary,
+                    data,
+                    time: Utc::now().naive_utc(),
+                };
+                save_ws_frame(state, frame);
+                state.ws_composer.clear();
+            }
+            Task::none()
+        }
+    }
+}
+
+fn save_ws_frame(state: &mut State, frame: WsFrame) {
+    if let Some(ws) = &mut state.ws {
+        create_ws_frame(&get_db().lock().unwrap(), ws.response_id, &frame).unwrap();
+        ws.frames.push(frame);
+    }
+}
+
+pub fn message_graphql(state: &mut State, message: MGraphQl) -> Task<Message> {
+    match message {
+        MGraphQl::EditQuery(action) => state.graphql_query.perform(action),
+        MGraphQl::EditVariables(action) => state.graphql_variables.perform(action),
+        MGraphQl::SelectOperation(operation) => state.graphql_operation = Some(operation),
+        MGraphQl::FetchSchema => {
+            let (url, headers) = authorized_request(state);
+            let key = schema_key(&url);
+            return Task::perform(
+                fetch_schema(url, headers, client_settings(state)),
+                move |res| match res {
+                    Ok(schema) => Message::GraphQl(MGraphQl::GotSchema(key.clone(), schema)),
+                    Err(err) => Message::GotError(err),
+                },
+            );
+        }
+        MGraphQl::GotSchema(url, schema) => {
+            state.graphql_schemas.insert(url, schema);
+        }
+        MGraphQl::Complete(suffix) => state
+            .graphql_query
+            .perform(Action::Edit(Edit::Paste(Arc::new(suffix)))),
+    }
+    Task::none()
+}
+
+pub fn message_grpc(state: &mut State, message: MGrpc) -> Task<Message> {
+    match message {
+        MGrpc::EditMessage(action) => state.grpc_message.perform(action),
+        MGrpc::SelectMethod(path) => {
+            let (origin, _) = split_url(&format_url_from_state(state));
+            let url = format!("{}/{}", origin, path);
+            if let Some(method) = state
+                .grpc_pools
+                .get(&origin)
+                .and_then(|it| find_method(it, &path))
+            {
+                state.grpc_message = text_editor::Content::with_text(&template(&method));
+            }
+            match state.selected_endpoint {
+                Some(id) => {
+                    update_endpoint_url(&get_db().lock().unwrap(), id, &url).unwrap();
+                    return update(state, Message::RefetchDb);
+                }
+                None => state.draft = url,
+            }
+        }
+        MGrpc::Reflect => {
+            let (origin, _) = split_url(&format_url_from_state(state));
+            let tls = state.tls.clone();
+            return Task::perform(
+                async move { (reflect(&origin, &tls).await, origin) },
+                |(res, origin)| match res {
+                    Ok(pool) => Message::Grpc(MGrpc::GotPool(origin, pool, vec![])),
+                    Err(err) => Message::GotError(err),
+                },
+            );
+        }
+        MGrpc::PickProtos => {
+            let (origin, _) = split_url(&format_url_from_state(state));
+            return Task::perform(pick_protos(), move |res| match res {
+                Some(Ok((pool, paths))) => {
+                    Message::Grpc(MGrpc::GotPool(origin.clone(), pool, paths))
+                }
+                Some(Err(err)) => Message::GotError(MyErr::Client(err)),
+                None => Message::ClearErrorMessage,
+            });
+        }
+        MGrpc::GotPool(origin, pool, paths) => {
+            state.grpc_pools.insert(origin, pool);
+            if !paths.is_empty() && paths != state.proto_paths {
+                state.proto_paths = paths;
+                if let Some(id) = state.selected_endpoint {
+                    update_endpoint_proto_paths(&get_db().lock().unwrap(), id, &state.proto_paths)
+                        .unwrap();
+                    return update(state, Message::RefetchDb);
+                }
+            }
+        }
+    }
+    Task::none()
+}
+
+pub fn message_rpc(state: &mut State, message: MRpc) -> Task<Message> {
+    match message {
+        MRpc::AddCall => state.rpc_calls.push(RpcCall::default()),
+        MRpc::DeleteCall(index) => {
+            if index < state.rpc_calls.len() {
+                state.rpc_calls.remove(index);
+            }
+        }
+        MRpc::SetMethod(index, method) => {
+            if let Some(call) = state.rpc_calls.get_mut(index) {
+                call.method = method;
+            }
+        }
+        MRpc::EditParams(index, action) => {
+            if let Some(call) = state.rpc_calls.get_mut(index) {
+                call.params.perform(action);
+            }
+        }
+    }
+    Task::none()
+}
+
+pub fn message_soap(state: &mut State, message: MSoap) -> Task<Message> {
+    match message {
+        MSoap::SetWsdlUrl(url) => state.soap_wsdl_url = url,
+        MSoap::FetchWsdl => {
+            let key = schema_key(&format_url_from_state(state));
+            let url = match state.soap_wsdl_url.trim() {
+                "" => format!("{}?wsdl", key),
+                it => it.to_string(),
+            };
+            let (_, headers) = authorized_request(state);
+            return Task::perform(
+                fetch_wsdl(url, headers, client_settings(state)),
+                move |res| match res {
+                    Ok(wsdl) => Message::Soap(MSoap::GotWsdl(key.clone(), wsdl)),
+                    Err(err) => Message::GotError(err),
+                },
+            );
+        }
+        MSoap::PickWsdl => {
+            let key = schema_key(&format_url_from_state(state));
+            return Task::perform(pick_wsdl(), move |res| match res {
+                Some(Ok(wsdl)) => Message::Soap(MSoap::GotWsdl(key.clone(), wsdl)),
+                Some(Err(err)) => Message::GotError(err),
+                None => Message::ClearErrorMessage,
+            });
+        }
+        MSoap::GotWsdl(mut key, wsdl) => {
+            // A new request takes the service address from the WSDL
+            if let Some(location) = &wsdl.location
+                && state.selected_endpoint.is_none()
+                && state.draft.trim().is_empty()
+            {
+                state.draft = location.clone();
+                key = schema_key(location);
+            }
+            state.soap_services.insert(key, wsdl);
+        }
+        MSoap::SelectOperation(name) => {
+            let Some(operation) = state
+                .soap_services
+                .get(&schema_key(&format_url_from_state(state)))
+                .and_then(|it| it.operations.iter().find(|it| it.name == name))
+                .cloned()
+            else {
+                return Task::none();
+            };
+            state.soap_operation = Some(name);
+            state.soap_envelope = text_editor::Content::with_text(&operation.envelope);
+            let mut saved = set_request_header(state, "Content-Type", &operation.content_type());
+            if !operation.soap12 {
+                saved |=
+                    set_request_header(state, "SOAPAction", &format!("\"{}\"", operation.action));
+            }
+            if saved {
+                return update(state, Message::RefetchDb);
+            }
+        }
+        MSoap::EditEnvelope(action) => state.soap_envelope.perform(action),
+    }
+    Task::none()
+}
+
+pub fn message_auth(state: &mut State, message: MAuth) -> Task<Message> {
+    match message {
+        MAuth::SetKind(kind) => state.auth.kind = kind,
+        MAuth::SetUsername(username) => state.auth.username = username,
+        MAuth::SetPassword(password) => state.auth.password = password,
+        MAuth::SetToken(token) => state.auth.token = token,
+        MAuth::SetKeyName(name) => state.auth.key_name = name,
+        MAuth::SetKeyValue(value) => state.auth.key_value = value,
+        MAuth::ToggleKeyInQuery => state.auth.key_in_query = !state.auth.key_in_query,
+        MAuth::ToggleSecrets => {
+            state.show_secrets = !state.show_secrets;
+            return Task::none();
+        }
+        MAuth::FetchToken => return fetch_oauth_token(state, None),
+        MAuth::GotToken(id, token, resend) => {
+            state.can_send = true;
+            // Another endpoint was selected meanwhile, the token belongs to the one it was
+            // fetched for and the waiting send is dropped
+            if id != state.selected_endpoint {
+                let Some(endpoint) = state.endpoints.iter_mut().find(|it| Some(it.id) == id) else {
+                    return Task::none();
+                };
+                endpoint.auth.oauth.token = Some(token);
+                update_endpoint_auth(&get_db().lock().unwrap(), endpoint.id, &endpoint.auth)
+                    .unwrap();
+                return Task::none();
+            }
+            state.auth.oauth.token = Some(token);
+            let saved = save_auth(state);
+            return match resend {
+                Some(is_draft) => Task::batch([saved, send_now(state, is_draft)]),
+                None => saved,
+            };
+        }
+        MAuth::ClearToken => state.auth.oauth.token = None,
+        MAuth::SetSigning(field, value) => {
+            let (aws, hmac) = (&mut state.auth.aws, &mut state.auth.hmac);
+            match field {
+                SigningField::AccessKey => aws.access_key = value,
+                SigningField::SecretKey => aws.secret_key = value,
+                SigningField::Region => aws.region = value,
+                SigningField::Service => aws.service = value,
+                SigningField::SessionToken => aws.session_token = value,
+                SigningField::HmacSecret => hmac.secret = value,
+                SigningField::HmacHeader => hmac.header = value,
+                SigningField::HmacCanonical => hmac.canonical = value,
+                SigningField::HmacTimestampHeader => hmac.timestamp_header = value,
+            }
+        }
+        MAuth::ToggleHmacBase64 => state.auth.hmac.base64 = !state.auth.hmac.base64,
+        // A token is only good for the settings it was issued with
+        config => {
+            let oauth = &mut state.auth.oauth;
+            match config {
+                MAuth::SetGrant(grant) => oauth.grant = grant,
+                MAuth::SetTokenUrl(url) => oauth.token_url = url,
+                MAuth::SetAuthorizeUrl(url) => oauth.authorize_url = url,
+                MAuth::SetClientId(id) => oauth.client_id = id,
+                MAuth::SetClientSecret(secret) => oauth.client_secret = secret,
+                MAuth::SetScope(scope) => oauth.scope = scope,
+                _ => {}
+            }
+            oauth.token = None;
+        }
+    }
+    save_auth(state)
+}
+
+fn save_auth(state: &mut State) -> Task<Message> {
+    match state.selected_endpoint {
+        Some(id) => {
+            update_endpoint_auth(&get_db().lock().unwrap(), id, &state.auth).unwrap();
+            update(state, Message::RefetchDb)
+        }
+        None => Task::none(),
+    }
+}
+
+pub fn message_script(state: &mut State, message: MScript) -> Task<Message> {
+    let is_edit = match message {
+        MScript::EditPreRequest(action) => {
+            let is_edit = action.is_edit();
+            state.pre_script.perform(action);
+            is_edit
+        }
+        MScript::EditPostResponse(action) => {
+            let is_edit = action.is_edit();
+            state.post_script.perform(action);
+            is_edit
+        }
+    };
+    match state.selected_endpoint {
+        Some(id) if is_edit => {
+            update_endpoint_scripts(
+                &get_db().lock().unwrap(),
+                id,
+                &script_text(&state.pre_script),
+                &script_text(&state.post_script),
+            )
+            .unwrap();
+            update(state, Message::RefetchDb)
+        }
+        _ => Task::none(),
+    }
+}
+
+pub fn message_data(state: &mut State, message: MData) -> Task<Message> {
+    match message {
+        MData::PickFile => {
+            return Task::perform(pick_data_file(), |res| match res {
+                Some(Ok((file, rows))) => Message::Data(MData::Loaded(file, rows)),
+                Some(Err(err)) => Message::GotError(err),
+                None => Message::ClearErrorMessage,
+            });
+        }
+        MData::Loaded(file, rows) => {
+            state.data_run = Some(DataRun {
+                file,
+                rows,
+                ..DataRun::default()
+            })
+        }
+        MData::Run => {
+            if state.auth.kind == AuthKind::OAuth2 && state.auth.oauth.fresh_token().is_none() {
+                return update(
+                    state,
+                    Message::GotError(MyErr::Client(
+                        "Get an OAuth token before running the data file".to_string(),
+                    )),
+                );
+            }
+            if let Some(run) = &mut state.data_run {
+                run.results.clear();
+                run.running = true;
+                state.can_send = false;
+                state.error_message = None;
+                return send_row(state, 0);
+            }
+        }
+        MData::Stop => {
+            if let Some(handle) = state.request_handle.take() {
+                handle.abort();
+            }
+            if let Some(run) = &mut state.data_run {
+                run.running = false;
+            }
+            state.can_send = true;
+        }
+        MData::RowDone(index, result, duration) => {
+            let (Some(endpoint_id), Some(row)) = (
+                state.selected_endpoint,
+                state
+                    .data_run
+                    .as_ref()
+                    .filter(|it| it.running)
+                    .and_then(|it| it.rows.get(index))
+                    .cloned(),
+            ) else {
+                state.can_send = true;
+                return Task::none();
+            };
+            let mut result = *result;
+            let script = script_text(&state.post_script);
+            if let Ok(response) = &mut result
+                && !script.is_empty()
+            {
+                response.tests = with_row(state, &row, |variables| {
+                    run_post_response(
+                        &script,
+                        response.code.as_u16(),
+                        &response.headers,
+                        &response.text,
+                        variables,
+                    )
+                });
+            }
+            let response_id = result.as_ref().ok().map(|response| {
+                let request = request_from_state(state);
+                let conn = get_db().lock().unwrap();
+                let id = create_response(&conn, endpoint_id, response).unwrap();
+                for q in request.query_params.iter().filter(|it| it.on) {
+                    create_query_param(&conn, id, &q.key, &q.value).unwrap();
+                }
+                for h in request.headers.iter().filter(|it| it.on) {
+                    create_header(&conn, id, &h.key, &h.value).unwrap();
+                }
+                id
+            });
+            let tests = result
+                .as_ref()
+                .map(|it| it.tests.clone())
+                .unwrap_or_default();
+            let Some(run) = &mut state.data_run else {
+                return Task::none();
+            };
+            run.results.push(RowResult {
+                response_id,
+                status: result.map(|it| it.code).map_err(|it| it.to_string()),
+                duration,
+                tests_passed: tests.iter().filter(|it| it.passed).count(),
+                tests_total: tests.len(),
+            });
+            let next = if index + 1 < run.rows.len() {
+                send_row(state, index + 1)
+            } else {
+                run.running = false;
+                state.can_send = true;
+                Task::none()
+            };
+            return Task::batch([next, update(state, Message::RefetchDb)]);
+        }
+        MData::ShowResponse(id) => {
+            let index = current_endpoint(state)
+                .and_then(|endpoint| endpoint.responses.iter().position(|it| it.id == id));
+            if let Some(index) = index {
+                return update(state, Message::SetSelectedResponseIndex(index));
+            }
+        }
+        MData::Clear => state.data_run = None,
+    }
+    Task::none()
+}
+
+pub fn message_load(state: &mut State, message: MLoad) -> Task<Message> {
+    let form = &mut state.load_form;
+    match message {
+        MLoad::SetLimit(limit) => form.limit = limit,
+        MLoad::SetAmount(amount) => form.amount = amount,
+        MLoad::SetConcurrency(concurrency) => form.concurrency = concurrency,
+        MLoad::SetRate(rate) => form.rate = rate,
+        MLoad::Start => return start_load_test(state),
+        MLoad::Stop => {
+            if let Some(load) = &state.load {
+                load.stop.store(true, Ordering::Relaxed);
+            }
+        }
+        MLoad::Progress(report) => {
+            if let Some(load) = &mut state.load {
+                load.report = report;
+            }
+        }
+        MLoad::Done(report) => {
+            state.can_send = true;
+            if let Some(load) = state.load.take() {
+                let run = LoadRun {
+                    id: 0,
+                    settings: load.settings,
+                    report,
+                    started_time: load.started_time,
+                };
+                let id =
+                    create_load_run(&get_db().lock().unwrap(), load.endpoint_id, &run).unwrap();
+                if state.selected_endpoint == Some(load.endpoint_id) {
+                    state.selected_load_run = Some(id);
+                }
+                return update(state, Message::RefetchDb);
+            }
+        }
+        MLoad::ShowRun(id) => {
+            state.selected_load_run = (state.selected_load_run != Some(id)).then_some(id)
+        }
+        MLoad::DeleteRun(id) => {
+            delete_load_run(&get_db().lock().unwrap(), id).unwrap();
+            if state.selected_load_run == Some(id) {
+                state.selected_load_run = None;
+            }
+            return update(state, Message::RefetchDb);
+        }
+    }
+    Task::none()
+}
+
+pub fn message_retry(state: &mut State, message: MRetry) -> Task<Message> {
+    let retry = &mut state.retry;
+    // Cleared inputs read as 0, the policy then sends once
+    let number = |text: String| text.trim().parse::<u64>().unwrap_or_default();
+    match message {
+        MRetry::SetMaxAttempts(attempts) => retry.max_attempts = number(attempts).min(100) as u32,
+        MRetry::ToggleOn5xx => retry.on_5xx = !retry.on_5xx,
+        MRetry::ToggleOn429 => retry.on_429 = !retry.on_429,
+        MRetry::ToggleOnNetwork => retry.on_network = !retry.on_network,
+        MRetry::SetBackoff(backoff) => retry.backoff = backoff,
+        MRetry::SetDelay(delay) => retry.delay_ms = number(delay),
+        MRetry::ToggleJitter => retry.jitter = !retry.jitter,
+        MRetry::ToggleRetryAfter => retry.retry_after = !retry.retry_after,
+    }
+    match state.selected_endpoint {
+        Some(id) => {
+            update_endpoint_retry(&get_db().lock().unwrap(), id, &state.retry).unwrap();
+            update(state, Message::RefetchDb)
+        }
+        None => Task::none(),
+    }
+}
+
+pub fn message_proxy(state: &mut State, message: MProxy) -> Task<Message> {
+    let (MProxy::SetMode(scope, _) | MProxy::SetScheme(scope, _) | MProxy::EditField(scope, _, _)) =
+        message;
+    let proxy = match scope {
+        ProxyScope::Global => &mut state.proxy,
+        ProxyScope::Endpoint => &mut state.endpoint_proxy,
+    };
+    match message {
+        MProxy::SetMode(_, mode) => proxy.mode = mode,
+        MProxy::SetScheme(_, scheme) => proxy.scheme = scheme,
+        MProxy::EditField(_, field, value) => proxy.set_field(field, value),
+    }
+    match (scope, state.selected_endpoint) {
+        (ProxyScope::Global, _) => {
+            set_setting(&get_db().lock().unwrap(), "proxy", &state.proxy.to_json()).unwrap();
+            Task::none()
+        }
+        (ProxyScope::Endpoint, Some(id)) => {
+            update_endpoint_proxy(&get_db().lock().unwrap(), id, &state.endpoint_proxy).unwrap();
+            update(state, Message::RefetchDb)
+        }
+        (ProxyScope::Endpoint, None) => Task::none(),
+    }
+}
+
+pub fn message_tls(state: &mut State, message: MTls) -> Task<Message> {
+    let picked = |file: Option<String>, message: fn(String) -> MTls| match file {
+        Some(file) => Message::Tls(message(file)),
+        None => Message::ClearErrorMessage,
+    };
+    match message {
+        MTls::SetMinVersion(version) => state.tls.min_version = version,
+        MTls::ToggleAcceptInvalid => state.tls.accept_invalid = !state.tls.accept_invalid,
+        MTls::PickCa => {
+            return Task::perform(
+                pick_tls_file("PEM certificates", &["pem", "crt", "cer"]),
+                move |file| picked(file, MTls::AddCa),
+            );
+        }
+        MTls::AddCa(file) => {
+            if !state.tls.ca_files.contains(&file) {
+                state.tls.ca_files.push(file)
+            }
+        }
+        MTls::RemoveCa(index) => {
+            if index < state.tls.ca_files.len() {
+                state.tls.ca_files.remove(index);
+            }
+        }
+        MTls::PickClientCert => {
+            return Task::perform(
+                pick_tls_file("PEM or PKCS#12", &["pem", "crt", "cer", "p12", "pfx"]),
+                move |file| picked(file, MTls::AddClientCert),
+            );
+        }
+        MTls::AddClientCert(cert_file) => {
+            // Starts out for the host of the current request
+            let host = Url::parse(&format_url_from_state(state))
+                .ok()
+                .and_then(|it| it.host_str().map(str::to_string))
+                .unwrap_or_default();
+            state.tls.client_certs.push(ClientCert {
+                host,
+                cert_file,
+                ..ClientCert::default()
+            })
+        }
+        MTls::PickKey(index) => {
+            return Task::perform(
+                pick_tls_file("PEM keys", &["pem", "key"]),
+                move |file| match file {
+                    Some(file) => Message::Tls(MTls::SetKey(index, file)),
+                    None => Message::ClearErrorMessage,
+                },
+            );
+        }
+        // A picker can answer after its certificate was removed
+        MTls::SetKey(index, file) => {
+            if let Some(cert) = state.tls.client_certs.get_mut(index) {
+                cert.key_file = file;
+            }
+        }
+        MTls::SetCertHost(index, host) => {
+            if let Some(cert) = state.tls.client_certs.get_mut(index) {
+                cert.host = host;
+            }
+        }
+        MTls::SetCertPassword(index, password) => {
+            if let Some(cert) = state.tls.client_certs.get_mut(index) {
+                cert.password = password;
+            }
+        }
+        MTls::RemoveClientCert(index) => {
+            if index < state.tls.client_certs.len() {
+                state.tls.client_certs.remove(index);
+            }
+        }
+    }
+    set_setting(&get_db().lock().unwrap(), "tls", &state.tls.to_json()).unwrap();
+    Task::none()
+}
+
+pub fn message_redirect(state: &mut State, message: MRedirect) -> Task<Message> {
+    let redirect = &mut state.redirect;
+    match message {
+        MRedirect::SetMode(mode) => redirect.mode = mode,
+        MRedirect::SetMaxHops(hops) => {
+            redirect.max_hops = hops.trim().parse::<u32>().unwrap_or_default().min(50)
+        }
+        MRedirect::ToggleKeepAuth => redirect.keep_auth = !redirect.keep_auth,
+    }
+    match state.selected_endpoint {
+        Some(id) => {
+            update_endpoint_redirect(&get_db().lock().unwrap(), id, &state.redirect).unwrap();
+            update(state, Message::RefetchDb)
+        }
+        None => Task::none(),
+    }
+}
@@ -0,0 +1,132 @@
+use encoding_rs::{Encoding, UTF_8};
+use mime_sniffer::{HttpRequest, MimeTypeSniffer};
+
+// The Content-Type header wins when the server sent one, otherwise the body is sniffed.
+pub fn detect_content_type(header: Option<&str>, url: &str, body: &[u8]) -> String {
+    match header.map(str::trim).filter(|it| !it.is_empty()) {
+        Some(content_type) => content_type.to_string(),
+        None => sniff(url, body),
+    }
+}
+
+// "Text/HTML; charset=UTF-8" -> "text/html"
+pub fn essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+pub fn charset(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        if key.trim().eq_ignore_ascii_case("charset") {
+            Some(value.trim().trim_matches('"'))
+        } else {
+            None
+        }
+    })
+}
+
+// Anything that cannot be shown as text is kept as raw bytes only
+pub fn is_binary(content_type: &str) -> bool {
+    let essence = essence(content_type);
+    let textual = essence.starts_with("text/")
+        || [
+            "json",
+            "xml",
+            "yaml",
+            "javascript",
+            "ecmascript",
+            "graphql",
+            "x-www-form-urlencoded",
+        ]
+        .iter()
+        .any(|it| essence.contains(it));
+    !essence.is_empty() && !textual
+}
+
+pub fn is_event_stream(content_type: &str) -> bool {
+    essence(content_type) == "text/event-stream"
+}
+
+pub fn is_image(content_type: &str) -> bool {
+    let essence = essence(content_type);
+    essence.starts_with("image/") && !essence.contains("svg")
+}
+
+pub fn extension(content_type: &str) -> &'static str {
+    match essence(content_type).as_str() {
+        "text/html" => "html",
+        "text/css" => "css",
+        "text/csv" => "csv",
+        "text/javascript" | "application/javascript" => "js",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/bmp" => "bmp",
+        "image/x-icon" | "image/vnd.microsoft.icon" => "ico",
+        "image/svg+xml" => "svg",
+        "application/pdf" => "pdf",
+        "application/zip" => "zip",
+        "application/gzip" | "application/x-gzip" => "gz",
+        "application/wasm" => "wasm",
+        it if it.contains("json") => "json",
+        it if it.contains("xml") => "xml",
+        it if it.contains("yaml") => "yaml",
+        it if it.starts_with("text/") => "txt",
+        _ => "bin",
+    }
+}
+
+pub fn decode_body(body: &[u8], content_type: &str) -> String {
+    let encoding = charset(content_type)
+        .and_then(|it| Encoding::for_label(it.as_bytes()))
+        .unwrap_or(UTF_8);
+    let (text, _, _) = encoding.decode(body);
+    text.into_owned()
+}
+
+fn sniff(url: &str, body: &[u8]) -> String {
+    let request = HttpRequest {
+        content: &body,
+        url: &url,
+        type_hint: "",
+    };
+    match request.sniff_mime_type() {
+        // The sniffer reports an empty type for anything that looks like plain text
+        Some(mime) if !mime.is_empty() && mime != "text/plain" => mime.to_string(),
+        _ => sniff_text(body).to_string(),
+    }
+}
+
+// mime-sniffer does not know about structured text formats, so they are told apart here.
+fn sniff_text(body: &[u8]) -> &'static str {
+    let Ok(content) = std::str::from_utf8(body) else {
+        return "application/octet-stream";
+    };
+    let trimmed = content.trim();
+
+    if trimmed.is_empty() {
+        return "text/plain";
+    }
+    if serde_json::from_str::<serde_json::Value>(trimmed).is_ok() {
+        return "application/json";
+    }
+    if trimmed.starts_with("<?xml") || trimmed.starts_with('<') && trimmed.contains("</") {
+        if trimmed.contains("<!DOCTYPE html") || trimmed.contains("<html") {
+            return "text/html";
+        }
+        if trimmed.contains("<svg") {
+            return "image/svg+xml";
+        }
+        return "application/xml";
+    }
+    if trimmed.starts_with("---") {
+        return "application/yaml";
+    }
+    "text/plain"
+}
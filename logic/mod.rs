@@ -1,5 +1,29 @@
+pub mod auth;
+pub mod client;
 pub mod common;
+pub mod connection;
 pub mod crud;
+pub mod data;
 pub mod db;
+pub mod filter;
+pub mod format;
+pub mod graphql;
+pub mod grpc;
+pub mod hex;
+pub mod highlight;
+pub mod jsonrpc;
+pub mod load;
 pub mod message_handlers;
+pub mod mime;
+pub mod oauth;
+pub mod proxy;
+pub mod redirect;
+pub mod retry;
+pub mod script;
+pub mod search;
+pub mod signing;
+pub mod soap;
+pub mod sse;
+pub mod tls;
 pub mod ui;
+pub mod variables;
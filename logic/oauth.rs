@@ -0,0 +1,538 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::Utc;
+use rand::RngCore;
+use reqwest::Url;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::logic::client::ClientSettings;
+
+// Tokens are renewed this many seconds before they run out
+const EXPIRY_MARGIN: i64 = 30;
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Grant {
+    #[default]
+    ClientCredentials,
+    Password,
+    AuthorizationCode,
+}
+
+impl Grant {
+    pub const ALL: [Grant; 3] = [
+        Grant::ClientCredentials,
+        Grant::Password,
+        Grant::AuthorizationCode,
+    ];
+}
+
+impl Display for Grant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Grant::ClientCredentials => "Client credentials",
+            Grant::Password => "Password",
+            Grant::AuthorizationCode => "Authorization code + PKCE",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub access_token: String,
+    pub token_type: String,
+    pub refresh_token: Option<String>,
+    // Unix seconds, None when the server didn't say
+    pub expires_at: Option<i64>,
+}
+
+impl Token {
+    pub fn is_fresh(&self) -> bool {
+        self.expires_at
+            .is_none_or(|it| it - EXPIRY_MARGIN > Utc::now().timestamp())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OAuth {
+    pub grant: Grant,
+    pub token_url: String,
+    pub authorize_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: String,
+    pub token: Option<Token>,
+}
+
+impl OAuth {
+    pub fn fresh_token(&self) -> Option<&Token> {
+        self.token.as_ref().filter(|it| it.is_fresh())
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "grant": match self.grant {
+                Grant::ClientCredentials => "client_credentials",
+                Grant::Password => "password",
+                Grant::AuthorizationCode => "authorization_code",
+            },
+            "token_url": self.token_url,
+            "authorize_url": self.authorize_url,
+            "client_id": self.client_id,
+            "client_secret": self.client_secret,
+            "scope": self.scope,
+            "token": self.token.as_ref().map(|it| json!({
+                "access_token": it.access_token,
+                "token_type": it.token_type,
+                "refresh_token": it.refresh_token,
+                "expires_at": it.expires_at,
+            })),
+        })
+    }
+
+    pub fn from_json(value: &Value) -> OAuth {
+        let field = |name: &str| value[name].as_str().unwrap_or_default().to_string();
+        let token = &value["token"];
+        OAuth {
+            grant: match value["grant"].as_str() {
+                Some("password") => Grant::Password,
+                Some("authorization_code") => Grant::AuthorizationCode,
+                _ => Grant::ClientCredentials,
+            },
+            token_url: field("token_url"),
+            authorize_url: field("authorize_url"),
+            client_id: field("client_id"),
+            client_secret: field("client_secret"),
+            scope: field("scope"),
+            token: token["access_token"].as_str().map(|access_token| Token {
+                access_token: access_token.to_string(),
+                token_type: token["token_type"].as_str().unwrap_or("Bearer").to_string(),
+                refresh_token: token["refresh_token"].as_str().map(str::to_string),
+                expires_at: token["expires_at"].as_i64(),
+            }),
+        }
+    }
+}
+
+// Refreshes the cached token when possible, otherwise runs the grant from the start
+pub async fn fetch_token(
+    oauth: OAuth,
+    username: String,
+    password: String,
+    settings: ClientSettings,
+) -> Result<Token, String> {
+    if oauth.token_url.trim().is_empty() {
+        return Err("OAuth 2.0 needs a token URL".to_string());
+    }
+    if let Some(refresh_token) = oauth.token.as_ref().and_then(|it| it.refresh_token.clone()) {
+        let refreshed = request_token(
+            &oauth,
+            &settings,
+            vec![
+                ("grant_type", "refresh_token".to_string()),
+                ("refresh_token", refresh_token.clone()),
+            ],
+        )
+        .await;
+        // An expired or revoked refresh token falls back to the grant
+        if let Ok(mut token) = refreshed {
+            token.refresh_token = token.refresh_token.or(Some(refresh_token));
+            return Ok(token);
+        }
+    }
+    let mut form = match oauth.grant {
+        Grant::ClientCredentials => vec![("grant_type", "client_credentials".to_string())],
+        Grant::Password => vec![
+            ("grant_type", "password".to_string()),
+            ("username", username),
+            ("password", password),
+        ],
+        Grant::AuthorizationCode => return authorization_code(&oauth, &settings).await,
+    };
+    if !oauth.scope.trim().is_empty() {
+        form.push(("scope", oauth.scope.trim().to_string()));
+    }
+    request_token(&oauth, &settings, form).await
+}
+
+async fn request_token(
+    oauth: &OAuth,
+    settings: &ClientSettings,
+    mut form: Vec<(&str, String)>,
+) -> Result<Token, String> {
+    let client = settings
+        .client(oauth.token_url.trim())
+        .map_err(|it| it.to_string())?;
+    let mut builder = client.post(oauth.token_url.trim());
+    // Confidential clients authenticate with Basic, public ones only name themselves
+    if oauth.client_secret.is_empty() {
+        form.push(("client_id", oauth.client_id.clone()));
+    } else {
+        builder = builder.basic_auth(
+            urlencoding::encode(&oauth.client_id),
+            Some(urlencoding::encode(&oauth.client_secret)),
+        );
+    }
+    let resp = builder
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&form)
+        .send()
+        .await
+        .map_err(|it| format!("Token request failed: {}", it))?;
+    let status = resp.status();
+    let bytes = resp.bytes().await.unwrap_or_default();
+    let body: Value = serde_json::from_slice(&bytes).unwrap_or_default();
+    if let Some(error) = body["error"].as_str() {
+        return Err(match body["error_description"].as_str() {
+            Some(description) => format!("Token request failed: {} ({})", error, description),
+            None => format!("Token request failed: {}", error),
+        });
+    }
+    let Some(access_token) = body["access_token"].as_str() else {
+        return Err(format!(
+            "Token request failed: no access_token in the {} response",
+            status
+        ));
+    };
+    Ok(Token {
+        access_token: access_token.to_string(),
+        token_type: body["token_type"].as_str().unwrap_or("Bearer").to_string(),
+        refresh_token: body["refresh_token"].as_str().map(str::to_string),
+        expires_at: body["expires_in"]
+            .as_i64()
+            .or_else(|| body["expires_in"].as_str()?.parse().ok())
+            .map(|it| Utc::now().timestamp() + it),
+    })
+}
+
+fn random_string() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// Opens the login page in the browser and waits for it to redirect back to a loopback listener
+// (RFC 8252), then trades the code for a token with the PKCE verifier (RFC 7636)
+async fn authorization_code(oauth: &OAuth, settings: &ClientSettings) -> Result<Token, String> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|it| format!("Could not listen for the redirect: {}", it))?;
+    let port = listener.local_addr().map_err(|it| it.to_string())?.port();
+    let redirect_uri = format!("http://127.0.0.1:{}/callback", port);
+    let verifier = random_string();
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(&verifier));
+    let state = random_string();
+
+    let mut url = Url::parse(oauth.authorize_url.trim())
+        .map_err(|it| format!("Invalid authorization URL: {}", it))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &oauth.client_id)
+        .append_pair("redirect_uri", &redirect_uri)
+        .append_pair("code_challenge", &challenge)
+        .append_pair("code_challenge_method", "S256")
+        .append_pair("state", &state);
+    if !oauth.scope.trim().is_empty() {
+        url.query_pairs_mut()
+            .append_pair("scope", oauth.scope.trim());
+    }
+    open::that(url.as_str()).map_err(|it| format!("Could not open the browser: {}", it))?;
+
+    let code = tokio::time::timeout(LOGIN_TIMEOUT, wait_for_code(&listener, &state))
+        .await
+        .map_err(|_| "Timed out waiting for the login to finish".to_string())??;
+    request_token(
+        oauth,
+        settings,
+        vec![
+            ("grant_type", "authorization_code".to_string()),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("code_verifier", verifier),
+        ],
+    )
+    .await
+}
+
+async fn wait_for_code(listener: &TcpListener, state: &str) -> Result<String, String> {
+    loop {
+        let (mut stream, _) = listener.accept().await.map_err(|it| it.to_string())?;
+        let mut buffer = vec![0; 8192];
+        let read = stream.read(&mut buffer).await.unwrap_or_default();
+        let request = String::from_utf8_lossy(&buffer[..read]);
+        // GET /callback?code=...&state=... HTTP/1.1
+        let Some(path) = request.split_whitespace().nth(1) else {
+            continue;
+        };
+        let Ok(url) = Url::parse(&format!("http://127.0.0.1{}", path)) else {
+            continue;
+        };
+        if url.path() != "/callback" {
+            let _ = stream
+                .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+                .await;
+            continue;
+        }
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.to_string())
+        };
+        let result = match (param("code"), param("error")) {
+            _ if param("state").as_deref() != Some(state) => {
+                Err("The login redirect has a wrong state".to_string())
+            }
+            (Some(code), None) => Ok(code),
+            (_, error) => Err(format!(
+                "Login failed: {}",
+                param("error_description")
+                    .or(error)
+                    .unwrap_or("no code in the redirect".to_string())
+            )),
+        };
+        let page = match &result {
+            Ok(_) => "Signed in, you can close this tab and go back to interfere.".to_string(),
+            Err(err) => err.clone(),
+        };
+        let _ = stream
+            .write_all(
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    page.len(),
+                    page
+                )
+                .as_bytes(),
+            )
+            .await;
+        return result;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::proxy::{ProxyMode, ProxySettings};
+    use base64::engine::general_purpose::STANDARD;
+
+    // A stand-in authorization server, one connection per token request. Answers with the
+    // given status and JSON bodies in order and returns the requests it got.
+    async fn serve(
+        responses: Vec<(u16, &'static str)>,
+    ) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut requests = vec![];
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![];
+                let mut buffer = [0; 4096];
+                while !is_complete(&request) {
+                    let read = stream.read(&mut buffer).await.unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                requests.push(String::from_utf8(request).unwrap());
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    fn is_complete(request: &[u8]) -> bool {
+        let text = String::from_utf8_lossy(request);
+        let Some((head, body)) = text.split_once("\r\n\r\n") else {
+            return false;
+        };
+        header(head, "content-length").map_or(0, |it| it.parse().unwrap()) <= body.len()
+    }
+
+    fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+        request
+            .lines()
+            .filter_map(|it| it.split_once(':'))
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim())
+    }
+
+    fn form(request: &str) -> Vec<(String, String)> {
+        let body = request.split_once("\r\n\r\n").unwrap().1;
+        Url::parse(&format!("http://127.0.0.1/?{}", body))
+            .unwrap()
+            .query_pairs()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn param(form: &[(String, String)], name: &str) -> Option<String> {
+        form.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    // The environment's proxy must not get the loopback requests
+    fn settings() -> ClientSettings {
+        ClientSettings {
+            proxy: ProxySettings {
+                mode: ProxyMode::Direct,
+                ..ProxySettings::default()
+            },
+            ..ClientSettings::default()
+        }
+    }
+
+    fn oauth(token_url: String, grant: Grant, client_secret: &str) -> OAuth {
+        OAuth {
+            grant,
+            token_url,
+            client_id: "app".to_string(),
+            client_secret: client_secret.to_string(),
+            scope: "read write".to_string(),
+            ..OAuth::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn client_credentials_grant() {
+        let (url, server) = serve(vec![(
+            200,
+            r#"{"access_token":"abc","token_type":"bearer","expires_in":3600}"#,
+        )])
+        .await;
+        let oauth = oauth(url, Grant::ClientCredentials, "s3cret");
+        let token = fetch_token(oauth, String::new(), String::new(), settings())
+            .await
+            .unwrap();
+        assert_eq!(token.access_token, "abc");
+        assert_eq!(token.token_type, "bearer");
+        assert_eq!(token.refresh_token, None);
+        let expires_in = token.expires_at.unwrap() - Utc::now().timestamp();
+        assert!((3590..=3600).contains(&expires_in));
+        assert!(token.is_fresh());
+
+        let requests = server.await.unwrap();
+        let request = &requests[0];
+        assert!(request.starts_with("POST /token "));
+        assert_eq!(
+            header(request, "authorization"),
+            Some(format!("Basic {}", STANDARD.encode("app:s3cret")).as_str())
+        );
+        let form = form(request);
+        assert_eq!(
+            param(&form, "grant_type").as_deref(),
+            Some("client_credentials")
+        );
+        assert_eq!(param(&form, "scope").as_deref(), Some("read write"));
+        assert_eq!(param(&form, "client_id"), None);
+    }
+
+    #[tokio::test]
+    async fn password_grant() {
+        let (url, server) = serve(vec![(
+            200,
+            r#"{"access_token":"abc","refresh_token":"r1","expires_in":"60"}"#,
+        )])
+        .await;
+        let oauth = oauth(url, Grant::Password, "");
+        let token = fetch_token(oauth, "ann".to_string(), "p&ss".to_string(), settings())
+            .await
+            .unwrap();
+        assert_eq!(token.access_token, "abc");
+        assert_eq!(token.token_type, "Bearer");
+        assert_eq!(token.refresh_token.as_deref(), Some("r1"));
+        assert!(token.expires_at.is_some());
+
+        let requests = server.await.unwrap();
+        let request = &requests[0];
+        // Public clients only name themselves
+        assert_eq!(header(request, "authorization"), None);
+        let form = form(request);
+        assert_eq!(param(&form, "grant_type").as_deref(), Some("password"));
+        assert_eq!(param(&form, "username").as_deref(), Some("ann"));
+        assert_eq!(param(&form, "password").as_deref(), Some("p&ss"));
+        assert_eq!(param(&form, "client_id").as_deref(), Some("app"));
+    }
+
+    #[tokio::test]
+    async fn refresh_grant_keeps_the_refresh_token() {
+        let (url, server) = serve(vec![(200, r#"{"access_token":"new"}"#)]).await;
+        let mut oauth = oauth(url, Grant::ClientCredentials, "s3cret");
+        oauth.token = Some(Token {
+            access_token: "old".to_string(),
+            token_type: "Bearer".to_string(),
+            refresh_token: Some("r1".to_string()),
+            expires_at: Some(0),
+        });
+        let token = fetch_token(oauth, String::new(), String::new(), settings())
+            .await
+            .unwrap();
+        assert_eq!(token.access_token, "new");
+        assert_eq!(token.refresh_token.as_deref(), Some("r1"));
+        assert_eq!(token.expires_at, None);
+
+        let requests = server.await.unwrap();
+        let form = form(&requests[0]);
+        assert_eq!(param(&form, "grant_type").as_deref(), Some("refresh_token"));
+        assert_eq!(param(&form, "refresh_token").as_deref(), Some("r1"));
+    }
+
+    #[tokio::test]
+    async fn rejected_refresh_falls_back_to_the_grant() {
+        let (url, server) = serve(vec![
+            (400, r#"{"error":"invalid_grant"}"#),
+            (200, r#"{"access_token":"abc"}"#),
+        ])
+        .await;
+        let mut oauth = oauth(url, Grant::ClientCredentials, "s3cret");
+        oauth.token = Some(Token {
+            access_token: "old".to_string(),
+            token_type: "Bearer".to_string(),
+            refresh_token: Some("revoked".to_string()),
+            expires_at: Some(0),
+        });
+        let token = fetch_token(oauth, String::new(), String::new(), settings())
+            .await
+            .unwrap();
+        assert_eq!(token.access_token, "abc");
+
+        let requests = server.await.unwrap();
+        assert_eq!(
+            param(&form(&requests[0]), "grant_type").as_deref(),
+            Some("refresh_token")
+        );
+        assert_eq!(
+            param(&form(&requests[1]), "grant_type").as_deref(),
+            Some("client_credentials")
+        );
+    }
+
+    #[tokio::test]
+    async fn error_response() {
+        let (url, _server) = serve(vec![(
+            401,
+            r#"{"error":"invalid_client","error_description":"Unknown client"}"#,
+        )])
+        .await;
+        let oauth = oauth(url, Grant::ClientCredentials, "wrong");
+        let err = fetch_token(oauth, String::new(), String::new(), settings())
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Token request failed: invalid_client (Unknown client)");
+    }
+}
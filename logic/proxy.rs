@@ -0,0 +1,221 @@
+use std::fmt::Display;
+
+use reqwest::{ClientBuilder, NoProxy, Proxy};
+use serde_json::{Value, json};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProxyMode {
+    // Only for endpoints, they use the global settings
+    #[default]
+    Inherit,
+    // HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY from the environment
+    System,
+    Direct,
+    Manual,
+}
+
+impl ProxyMode {
+    pub const GLOBAL: [ProxyMode; 3] = [ProxyMode::System, ProxyMode::Direct, ProxyMode::Manual];
+    pub const ENDPOINT: [ProxyMode; 4] = [
+        ProxyMode::Inherit,
+        ProxyMode::System,
+        ProxyMode::Direct,
+        ProxyMode::Manual,
+    ];
+}
+
+impl Display for ProxyMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ProxyMode::Inherit => "Global proxy",
+            ProxyMode::System => "System proxy",
+            ProxyMode::Direct => "No proxy",
+            ProxyMode::Manual => "Manual proxy",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProxyScheme {
+    #[default]
+    Http,
+    Https,
+    Socks5,
+}
+
+impl ProxyScheme {
+    pub const ALL: [ProxyScheme; 3] = [ProxyScheme::Http, ProxyScheme::Https, ProxyScheme::Socks5];
+
+    fn prefix(&self) -> &'static str {
+        match self {
+            ProxyScheme::Http => "http",
+            ProxyScheme::Https => "https",
+            // Host names are resolved by the proxy, so internal names work through it
+            ProxyScheme::Socks5 => "socks5h",
+        }
+    }
+}
+
+impl Display for ProxyScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ProxyScheme::Http => "HTTP",
+            ProxyScheme::Https => "HTTPS",
+            ProxyScheme::Socks5 => "SOCKS5",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Which settings a proxy message changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyScope {
+    Global,
+    Endpoint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyField {
+    Address,
+    Username,
+    Password,
+    NoProxy,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProxySettings {
+    pub mode: ProxyMode,
+    pub scheme: ProxyScheme,
+    // host:port
+    pub address: String,
+    pub username: String,
+    pub password: String,
+    // Comma separated hosts, domains and IP ranges that are reached directly
+    pub no_proxy: String,
+}
+
+impl ProxySettings {
+    // Without saved settings the environment decides, like most HTTP tools do
+    pub fn global_default() -> ProxySettings {
+        ProxySettings {
+            mode: ProxyMode::System,
+            ..ProxySettings::default()
+        }
+    }
+
+    // The endpoint settings unless they defer to the global ones
+    pub fn effective<'a>(&'a self, global: &'a ProxySettings) -> &'a ProxySettings {
+        match self.mode {
+            ProxyMode::Inherit => global,
+            _ => self,
+        }
+    }
+
+    // Whether requests go straight to the server, NO_PROXY aside
+    pub fn is_direct(&self) -> bool {
+        match self.mode {
+            ProxyMode::Direct => true,
+            ProxyMode::Manual => false,
+            ProxyMode::Inherit | ProxyMode::System => {
+                ["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]
+                    .iter()
+                    .all(|it| std::env::var_os(it).is_none())
+            }
+        }
+    }
+
+    pub fn field(&self, field: ProxyField) -> &str {
+        match field {
+            ProxyField::Address => &self.address,
+            ProxyField::Username => &self.username,
+            ProxyField::Password => &self.password,
+            ProxyField::NoProxy => &self.no_proxy,
+        }
+    }
+
+    pub fn set_field(&mut self, field: ProxyField, value: String) {
+        match field {
+            ProxyField::Address => self.address = value,
+            ProxyField::Username => self.username = value,
+            ProxyField::Password => self.password = value,
+            ProxyField::NoProxy => self.no_proxy = value,
+        }
+    }
+
+    // reqwest reads the environment unless told otherwise, so System leaves the builder as is
+    pub fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder, String> {
+        match self.mode {
+            ProxyMode::Inherit | ProxyMode::System => Ok(builder),
+            ProxyMode::Direct => Ok(builder.no_proxy()),
+            ProxyMode::Manual => {
+                let address = self.address.trim();
+                if address.is_empty() {
+                    return Err("The proxy address is empty".to_string());
+                }
+                let address = address.split_once("://").map_or(address, |(_, it)| it);
+                let mut url =
+                    reqwest::Url::parse(&format!("{}://{}", self.scheme.prefix(), address))
+                        .map_err(|err| format!("Invalid proxy address: {}", err))?;
+                // SOCKS credentials only travel in the URL, HTTP proxies get a header
+                if self.scheme == ProxyScheme::Socks5 && !self.username.is_empty() {
+                    let _ = url.set_username(&self.username);
+                    let _ = url.set_password(Some(&self.password));
+                }
+                let mut proxy =
+                    Proxy::all(url).map_err(|err| format!("Invalid proxy address: {}", err))?;
+                if self.scheme != ProxyScheme::Socks5 && !self.username.is_empty() {
+                    proxy = proxy.basic_auth(&self.username, &self.password);
+                }
+                Ok(builder.proxy(proxy.no_proxy(NoProxy::from_string(&self.no_proxy))))
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        json!({
+            "mode": match self.mode {
+                ProxyMode::Inherit => "inherit",
+                ProxyMode::System => "system",
+                ProxyMode::Direct => "none",
+                ProxyMode::Manual => "manual",
+            },
+            "scheme": match self.scheme {
+                ProxyScheme::Http => "http",
+                ProxyScheme::Https => "https",
+                ProxyScheme::Socks5 => "socks5",
+            },
+            "address": self.address,
+            "username": self.username,
+            "password": self.password,
+            "no_proxy": self.no_proxy,
+        })
+        .to_string()
+    }
+
+    // Empty text gives `default`, endpoints saved before proxies existed use the global settings
+    pub fn from_json(text: &str, default: ProxySettings) -> ProxySettings {
+        let Ok(value) = serde_json::from_str::<Value>(text) else {
+            return default;
+        };
+        let string = |name: &str| value[name].as_str().unwrap_or_default().to_string();
+        ProxySettings {
+            mode: match value["mode"].as_str() {
+                Some("system") => ProxyMode::System,
+                Some("none") => ProxyMode::Direct,
+                Some("manual") => ProxyMode::Manual,
+                Some("inherit") => ProxyMode::Inherit,
+                _ => default.mode,
+            },
+            scheme: match value["scheme"].as_str() {
+                Some("https") => ProxyScheme::Https,
+                Some("socks5") => ProxyScheme::Socks5,
+                _ => ProxyScheme::Http,
+            },
+            address: string("address"),
+            username: string("username"),
+            password: string("password"),
+            no_proxy: string("no_proxy"),
+        }
+    }
+}
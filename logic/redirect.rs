@@ -0,0 +1,199 @@
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, HeaderName, LOCATION};
+use reqwest::{Client, Method, Request, Response, StatusCode};
+use serde_json::{Value, json};
+
+// The limit browsers and reqwest use when following
+const FOLLOW_LIMIT: u32 = 10;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RedirectMode {
+    #[default]
+    Follow,
+    DontFollow,
+    Limit,
+}
+
+impl RedirectMode {
+    pub const ALL: [RedirectMode; 3] = [
+        RedirectMode::Follow,
+        RedirectMode::DontFollow,
+        RedirectMode::Limit,
+    ];
+}
+
+impl Display for RedirectMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RedirectMode::Follow => "Follow redirects",
+            RedirectMode::DontFollow => "Don't follow",
+            RedirectMode::Limit => "Follow at most",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedirectPolicy {
+    pub mode: RedirectMode,
+    pub max_hops: u32,
+    // Send Authorization and Cookie on to another host too
+    pub keep_auth: bool,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        RedirectPolicy {
+            mode: RedirectMode::Follow,
+            max_hops: 5,
+            keep_auth: false,
+        }
+    }
+}
+
+// One redirect response on the way to the final one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hop {
+    pub status: u16,
+    pub url: String,
+    pub location: String,
+    pub duration: Duration,
+}
+
+impl RedirectPolicy {
+    fn limit(&self) -> u32 {
+        match self.mode {
+            RedirectMode::Follow => FOLLOW_LIMIT,
+            RedirectMode::DontFollow => 0,
+            RedirectMode::Limit => self.max_hops,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        json!({
+            "mode": match self.mode {
+                RedirectMode::Follow => "follow",
+                RedirectMode::DontFollow => "none",
+                RedirectMode::Limit => "limit",
+            },
+            "max_hops": self.max_hops,
+            "keep_auth": self.keep_auth,
+        })
+        .to_string()
+    }
+
+    pub fn from_json(text: &str) -> RedirectPolicy {
+        let default = RedirectPolicy::default();
+        let value: Value = serde_json::from_str(text).unwrap_or_default();
+        RedirectPolicy {
+            mode: match value["mode"].as_str() {
+                Some("none") => RedirectMode::DontFollow,
+                Some("limit") => RedirectMode::Limit,
+                _ => RedirectMode::Follow,
+            },
+            max_hops: value["max_hops"]
+                .as_u64()
+                .map_or(default.max_hops, |it| it as u32),
+            keep_auth: value["keep_auth"].as_bool().unwrap_or(default.keep_auth),
+        }
+    }
+}
+
+// Sends the request and follows redirects by hand, so every hop can be recorded. The client
+// must not follow on its own. When the limit is reached the last redirect is the response.
+// `credentials` are the headers besides Authorization and Cookie kept from other hosts.
+pub async fn execute(
+    client: &Client,
+    mut request: Request,
+    policy: &RedirectPolicy,
+    credentials: &[HeaderName],
+    hops: &mut Vec<Hop>,
+) -> Result<Response, reqwest::Error> {
+    hops.clear();
+    loop {
+        let next = request.try_clone();
+        let started = Instant::now();
+        let resp = client.execute(request).await?;
+        let status = resp.status();
+        let Some(location) = resp
+            .headers()
+            .get(LOCATION)
+            .and_then(|it| it.to_str().ok())
+            .filter(|_| status.is_redirection())
+            .map(str::to_string)
+        else {
+            return Ok(resp);
+        };
+        let (Some(mut next), Ok(url)) = (next, resp.url().join(&location)) else {
+            return Ok(resp);
+        };
+        // A redirect that isn't followed is the response, not a hop
+        if hops.len() as u32 >= policy.limit() {
+            return Ok(resp);
+        }
+        hops.push(Hop {
+            status: status.as_u16(),
+            url: resp.url().to_string(),
+            location,
+            duration: started.elapsed(),
+        });
+        // 303 always turns into a GET, 301 and 302 only for POST, like browsers do
+        let to_get = match status {
+            StatusCode::SEE_OTHER => next.method() != Method::HEAD,
+            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => next.method() == Method::POST,
+            _ => false,
+        };
+        if to_get {
+            *next.method_mut() = Method::GET;
+            *next.body_mut() = None;
+            next.headers_mut().remove(CONTENT_TYPE);
+            next.headers_mut().remove(CONTENT_LENGTH);
+        }
+        // Only another host loses them, an upgrade to https on the same host keeps them
+        if url.host_str() != next.url().host_str() && !policy.keep_auth {
+            for name in [AUTHORIZATION, COOKIE].iter().chain(credentials) {
+                next.headers_mut().remove(name);
+            }
+        }
+        *next.url_mut() = url;
+        request = next;
+    }
+}
+
+pub fn hops_to_json(hops: &[Hop]) -> String {
+    if hops.is_empty() {
+        return String::new();
+    }
+    Value::Array(
+        hops.iter()
+            .map(|it| {
+                json!({
+                    "status": it.status,
+                    "url": it.url,
+                    "location": it.location,
+                    "duration_ms": it.duration.as_millis() as u64,
+                })
+            })
+            .collect(),
+    )
+    .to_string()
+}
+
+pub fn hops_from_json(text: &str) -> Vec<Hop> {
+    let value: Value = serde_json::from_str(text).unwrap_or_default();
+    value
+        .as_array()
+        .map(|hops| {
+            hops.iter()
+                .map(|it| Hop {
+                    status: it["status"].as_u64().unwrap_or_default() as u16,
+                    url: it["url"].as_str().unwrap_or_default().to_string(),
+                    location: it["location"].as_str().unwrap_or_default().to_string(),
+                    duration: Duration::from_millis(it["duration_ms"].as_u64().unwrap_or_default()),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
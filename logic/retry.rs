@@ -0,0 +1,307 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::StatusCode;
+use reqwest::header::HeaderValue;
+use serde_json::{Value, json};
+
+use crate::logic::tls::certificate_error;
+
+// No single wait is longer than this, whatever the backoff or the server say
+const MAX_DELAY: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backoff {
+    Fixed,
+    #[default]
+    Exponential,
+}
+
+impl Backoff {
+    pub const ALL: [Backoff; 2] = [Backoff::Fixed, Backoff::Exponential];
+}
+
+impl Display for Backoff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Backoff::Fixed => "Fixed delay",
+            Backoff::Exponential => "Exponential backoff",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    // 1 sends the request once, like without a policy
+    pub max_attempts: u32,
+    pub on_5xx: bool,
+    pub on_429: bool,
+    pub on_network: bool,
+    pub backoff: Backoff,
+    pub delay_ms: u64,
+    pub jitter: bool,
+    pub retry_after: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            on_5xx: true,
+            on_429: true,
+            on_network: true,
+            backoff: Backoff::Exponential,
+            delay_ms: 500,
+            jitter: true,
+            retry_after: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attempt {
+    // The status code, or the error when no response came back
+    pub outcome: Result<u16, String>,
+    pub duration: Duration,
+    // How long it waited before the next attempt, zero for the last one
+    pub wait: Duration,
+}
+
+impl RetryPolicy {
+    pub fn is_on(&self) -> bool {
+        self.max_attempts > 1
+    }
+
+    pub fn retries_status(&self, code: StatusCode) -> bool {
+        (self.on_5xx && code.is_server_error())
+            || (self.on_429 && code == StatusCode::TOO_MANY_REQUESTS)
+    }
+
+    // Certificate failures look like connect errors but fail the same way every time
+    pub fn retries_error(&self, err: &reqwest::Error) -> bool {
+        self.on_network
+            && (err.is_connect() || err.is_timeout())
+            && certificate_error(err).is_none()
+    }
+
+    // The wait after the given attempt (counted from 1). Retry-After wins over the backoff,
+    // jitter picks a random wait between half and all of it so clients don't retry in step.
+    pub fn delay(&self, attempt: u32, retry_after: Option<&HeaderValue>) -> Duration {
+        if self.retry_after
+            && let Some(delay) = retry_after.and_then(parse_retry_after)
+        {
+            return delay.min(MAX_DELAY);
+        }
+        let base = Duration::from_millis(self.delay_ms);
+        let delay = match self.backoff {
+            Backoff::Fixed => base,
+            Backoff::Exponential => base.saturating_mul(2u32.saturating_pow(attempt - 1)),
+        }
+        .min(MAX_DELAY);
+        if self.jitter && !delay.is_zero() {
+            rand::rng().random_range(delay / 2..=delay)
+        } else {
+            delay
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        json!({
+            "max_attempts": self.max_attempts,
+            "on_5xx": self.on_5xx,
+            "on_429": self.on_429,
+            "on_network": self.on_network,
+            "backoff": match self.backoff {
+                Backoff::Fixed => "fixed",
+                Backoff::Exponential => "exponential",
+            },
+            "delay_ms": self.delay_ms,
+            "jitter": self.jitter,
+            "retry_after": self.retry_after,
+        })
+        .to_string()
+    }
+
+    // Endpoints saved before retries existed get the default policy, which sends once
+    pub fn from_json(text: &str) -> RetryPolicy {
+        let default = RetryPolicy::default();
+        let value: Value = serde_json::from_str(text).unwrap_or_default();
+        let flag = |name: &str, default: bool| value[name].as_bool().unwrap_or(default);
+        RetryPolicy {
+            max_attempts: value["max_attempts"]
+                .as_u64()
+                .map_or(default.max_attempts, |it| it as u32),
+            on_5xx: flag("on_5xx", default.on_5xx),
+            on_429: flag("on_429", default.on_429),
+            on_network: flag("on_network", default.on_network),
+            backoff: match value["backoff"].as_str() {
+                Some("fixed") => Backoff::Fixed,
+                Some(_) => Backoff::Exponential,
+                None => default.backoff,
+            },
+            delay_ms: value["delay_ms"].as_u64().unwrap_or(default.delay_ms),
+            jitter: flag("jitter", default.jitter),
+            retry_after: flag("retry_after", default.retry_after),
+        }
+    }
+}
+
+// Retry-After is either a number of seconds or an HTTP date
+fn parse_retry_after(value: &HeaderValue) -> Option<Duration> {
+    let value = value.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+pub fn attempts_to_json(attempts: &[Attempt]) -> String {
+    if attempts.is_empty() {
+        return String::new();
+    }
+    Value::Array(
+        attempts
+            .iter()
+            .map(|it| {
+                json!({
+                    "code": it.outcome.as_ref().ok(),
+                    "error": it.outcome.as_ref().err(),
+                    "duration_ms": it.duration.as_millis() as u64,
+                    "wait_ms": it.wait.as_millis() as u64,
+                })
+            })
+            .collect(),
+    )
+    .to_string()
+}
+
+pub fn attempts_from_json(text: &str) -> Vec<Attempt> {
+    let value: Value = serde_json::from_str(text).unwrap_or_default();
+    value
+        .as_array()
+        .map(|attempts| {
+            attempts
+                .iter()
+                .map(|it| Attempt {
+                    outcome: match it["code"].as_u64() {
+                        Some(code) => Ok(code as u16),
+                        None => Err(it["error"].as_str().unwrap_or_default().to_string()),
+                    },
+                    duration: Duration::from_millis(it["duration_ms"].as_u64().unwrap_or_default()),
+                    wait: Duration::from_millis(it["wait_ms"].as_u64().unwrap_or_default()),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(backoff: Backoff, jitter: bool) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 5,
+            backoff,
+            delay_ms: 500,
+            jitter,
+            ..RetryPolicy::default()
+        }
+    }
+
+    fn header(value: &str) -> HeaderValue {
+        HeaderValue::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn retry_after_seconds() {
+        let policy = policy(Backoff::Exponential, true);
+        assert_eq!(policy.delay(3, Some(&header("7"))), Duration::from_secs(7));
+        assert_eq!(policy.delay(1, Some(&header(" 0 "))), Duration::ZERO);
+        assert_eq!(policy.delay(1, Some(&header("86400"))), MAX_DELAY);
+    }
+
+    #[test]
+    fn retry_after_http_date() {
+        let policy = policy(Backoff::Fixed, false);
+        let date = (Utc::now() + chrono::Duration::seconds(30))
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string();
+        let delay = policy.delay(1, Some(&header(&date)));
+        assert!(delay > Duration::from_secs(27) && delay <= Duration::from_secs(30));
+        // A date that already passed means right away
+        assert_eq!(
+            policy.delay(1, Some(&header("Wed, 21 Oct 2015 07:28:00 GMT"))),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn retry_after_falls_back_to_the_backoff() {
+        let mut policy = policy(Backoff::Fixed, false);
+        let backoff = Duration::from_millis(500);
+        assert_eq!(policy.delay(1, Some(&header("soon"))), backoff);
+        assert_eq!(policy.delay(1, None), backoff);
+        policy.retry_after = false;
+        assert_eq!(policy.delay(1, Some(&header("7"))), backoff);
+    }
+
+    #[test]
+    fn exponential_backoff() {
+        let policy = policy(Backoff::Exponential, false);
+        let delays: Vec<u128> = (1..=4)
+            .map(|it| policy.delay(it, None).as_millis())
+            .collect();
+        assert_eq!(delays, [500, 1000, 2000, 4000]);
+        assert_eq!(policy.delay(9, None), MAX_DELAY);
+        assert_eq!(policy.delay(u32::MAX, None), MAX_DELAY);
+    }
+
+    #[test]
+    fn fixed_backoff() {
+        let policy = policy(Backoff::Fixed, false);
+        assert_eq!(policy.delay(1, None), Duration::from_millis(500));
+        assert_eq!(policy.delay(10, None), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn jitter_stays_between_half_and_all_of_the_delay() {
+        let jittered = policy(Backoff::Exponential, true);
+        let exact = policy(Backoff::Exponential, false);
+        for attempt in [1, 3, 20] {
+            let full = exact.delay(attempt, None);
+            for _ in 0..200 {
+                let delay = jittered.delay(attempt, None);
+                assert!(
+                    delay >= full / 2 && delay <= full,
+                    "{:?} of {:?}",
+                    delay,
+                    full
+                );
+            }
+        }
+        let mut zero = jittered;
+        zero.delay_ms = 0;
+        assert_eq!(zero.delay(1, None), Duration::ZERO);
+    }
+
+    #[test]
+    fn retried_statuses() {
+        let mut policy = RetryPolicy::default();
+        assert!(policy.retries_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(policy.retries_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!policy.retries_status(StatusCode::NOT_FOUND));
+        policy.on_5xx = false;
+        policy.on_429 = false;
+        assert!(!policy.retries_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!policy.retries_status(StatusCode::TOO_MANY_REQUESTS));
+    }
+}
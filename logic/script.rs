@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use rhai::{Dynamic, Engine, Map, Scope};
+use serde_json::{Value, json};
+
+// Scripts run on the UI thread, so runaway loops are cut off
+const MAX_OPERATIONS: u64 = 1_000_000;
+const MAX_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub name: String,
+    pub passed: bool,
+    pub message: Option<String>,
+}
+
+// The parts of a request a pre-request script can change
+#[derive(Debug, Clone, Default)]
+pub struct ScriptRequest {
+    pub method: String,
+    pub url: String,
+    pub params: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+// Engine::new has no access to files, network or the environment
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(64);
+    engine.set_max_expr_depths(64, 64);
+    engine.set_max_string_size(MAX_SIZE);
+    engine.set_max_array_size(100_000);
+    engine.set_max_map_size(100_000);
+    engine
+}
+
+fn to_map(pairs: &[(String, String)]) -> Map {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.into(), Dynamic::from(value.clone())))
+        .collect()
+}
+
+fn from_map(value: Option<&Dynamic>) -> Vec<(String, String)> {
+    value
+        .and_then(|it| it.clone().try_cast::<Map>())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn text(map: &Map, name: &str) -> String {
+    map.get(name).map(Dynamic::to_string).unwrap_or_default()
+}
+
+// Variables are handed to the script as the `vars` map and read back after it ran
+fn with_variables(
+    scope: &mut Scope,
+    variables: &mut HashMap<String, String>,
+    run: impl FnOnce(&mut Scope) -> Result<(), String>,
+) -> Result<(), String> {
+    let pairs: Vec<(String, String)> = variables
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    scope.push("vars", to_map(&pairs));
+    let result = run(scope);
+    if let Some(vars) = scope.get_value::<Map>("vars") {
+        *variables = from_map(Some(&Dynamic::from(vars))).into_iter().collect();
+    }
+    result
+}
+
+pub fn run_pre_request(
+    script: &str,
+    request: ScriptRequest,
+    variables: &mut HashMap<String, String>,
+) -> Result<ScriptRequest, String> {
+    let engine = engine();
+    let mut scope = Scope::new();
+    let mut map = Map::new();
+    map.insert("method".into(), request.method.clone().into());
+    map.insert("url".into(), request.url.into());
+    map.insert("params".into(), to_map(&request.params).into());
+    map.insert("headers".into(), to_map(&request.headers).into());
+    map.insert("body".into(), request.body.into());
+    scope.push("request", map);
+    with_variables(&mut scope, variables, |scope| {
+        engine
+            .run_with_scope(scope, script)
+            .map_err(|it| it.to_string())
+    })?;
+    let map = scope
+        .get_value::<Map>("request")
+        .ok_or("`request` is no longer a map")?;
+    Ok(ScriptRequest {
+        method: request.method,
+        url: text(&map, "url"),
+        params: from_map(map.get("params")),
+        headers: from_map(map.get("headers")),
+        body: text(&map, "body"),
+    })
+}
+
+// Script errors are recorded as a failed result, the response is kept either way
+pub fn run_post_response(
+    script: &str,
+    status: u16,
+    headers: &[(String, String)],
+    body: &str,
+    variables: &mut HashMap<String, String>,
+) -> Vec<TestResult> {
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let mut engine = engine();
+    let recorded = results.clone();
+    engine.register_fn("test", move |name: &str, passed: bool| {
+        recorded.lock().unwrap().push(TestResult {
+            name: name.to_string(),
+            passed,
+            message: None,
+        });
+    });
+    let mut scope = Scope::new();
+    let mut map = Map::new();
+    map.insert("status".into(), (status as i64).into());
+    map.insert("headers".into(), to_map(headers).into());
+    map.insert("body".into(), body.to_string().into());
+    map.insert(
+        "json".into(),
+        serde_json::from_str::<Value>(body)
+            .ok()
+            .and_then(|it| rhai::serde::to_dynamic(it).ok())
+            .unwrap_or(Dynamic::UNIT),
+    );
+    scope.push("response", map);
+    let result = with_variables(&mut scope, variables, |scope| {
+        engine
+            .run_with_scope(scope, script)
+            .map_err(|it| it.to_string())
+    });
+    let mut results = results.lock().unwrap().clone();
+    if let Err(err) = result {
+        results.push(TestResult {
+            name: "Post-response script".to_string(),
+            passed: false,
+            message: Some(err),
+        });
+    }
+    results
+}
+
+pub fn tests_to_json(tests: &[TestResult]) -> String {
+    if tests.is_empty() {
+        return String::new();
+    }
+    Value::Array(
+        tests
+            .iter()
+            .map(|it| json!({"name": it.name, "passed": it.passed, "message": it.message}))
+            .collect(),
+    )
+    .to_string()
+}
+
+pub fn tests_from_json(text: &str) -> Vec<TestResult> {
+    let value: Value = serde_json::from_str(text).unwrap_or_default();
+    value
+        .as_array()
+        .map(|tests| {
+            tests
+                .iter()
+                .map(|it| TestResult {
+                    name: it["name"].as_str().unwrap_or_default().to_string(),
+                    passed: it["passed"].as_bool().unwrap_or_default(),
+                    message: it["message"].as_str().map(str::to_string),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
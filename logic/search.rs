@@ -0,0 +1,72 @@
+use std::ops::Range;
+
+use regex::{RegexBuilder, escape};
+
+use crate::BodySearch;
+use crate::logic::highlight::{HighlightedLine, Token};
+
+pub fn find_matches(body: &str, search: &BodySearch) -> Result<Vec<Range<usize>>, String> {
+    if search.query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let pattern = if search.regex {
+        search.query.clone()
+    } else {
+        escape(&search.query)
+    };
+    let regex = RegexBuilder::new(&pattern)
+        .case_insensitive(!search.case_sensitive)
+        .build()
+        .map_err(|it| match it {
+            regex::Error::Syntax(_) => "Invalid regular expression".to_string(),
+            _ => it.to_string(),
+        })?;
+    Ok(regex
+        .find_iter(body)
+        .filter(|it| !it.is_empty())
+        .map(|it| it.range())
+        .collect())
+}
+
+// Searches again, after the query, one of its toggles or the searched body changed
+pub fn refresh_matches(search: &mut BodySearch, body: &str) {
+    (search.matches, search.error) = match find_matches(body, search) {
+        Ok(matches) => (matches, None),
+        Err(error) => (Vec::new(), Some(error)),
+    };
+}
+
+// Splits highlighted segments at match boundaries, tagging every part that
+// falls inside a match with the index of that match.
+pub fn mark_matches<'a>(
+    body: &'a str,
+    line: HighlightedLine<'a>,
+    matches: &[Range<usize>],
+) -> Vec<(Token, &'a str, Option<usize>)> {
+    let mut parts = Vec::new();
+    for (token, segment) in line {
+        let start = segment.as_ptr() as usize - body.as_ptr() as usize;
+        let end = start + segment.len();
+        let mut pos = start;
+        let mut index = matches.partition_point(|it| it.end <= start);
+        while pos < end {
+            match matches.get(index) {
+                Some(m) if m.start < end => {
+                    if m.start > pos {
+                        parts.push((token, &body[pos..m.start], None));
+                        pos = m.start;
+                    }
+                    let part_end = m.end.min(end);
+                    parts.push((token, &body[pos..part_end], Some(index)));
+                    pos = part_end;
+                    index += 1;
+                }
+                _ => {
+                    parts.push((token, &body[pos..end], None));
+                    pos = end;
+                }
+            }
+        }
+    }
+    parts
+}
@@ -0,0 +1,432 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+pub const DEFAULT_CANONICAL: &str = "{method}\\n{path}\\n{timestamp}\\n{body_sha256}";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AwsSigning {
+    pub access_key: String,
+    pub secret_key: String,
+    pub region: String,
+    pub service: String,
+    pub session_token: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HmacSigning {
+    pub secret: String,
+    pub header: String,
+    pub canonical: String,
+    pub timestamp_header: String,
+    pub base64: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningField {
+    AccessKey,
+    SecretKey,
+    Region,
+    Service,
+    SessionToken,
+    HmacSecret,
+    HmacHeader,
+    HmacCanonical,
+    HmacTimestampHeader,
+}
+
+impl AwsSigning {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "access_key": self.access_key,
+            "secret_key": self.secret_key,
+            "region": self.region,
+            "service": self.service,
+            "session_token": self.session_token,
+        })
+    }
+
+    pub fn from_json(value: &Value) -> AwsSigning {
+        let field = |name: &str| value[name].as_str().unwrap_or_default().to_string();
+        AwsSigning {
+            access_key: field("access_key"),
+            secret_key: field("secret_key"),
+            region: field("region"),
+            service: field("service"),
+            session_token: field("session_token"),
+        }
+    }
+}
+
+impl HmacSigning {
+    pub fn signature_header(&self) -> &str {
+        match self.header.trim() {
+            "" => "X-Signature",
+            it => it,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "secret": self.secret,
+            "header": self.header,
+            "canonical": self.canonical,
+            "timestamp_header": self.timestamp_header,
+            "base64": self.base64,
+        })
+    }
+
+    pub fn from_json(value: &Value) -> HmacSigning {
+        let field = |name: &str| value[name].as_str().unwrap_or_default().to_string();
+        HmacSigning {
+            secret: field("secret"),
+            header: field("header"),
+            canonical: field("canonical"),
+            timestamp_header: field("timestamp_header"),
+            base64: value["base64"].as_bool().unwrap_or_default(),
+        }
+    }
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|it| format!("{:02x}", it)).collect()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+// Percent-encodes everything except the RFC 3986 unreserved characters, like AWS expects
+fn uri_encode(input: &str, keep_slash: bool) -> String {
+    let mut encoded = String::new();
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn host(url: &Url) -> String {
+    match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    }
+}
+
+fn insert(headers: &mut HeaderMap, name: &str, value: &str) {
+    if let (Ok(name), Ok(value)) = (
+        HeaderName::from_bytes(name.as_bytes()),
+        HeaderValue::from_str(value),
+    ) {
+        headers.insert(name, value);
+    }
+}
+
+// https://docs.aws.amazon.com/IAM/latest/UserGuide/create-signed-request.html
+pub fn sign_aws(
+    aws: &AwsSigning,
+    method: &str,
+    url: &Url,
+    headers: &mut HeaderMap,
+    body: &str,
+    now: DateTime<Utc>,
+) {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = sha256_hex(body.as_bytes());
+    insert(headers, "x-amz-date", &amz_date);
+    if !aws.session_token.is_empty() {
+        insert(headers, "x-amz-security-token", &aws.session_token);
+    }
+    if aws.service == "s3" {
+        insert(headers, "x-amz-content-sha256", &payload_hash);
+    }
+
+    // S3 takes the path as it is, every other service wants it encoded once more
+    let path = match url.path() {
+        "" => "/".to_string(),
+        it if aws.service == "s3" => it.to_string(),
+        it => uri_encode(it, true),
+    };
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(key, value)| (uri_encode(&key, false), uri_encode(&value, false)))
+        .collect();
+    query.sort();
+    let query = query
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    // A repeated header is one line, its values joined by commas in the order they were added
+    let mut signed: Vec<(String, String)> = headers
+        .keys()
+        .filter(|name| name.as_str().starts_with("x-amz-") || *name == CONTENT_TYPE)
+        .map(|name| {
+            let values = headers
+                .get_all(name)
+                .iter()
+                .map(|it| it.to_str().unwrap_or_default().trim())
+                .collect::<Vec<_>>();
+            (name.as_str().to_string(), values.join(","))
+        })
+        .collect();
+    signed.push(("host".to_string(), host(url)));
+    signed.sort();
+    let canonical_headers: String = signed
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let signed_headers = signed
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method, path, query, canonical_headers, signed_headers, payload_hash
+    );
+    let scope = format!("{}/{}/{}/aws4_request", date, aws.region, aws.service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+    let mut key = format!("AWS4{}", aws.secret_key).into_bytes();
+    for part in [date.as_str(), &aws.region, &aws.service, "aws4_request"] {
+        key = hmac(&key, part);
+    }
+    let signature = hex(&hmac(&key, &string_to_sign));
+    insert(
+        headers,
+        AUTHORIZATION.as_str(),
+        &format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            aws.access_key, scope, signed_headers, signature
+        ),
+    );
+}
+
+// Fills the placeholders of the canonical string, then signs it with the shared secret
+pub fn sign_hmac(
+    signing: &HmacSigning,
+    method: &str,
+    url: &Url,
+    headers: &mut HeaderMap,
+    body: &str,
+    now: DateTime<Utc>,
+) {
+    let timestamp = now.timestamp().to_string();
+    let template = match signing.canonical.trim() {
+        "" => DEFAULT_CANONICAL,
+        it => it,
+    };
+    let canonical = template
+        .replace("\\n", "\n")
+        .replace("{method}", method)
+        .replace("{url}", url.as_str())
+        .replace("{host}", &host(url))
+        .replace("{path}", url.path())
+        .replace("{query}", url.query().unwrap_or_default())
+        .replace("{timestamp}", &timestamp)
+        .replace("{body_sha256}", &sha256_hex(body.as_bytes()))
+        .replace("{body}", body);
+    let signature = hmac(signing.secret.as_bytes(), &canonical);
+    let signature = if signing.base64 {
+        STANDARD.encode(signature)
+    } else {
+        hex(&signature)
+    };
+    if !signing.timestamp_header.trim().is_empty() {
+        insert(headers, signing.timestamp_header.trim(), &timestamp);
+    }
+    insert(headers, signing.signature_header(), &signature);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The credentials, scope and time of the AWS Signature Version 4 test suite
+    fn suite() -> (AwsSigning, DateTime<Utc>) {
+        let aws = AwsSigning {
+            access_key: "AKIDEXAMPLE".to_string(),
+            secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            region: "us-east-1".to_string(),
+            service: "service".to_string(),
+            session_token: String::new(),
+        };
+        let now = DateTime::parse_from_rfc3339("2015-08-30T12:36:00Z").unwrap();
+        (aws, now.to_utc())
+    }
+
+    fn authorization(method: &str, url: &str, mut headers: HeaderMap, body: &str) -> String {
+        let (aws, now) = suite();
+        let url = Url::parse(url).unwrap();
+        sign_aws(&aws, method, &url, &mut headers, body, now);
+        assert_eq!(headers["x-amz-date"], "20150830T123600Z");
+        headers[AUTHORIZATION].to_str().unwrap().to_string()
+    }
+
+    fn expected(signed_headers: &str, signature: &str) -> String {
+        format!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders={}, Signature={}",
+            signed_headers, signature
+        )
+    }
+
+    #[test]
+    fn aws_get_vanilla() {
+        assert_eq!(
+            authorization(
+                "GET",
+                "https://example.amazonaws.com/",
+                HeaderMap::new(),
+                ""
+            ),
+            expected(
+                "host;x-amz-date",
+                "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+            )
+        );
+    }
+
+    #[test]
+    fn aws_get_vanilla_query_order_key_case() {
+        assert_eq!(
+            authorization(
+                "GET",
+                "https://example.amazonaws.com/?Param2=value2&Param1=value1",
+                HeaderMap::new(),
+                ""
+            ),
+            expected(
+                "host;x-amz-date",
+                "b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+            )
+        );
+    }
+
+    #[test]
+    fn aws_post_vanilla() {
+        assert_eq!(
+            authorization(
+                "POST",
+                "https://example.amazonaws.com/",
+                HeaderMap::new(),
+                ""
+            ),
+            expected(
+                "host;x-amz-date",
+                "5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b"
+            )
+        );
+    }
+
+    #[test]
+    fn aws_post_x_www_form_urlencoded() {
+        let mut headers = HeaderMap::new();
+        insert(
+            &mut headers,
+            "content-type",
+            "application/x-www-form-urlencoded",
+        );
+        assert_eq!(
+            authorization(
+                "POST",
+                "https://example.amazonaws.com/",
+                headers,
+                "Param1=value1"
+            ),
+            expected(
+                "content-type;host;x-amz-date",
+                "ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a"
+            )
+        );
+    }
+
+    #[test]
+    fn aws_post_x_www_form_urlencoded_parameters() {
+        let mut headers = HeaderMap::new();
+        insert(
+            &mut headers,
+            "content-type",
+            "application/x-www-form-urlencoded; charset=utf8",
+        );
+        assert_eq!(
+            authorization(
+                "POST",
+                "https://example.amazonaws.com/",
+                headers,
+                "Param1=value1"
+            ),
+            expected(
+                "content-type;host;x-amz-date",
+                "1a72ec8f64bd914b0e42e42607c7fbce7fb2c7465f63e3092b3b0d39fa77a6fe"
+            )
+        );
+    }
+
+    #[test]
+    fn aws_repeated_header_is_one_line() {
+        let mut repeated = HeaderMap::new();
+        repeated.append("x-amz-meta-tag", HeaderValue::from_static("value2"));
+        repeated.append("x-amz-meta-tag", HeaderValue::from_static(" value1 "));
+        let mut joined = HeaderMap::new();
+        insert(&mut joined, "x-amz-meta-tag", "value2,value1");
+        let url = "https://example.amazonaws.com/";
+        let signature = expected(
+            "host;x-amz-date;x-amz-meta-tag",
+            "11f6636602fc4317921b44b4afb5d08fa15941120b21464b1517dd5466898228",
+        );
+        assert_eq!(authorization("GET", url, repeated, ""), signature);
+        assert_eq!(authorization("GET", url, joined, ""), signature);
+    }
+
+    #[test]
+    fn hmac_fixed_timestamp() {
+        let mut signing = HmacSigning {
+            secret: "secret".to_string(),
+            timestamp_header: "X-Timestamp".to_string(),
+            ..HmacSigning::default()
+        };
+        let url = Url::parse("https://api.example.com/orders?id=1").unwrap();
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+
+        let mut headers = HeaderMap::new();
+        sign_hmac(&signing, "POST", &url, &mut headers, r#"{"a":1}"#, now);
+        assert_eq!(headers["x-timestamp"], "1700000000");
+        assert_eq!(
+            headers["x-signature"],
+            "6f3aa603b2657cf4731fdd8acee82d3d3205e7261cc5fe2904eadd7db0316af5"
+        );
+
+        signing.header = "X-Api-Signature".to_string();
+        signing.base64 = true;
+        let mut headers = HeaderMap::new();
+        sign_hmac(&signing, "POST", &url, &mut headers, r#"{"a":1}"#, now);
+        assert_eq!(
+            headers["x-api-signature"],
+            "bzqmA7JlfPRzH92KzugtPTIF5yYcxf4pBOrdfbAxavU="
+        );
+    }
+}
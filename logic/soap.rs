@@ -0,0 +1,399 @@
+use quick_xml::NsReader;
+use quick_xml::escape::{resolve_predefined_entity, unescape};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::ResolveResult;
+
+const SOAP11_BINDING: &str = "http://schemas.xmlsoap.org/wsdl/soap/";
+const SOAP12_BINDING: &str = "http://schemas.xmlsoap.org/wsdl/soap12/";
+const SOAP11_ENVELOPE: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+const SOAP12_ENVELOPE: &str = "http://www.w3.org/2003/05/soap-envelope";
+
+// Schemas can reference themselves, so templates stop nesting at this depth
+const MAX_DEPTH: usize = 6;
+
+#[derive(Debug, Clone, Default)]
+pub struct Wsdl {
+    pub location: Option<String>,
+    pub operations: Vec<Operation>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub name: String,
+    pub action: String,
+    pub soap12: bool,
+    pub envelope: String,
+}
+
+impl Operation {
+    // SOAP 1.2 carries the action in the content type instead of a SOAPAction header
+    pub fn content_type(&self) -> String {
+        if self.soap12 {
+            format!(
+                "application/soap+xml; charset=utf-8; action=\"{}\"",
+                self.action
+            )
+        } else {
+            "text/xml; charset=utf-8".to_string()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Fault {
+    pub code: String,
+    pub reason: String,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    name: String,
+    namespace: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Node>,
+    text: String,
+}
+
+impl Node {
+    fn attr(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn children_named<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a Node> {
+        self.children.iter().filter(move |it| it.name == name)
+    }
+
+    fn child(&self, name: &str) -> Option<&Node> {
+        self.children_named(name).next()
+    }
+
+    fn find(&self, name: &str) -> Option<&Node> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children.iter().find_map(|it| it.find(name))
+    }
+
+    fn text_content(&self) -> String {
+        let mut text = self.text.trim().to_string();
+        for child in &self.children {
+            let child = child.text_content();
+            if !child.is_empty() {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(&child);
+            }
+        }
+        text
+    }
+}
+
+fn parse_tree(xml: &str) -> Result<Node, String> {
+    let mut reader = NsReader::from_str(xml);
+    let mut stack = vec![Node::default()];
+    loop {
+        let (namespace, event) = reader.read_resolved_event().map_err(|it| it.to_string())?;
+        match event {
+            Event::Start(start) => stack.push(node(namespace, &start)),
+            Event::Empty(start) => {
+                let node = node(namespace, &start);
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(node);
+                }
+            }
+            Event::End(_) => {
+                let node = stack.pop().ok_or("Unexpected closing tag")?;
+                stack
+                    .last_mut()
+                    .ok_or("Unexpected closing tag")?
+                    .children
+                    .push(node);
+            }
+            Event::Text(text) => {
+                let text = text.decode().map_err(|it| it.to_string())?;
+                let text = unescape(&text).map_err(|it| it.to_string())?;
+                if let Some(node) = stack.last_mut() {
+                    node.text.push_str(&text);
+                }
+            }
+            Event::CData(data) => {
+                let text = data.decode().map_err(|it| it.to_string())?;
+                if let Some(node) = stack.last_mut() {
+                    node.text.push_str(&text);
+                }
+            }
+            Event::GeneralRef(reference) => {
+                let resolved = match reference.resolve_char_ref() {
+                    Ok(Some(char)) => char.to_string(),
+                    _ => {
+                        let name = reference.decode().map_err(|it| it.to_string())?;
+                        resolve_predefined_entity(&name)
+                            .unwrap_or_default()
+                            .to_string()
+                    }
+                };
+                if let Some(node) = stack.last_mut() {
+                    node.text.push_str(&resolved);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    stack
+        .pop()
+        .and_then(|it| it.children.into_iter().next())
+        .ok_or_else(|| "The document has no root element".to_string())
+}
+
+fn node(namespace: ResolveResult, start: &BytesStart) -> Node {
+    Node {
+        name: String::from_utf8_lossy(start.local_name().as_ref()).to_string(),
+        namespace: match namespace {
+            ResolveResult::Bound(it) => String::from_utf8_lossy(it.as_ref()).to_string(),
+            _ => String::new(),
+        },
+        attributes: start
+            .attributes()
+            .flatten()
+            .map(|it| {
+                (
+                    String::from_utf8_lossy(it.key.local_name().as_ref()).to_string(),
+                    it.unescape_value().unwrap_or_default().to_string(),
+                )
+            })
+            .collect(),
+        ..Node::default()
+    }
+}
+
+// "tns:GetWeather" -> "GetWeather"
+fn local(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+pub fn parse_wsdl(xml: &str) -> Result<Wsdl, String> {
+    let root = parse_tree(xml)?;
+    if root.name != "definitions" {
+        return Err("Only WSDL 1.1 documents are supported".to_string());
+    }
+    let schemas: Vec<&Node> = root
+        .child("types")
+        .map(|it| it.children_named("schema").collect())
+        .unwrap_or_default();
+    let mut ports: Vec<(&Node, &Node)> = root
+        .children_named("service")
+        .flat_map(|it| it.children_named("port"))
+        .filter_map(|port| {
+            let address = port.children.iter().find(|it| {
+                it.name == "address"
+                    && (it.namespace == SOAP11_BINDING || it.namespace == SOAP12_BINDING)
+            })?;
+            Some((port, address))
+        })
+        .collect();
+    // Services often offer both versions, SOAP 1.1 is preferred
+    ports.sort_by_key(|(_, address)| address.namespace == SOAP12_BINDING);
+
+    let mut wsdl = Wsdl::default();
+    for (port, address) in ports {
+        let soap12 = address.namespace == SOAP12_BINDING;
+        let Some(binding) = root
+            .children_named("binding")
+            .find(|it| it.attr("name") == port.attr("binding").map(local))
+        else {
+            continue;
+        };
+        let port_type = root
+            .children_named("portType")
+            .find(|it| it.attr("name") == binding.attr("type").map(local));
+        if wsdl.location.is_none() {
+            wsdl.location = address.attr("location").map(str::to_string);
+        }
+        let binding_style = binding
+            .child("binding")
+            .and_then(|it| it.attr("style"))
+            .unwrap_or("document");
+        for operation in binding.children_named("operation") {
+            let name = operation.attr("name").unwrap_or_default();
+            if wsdl.operations.iter().any(|it| it.name == name) {
+                continue;
+            }
+            let soap_operation = operation
+                .children
+                .iter()
+                .find(|it| it.name == "operation" && !it.namespace.is_empty());
+            let style = soap_operation
+                .and_then(|it| it.attr("style"))
+                .unwrap_or(binding_style);
+            let parts: Vec<&Node> = port_type
+                .and_then(|it| {
+                    it.children_named("operation")
+                        .find(|it| it.attr("name") == Some(name))
+                })
+                .and_then(|it| it.child("input"))
+                .and_then(|input| {
+                    root.children_named("message")
+                        .find(|it| it.attr("name") == input.attr("message").map(local))
+                })
+                .map(|it| it.children_named("part").collect())
+                .unwrap_or_default();
+            let namespace = root.attr("targetNamespace").unwrap_or_default();
+            let (namespace, body) = if style == "rpc" {
+                let mut body = vec![format!("<tns:{}>", name)];
+                for part in parts {
+                    let part = part.attr("name").unwrap_or_default();
+                    body.push(format!("  <{}>?</{}>", part, part));
+                }
+                body.push(format!("</tns:{}>", name));
+                (namespace.to_string(), body)
+            } else {
+                document_body(&schemas, &parts, namespace)
+            };
+            wsdl.operations.push(Operation {
+                name: name.to_string(),
+                action: soap_operation
+                    .and_then(|it| it.attr("soapAction"))
+                    .unwrap_or_default()
+                    .to_string(),
+                soap12,
+                envelope: envelope(soap12, &namespace, &body),
+            });
+        }
+    }
+    if wsdl.operations.is_empty() {
+        return Err("The WSDL has no SOAP operations".to_string());
+    }
+    Ok(wsdl)
+}
+
+fn document_body(schemas: &[&Node], parts: &[&Node], namespace: &str) -> (String, Vec<String>) {
+    let mut namespace = namespace.to_string();
+    let mut body = Vec::new();
+    for part in parts {
+        let Some(element) = part.attr("element").map(local) else {
+            continue;
+        };
+        let Some((schema, element)) = top_level(schemas, "element", element) else {
+            body.push(format!("<tns:{}>?</tns:{}>", element, element));
+            continue;
+        };
+        namespace = schema
+            .attr("targetNamespace")
+            .unwrap_or_default()
+            .to_string();
+        render_element(schemas, element, "tns:", 0, &mut body);
+    }
+    (namespace, body)
+}
+
+fn top_level<'a>(schemas: &[&'a Node], kind: &str, name: &str) -> Option<(&'a Node, &'a Node)> {
+    schemas.iter().find_map(|schema| {
+        schema
+            .children_named(kind)
+            .find(|it| it.attr("name") == Some(name))
+            .map(|it| (*schema, it))
+    })
+}
+
+fn render_element(
+    schemas: &[&Node],
+    element: &Node,
+    prefix: &str,
+    depth: usize,
+    lines: &mut Vec<String>,
+) {
+    let element = match element.attr("ref") {
+        Some(reference) => match top_level(schemas, "element", local(reference)) {
+            Some((_, it)) => it,
+            None => element,
+        },
+        None => element,
+    };
+    let name = element.attr("name").unwrap_or_default();
+    let indent = "  ".repeat(depth);
+    let complex = match element.attr("type") {
+        Some(it) => top_level(schemas, "complexType", local(it)).map(|(_, it)| it),
+        None => element.child("complexType"),
+    };
+    let fields = complex.map(fields).unwrap_or_default();
+    if fields.is_empty() || depth >= MAX_DEPTH {
+        lines.push(format!(
+            "{}<{}{}>?</{}{}>",
+            indent, prefix, name, prefix, name
+        ));
+        return;
+    }
+    lines.push(format!("{}<{}{}>", indent, prefix, name));
+    let qualified = schemas
+        .iter()
+        .any(|it| it.attr("elementFormDefault") == Some("qualified"));
+    for field in fields {
+        render_element(
+            schemas,
+            field,
+            if qualified { prefix } else { "" },
+            depth + 1,
+            lines,
+        );
+    }
+    lines.push(format!("{}</{}{}>", indent, prefix, name));
+}
+
+// Child elements of a complex type, looking through sequences, choices and extensions
+fn fields(node: &Node) -> Vec<&Node> {
+    let mut fields = Vec::new();
+    for child in &node.children {
+        match child.name.as_str() {
+            "element" => fields.push(child),
+            "sequence" | "all" | "choice" | "complexContent" | "extension" => {
+                fields.extend(self::fields(child))
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+fn envelope(soap12: bool, namespace: &str, body: &[String]) -> String {
+    let mut envelope = format!(
+        "<soap:Envelope xmlns:soap=\"{}\" xmlns:tns=\"{}\">\n  <soap:Header/>\n  <soap:Body>\n",
+        if soap12 {
+            SOAP12_ENVELOPE
+        } else {
+            SOAP11_ENVELOPE
+        },
+        namespace
+    );
+    for line in body {
+        envelope.push_str(&format!("    {}\n", line));
+    }
+    envelope.push_str("  </soap:Body>\n</soap:Envelope>");
+    envelope
+}
+
+// SOAP 1.1 faults have faultcode/faultstring/detail, SOAP 1.2 ones Code/Reason/Detail
+pub fn parse_fault(body: &str) -> Option<Fault> {
+    let root = parse_tree(body).ok()?;
+    let fault = root.find("Fault")?;
+    let text = |name: &str| fault.child(name).map(Node::text_content);
+    let code = text("faultcode")
+        .or_else(|| fault.child("Code")?.child("Value").map(Node::text_content))
+        .unwrap_or_default();
+    let reason = text("faultstring")
+        .or_else(|| text("Reason"))
+        .unwrap_or_default();
+    let detail = text("detail")
+        .or_else(|| text("Detail"))
+        .filter(|it| !it.is_empty());
+    Some(Fault {
+        code,
+        reason,
+        detail,
+    })
+}
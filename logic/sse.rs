@@ -0,0 +1,190 @@
+use chrono::{NaiveDateTime, Utc};
+
+#[derive(Debug, Clone)]
+pub struct SseEvent {
+    pub event: String,
+    pub id: Option<String>,
+    pub data: String,
+    // UTC, like the other receive times
+    pub time: NaiveDateTime,
+}
+
+// Incremental parser for the text/event-stream format. Chunks can end anywhere,
+// so incomplete lines are kept until the rest of them arrives.
+#[derive(Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    event: String,
+    data: Vec<String>,
+    pub last_event_id: Option<String>,
+    pub retry: Option<u64>,
+}
+
+impl SseParser {
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|it| *it == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(event) = self.line(line.trim_end_matches(['\n', '\r'])) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    // A new connection starts from a clean buffer but keeps the last id and retry delay
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.event.clear();
+        self.data.clear();
+    }
+
+    fn line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => self.data.push(value.to_string()),
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" => self.retry = value.parse().ok().or(self.retry),
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = std::mem::take(&mut self.event);
+        if self.data.is_empty() {
+            return None;
+        }
+        Some(SseEvent {
+            event: if event.is_empty() {
+                "message".to_string()
+            } else {
+                event
+            },
+            id: self.last_event_id.clone(),
+            data: std::mem::take(&mut self.data).join("\n"),
+            time: Utc::now().naive_utc(),
+        })
+    }
+}
+
+// Writes the captured events back in the wire format, with UTC receive times as comments
+pub fn event_log(events: &[SseEvent]) -> String {
+    let mut log = String::new();
+    for event in events {
+        log.push_str(&format!(
+            ": {}\n",
+            event.time.format("%Y-%m-%d %H:%M:%S%.3fZ")
+        ));
+        log.push_str(&format!("event: {}\n", event.event));
+        if let Some(id) = &event.id {
+            log.push_str(&format!("id: {}\n", id));
+        }
+        for line in event.data.split('\n') {
+            log.push_str(&format!("data: {}\n", line));
+        }
+        log.push('\n');
+    }
+    log
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(events: &[SseEvent]) -> Vec<(&str, Option<&str>, &str)> {
+        events
+            .iter()
+            .map(|it| (it.event.as_str(), it.id.as_deref(), it.data.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn fields() {
+        let mut parser = SseParser::default();
+        let events = parser.feed(
+            b": comment\nevent: update\nid: 7\ndata: first\ndata:second\nretry: 1500\n\ndata\n\n",
+        );
+        assert_eq!(
+            summary(&events),
+            [
+                ("update", Some("7"), "first\nsecond"),
+                ("message", Some("7"), "")
+            ]
+        );
+        assert_eq!(parser.last_event_id.as_deref(), Some("7"));
+        assert_eq!(parser.retry, Some(1500));
+    }
+
+    #[test]
+    fn only_one_leading_space_is_dropped() {
+        let mut parser = SseParser::default();
+        let events = parser.feed(b"data:  indented\ndata: a: b\n\n");
+        assert_eq!(summary(&events), [("message", None, " indented\na: b")]);
+    }
+
+    #[test]
+    fn chunks_split_anywhere() {
+        let stream = b"event: tick\r\ndata: \xe2\x9c\x93 1\r\n\r\ndata: 2\r\n\r\n";
+        let mut parser = SseParser::default();
+        let mut events = vec![];
+        for byte in stream {
+            events.extend(parser.feed(&[*byte]));
+        }
+        assert_eq!(
+            summary(&events),
+            [("tick", None, "\u{2713} 1"), ("message", None, "2")]
+        );
+    }
+
+    #[test]
+    fn events_without_data_are_dropped() {
+        let mut parser = SseParser::default();
+        let events = parser.feed(b"event: ping\n\ndata: x\n\n");
+        // The event name does not carry over to the next event
+        assert_eq!(summary(&events), [("message", None, "x")]);
+    }
+
+    #[test]
+    fn invalid_id_and_retry_are_ignored() {
+        let mut parser = SseParser::default();
+        parser.feed(b"id: 1\nretry: 200\n\nid: a\0b\nretry: soon\n\n");
+        assert_eq!(parser.last_event_id.as_deref(), Some("1"));
+        assert_eq!(parser.retry, Some(200));
+        // An empty id resets it
+        let events = parser.feed(b"id\ndata: x\n\n");
+        assert_eq!(summary(&events), [("message", Some(""), "x")]);
+    }
+
+    #[test]
+    fn reset_keeps_the_last_id_and_retry() {
+        let mut parser = SseParser::default();
+        parser.feed(b"id: 5\nretry: 100\n\nevent: half\ndata: cut");
+        parser.reset();
+        let events = parser.feed(b"data: new\n\n");
+        assert_eq!(summary(&events), [("message", Some("5"), "new")]);
+        assert_eq!(parser.retry, Some(100));
+    }
+
+    #[test]
+    fn log() {
+        let mut parser = SseParser::default();
+        let events = parser.feed(b"event: update\nid: 1\ndata: a\ndata: b\n\ndata: c\n\n");
+        let time = events[0].time.format("%Y-%m-%d %H:%M:%S%.3fZ").to_string();
+        let first = format!(": {}\nevent: update\nid: 1\ndata: a\ndata: b\n\n", time);
+        assert!(event_log(&events).starts_with(&first));
+        assert!(event_log(&events).ends_with("event: message\nid: 1\ndata: c\n\n"));
+    }
+}
@@ -0,0 +1,379 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::{PKey, Private};
+use openssl::x509::X509;
+use reqwest::tls::Version;
+use reqwest::{Certificate, ClientBuilder, Identity};
+use serde_json::{Value, json};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MinTls {
+    #[default]
+    Default,
+    Tls10,
+    Tls11,
+    Tls12,
+}
+
+impl MinTls {
+    // The native TLS backend cannot require 1.3, it is still negotiated when both sides have it
+    pub const ALL: [MinTls; 4] = [MinTls::Default, MinTls::Tls10, MinTls::Tls11, MinTls::Tls12];
+
+    fn version(&self) -> Option<Version> {
+        match self {
+            MinTls::Default => None,
+            MinTls::Tls10 => Some(Version::TLS_1_0),
+            MinTls::Tls11 => Some(Version::TLS_1_1),
+            MinTls::Tls12 => Some(Version::TLS_1_2),
+        }
+    }
+}
+
+impl Display for MinTls {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            MinTls::Default => "Any TLS version",
+            MinTls::Tls10 => "TLS 1.0 or later",
+            MinTls::Tls11 => "TLS 1.1 or later",
+            MinTls::Tls12 => "TLS 1.2 or later",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// A certificate presented to servers that ask for one, picked by the host of the request
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientCert {
+    // example.com, or *.example.com for every subdomain
+    pub host: String,
+    pub cert_file: String,
+    // Only for PEM certificates, PKCS#12 files carry the key
+    pub key_file: String,
+    // Only for PKCS#12
+    pub password: String,
+}
+
+// The certificate with its chain and the key, read with OpenSSL for the TLS stacks that are
+// not reqwest's. PKCS#12 files carry the chain, PEM keys may be PKCS#1 or PKCS#8.
+pub struct LoadedCert {
+    pub cert: X509,
+    pub key: PKey<Private>,
+    pub chain: Vec<X509>,
+}
+
+impl LoadedCert {
+    // Certificate and chain, then the PKCS#8 key, the form tonic and native-tls take
+    pub fn pem(&self) -> Result<(Vec<u8>, Vec<u8>), String> {
+        let mut certs = self.cert.to_pem().map_err(|it| it.to_string())?;
+        for cert in &self.chain {
+            certs.extend(cert.to_pem().map_err(|it| it.to_string())?);
+        }
+        let key = self
+            .key
+            .private_key_to_pem_pkcs8()
+            .map_err(|it| it.to_string())?;
+        Ok((certs, key))
+    }
+}
+
+impl ClientCert {
+    pub fn is_pkcs12(&self) -> bool {
+        Path::new(&self.cert_file)
+            .extension()
+            .and_then(|it| it.to_str())
+            .is_some_and(|it| it.eq_ignore_ascii_case("p12") || it.eq_ignore_ascii_case("pfx"))
+    }
+
+    fn matches(&self, host: &str) -> bool {
+        let pattern = self.host.trim();
+        match pattern.strip_prefix("*.") {
+            Some(domain) => host
+                .strip_suffix(domain)
+                .is_some_and(|it| it.ends_with('.')),
+            None => pattern.eq_ignore_ascii_case(host),
+        }
+    }
+
+    pub fn load(&self) -> Result<LoadedCert, String> {
+        let file = read(&self.cert_file)?;
+        let (cert, key, chain) = if self.is_pkcs12() {
+            let parsed = Pkcs12::from_der(&file)
+                .and_then(|it| it.parse2(&self.password))
+                .map_err(|err| {
+                    format!(
+                        "Could not open {}, check the password: {}",
+                        self.cert_file,
+                        readable(&err.to_string())
+                    )
+                })?;
+            let chain = parsed.ca.into_iter().flatten().collect();
+            (parsed.cert, parsed.pkey, chain)
+        } else {
+            if self.key_file.is_empty() {
+                return Err(format!("Pick the key file for {}", self.cert_file));
+            }
+            let key = PKey::private_key_from_pem(&read(&self.key_file)?);
+            (X509::from_pem(&file).ok(), key.ok(), vec![])
+        };
+        match (cert, key) {
+            (Some(cert), Some(key)) => Ok(LoadedCert { cert, key, chain }),
+            _ => Err(format!(
+                "Could not load the client certificate {}",
+                self.cert_file
+            )),
+        }
+    }
+
+    fn identity(&self) -> Result<Identity, String> {
+        let cert = read(&self.cert_file)?;
+        if self.is_pkcs12() {
+            return Identity::from_pkcs12_der(&cert, &self.password).map_err(|err| {
+                format!(
+                    "Could not open {}, check the password: {}",
+                    self.cert_file,
+                    source_message(&err)
+                )
+            });
+        }
+        if self.key_file.is_empty() {
+            return Err(format!("Pick the key file for {}", self.cert_file));
+        }
+        let key = read(&self.key_file)?;
+        Identity::from_pkcs8_pem(&cert, &key).map_err(|err| {
+            format!(
+                "Could not load {} with {}, the key must be unencrypted PKCS#8 (BEGIN PRIVATE KEY): {}",
+                self.cert_file,
+                self.key_file,
+                source_message(&err)
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TlsSettings {
+    // PEM files trusted on top of the system roots
+    pub ca_files: Vec<String>,
+    pub client_certs: Vec<ClientCert>,
+    pub min_version: MinTls,
+    pub accept_invalid: bool,
+}
+
+impl TlsSettings {
+    pub fn apply(&self, mut builder: ClientBuilder, host: &str) -> Result<ClientBuilder, String> {
+        for file in &self.ca_files {
+            let certs = Certificate::from_pem_bundle(&read(file)?).map_err(|err| {
+                format!(
+                    "{} is not a PEM certificate: {}",
+                    file,
+                    source_message(&err)
+                )
+            })?;
+            if certs.is_empty() {
+                return Err(format!("{} has no certificates", file));
+            }
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        if let Some(cert) = self.client_cert(host) {
+            builder = builder.identity(cert.identity()?);
+        }
+        if let Some(version) = self.min_version.version() {
+            builder = builder.min_tls_version(version);
+        }
+        Ok(builder
+            .danger_accept_invalid_certs(self.accept_invalid)
+            .danger_accept_invalid_hostnames(self.accept_invalid))
+    }
+
+    // The extra CA certificates, for the TLS stacks that are not reqwest's
+    pub fn ca_certs(&self) -> Result<Vec<X509>, String> {
+        let mut all = vec![];
+        for file in &self.ca_files {
+            let certs = X509::stack_from_pem(&read(file)?).map_err(|err| {
+                format!(
+                    "{} is not a PEM certificate: {}",
+                    file,
+                    readable(&err.to_string())
+                )
+            })?;
+            if certs.is_empty() {
+                return Err(format!("{} has no certificates", file));
+            }
+            all.extend(certs);
+        }
+        Ok(all)
+    }
+
+    // WebSockets connect through tungstenite, which takes a native-tls connector
+    pub fn native_connector(&self, host: &str) -> Result<native_tls::TlsConnector, String> {
+        let mut builder = native_tls::TlsConnector::builder();
+        for cert in self.ca_certs()? {
+            let der = cert.to_der().map_err(|it| it.to_string())?;
+            builder.add_root_certificate(
+                native_tls::Certificate::from_der(&der).map_err(|it| it.to_string())?,
+            );
+        }
+        if let Some(cert) = self.client_cert(host) {
+            let (certs, key) = cert.load()?.pem()?;
+            builder.identity(
+                native_tls::Identity::from_pkcs8(&certs, &key).map_err(|it| it.to_string())?,
+            );
+        }
+        builder
+            .min_protocol_version(match self.min_version {
+                MinTls::Default => None,
+                MinTls::Tls10 => Some(native_tls::Protocol::Tlsv10),
+                MinTls::Tls11 => Some(native_tls::Protocol::Tlsv11),
+                MinTls::Tls12 => Some(native_tls::Protocol::Tlsv12),
+            })
+            .danger_accept_invalid_certs(self.accept_invalid)
+            .danger_accept_invalid_hostnames(self.accept_invalid)
+            .build()
+            .map_err(|err| source_message(&err))
+    }
+
+    // gRPC goes through tonic and rustls. rustls never negotiates below TLS 1.2, which meets
+    // every minimum version, and it cannot skip certificate checks.
+    pub fn tonic_config(&self, host: &str) -> Result<tonic::transport::ClientTlsConfig, String> {
+        let mut config = tonic::transport::ClientTlsConfig::new().with_native_roots();
+        for cert in self.ca_certs()? {
+            let pem = cert.to_pem().map_err(|it| it.to_string())?;
+            config = config.ca_certificate(tonic::transport::Certificate::from_pem(pem));
+        }
+        if let Some(cert) = self.client_cert(host) {
+            let (certs, key) = cert.load()?.pem()?;
+            config = config.identity(tonic::transport::Identity::from_pem(certs, key));
+        }
+        Ok(config)
+    }
+
+    // The certificate presented to `host`, the first one whose host matches
+    pub fn client_cert(&self, host: &str) -> Option<&ClientCert> {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        self.client_certs.iter().find(|it| it.matches(&host))
+    }
+
+    pub fn to_json(&self) -> String {
+        json!({
+            "ca_files": self.ca_files,
+            "client_certs": self
+                .client_certs
+                .iter()
+                .map(|it| {
+                    json!({
+                        "host": it.host,
+                        "cert_file": it.cert_file,
+                        "key_file": it.key_file,
+                        "password": it.password,
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "min_version": match self.min_version {
+                MinTls::Default => "default",
+                MinTls::Tls10 => "1.0",
+                MinTls::Tls11 => "1.1",
+                MinTls::Tls12 => "1.2",
+            },
+            "accept_invalid": self.accept_invalid,
+        })
+        .to_string()
+    }
+
+    pub fn from_json(text: &str) -> TlsSettings {
+        let value: Value = serde_json::from_str(text).unwrap_or_default();
+        let string = |it: &Value| it.as_str().unwrap_or_default().to_string();
+        TlsSettings {
+            ca_files: value["ca_files"]
+                .as_array()
+                .map(|files| files.iter().map(string).collect())
+                .unwrap_or_default(),
+            client_certs: value["client_certs"]
+                .as_array()
+                .map(|certs| {
+                    certs
+                        .iter()
+                        .map(|it| ClientCert {
+                            host: string(&it["host"]),
+                            cert_file: string(&it["cert_file"]),
+                            key_file: string(&it["key_file"]),
+                            password: string(&it["password"]),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            min_version: match value["min_version"].as_str() {
+                Some("1.0") => MinTls::Tls10,
+                Some("1.1") => MinTls::Tls11,
+                Some("1.2") => MinTls::Tls12,
+                _ => MinTls::Default,
+            },
+            accept_invalid: value["accept_invalid"].as_bool().unwrap_or_default(),
+        }
+    }
+}
+
+pub fn read(file: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(file).map_err(|err| format!("Could not read {}: {}", file, err))
+}
+
+// The message of the error and of every error under it, outermost first
+fn messages(err: &(dyn Error + 'static)) -> Vec<String> {
+    let mut messages = vec![];
+    let mut current = Some(err);
+    while let Some(err) = current {
+        messages.push(err.to_string());
+        current = err.source();
+    }
+    messages
+}
+
+// reqwest wraps the TLS library error, whose message is the useful part
+fn source_message(err: &(dyn Error + 'static)) -> String {
+    readable(&messages(err).pop().unwrap_or_default())
+}
+
+// OpenSSL errors read "error:0A000086:SSL routines:function:reason:file.c:1889:", followed
+// by the certificate check result in parentheses when there is one. Some wrappers prefix it.
+fn readable(message: &str) -> String {
+    let message = message
+        .match_indices("error:")
+        .map(|(start, _)| &message[start..])
+        .find(|it| {
+            it[6..]
+                .split(':')
+                .next()
+                .is_some_and(|code| code.len() == 8)
+        })
+        .unwrap_or(message);
+    let reason = match message.splitn(6, ':').collect::<Vec<_>>()[..] {
+        ["error", _, _, _, reason, _] => reason,
+        _ => return message.to_string(),
+    };
+    match message
+        .trim_end()
+        .strip_suffix(')')
+        .and_then(|it| it.rsplit_once(" ("))
+    {
+        Some((_, result)) => format!("{}: {}", reason, result),
+        None => reason.to_string(),
+    }
+}
+
+// Why the handshake failed when it was over the server's certificate or our own,
+// e.g. "certificate verify failed: self-signed certificate in certificate chain"
+pub fn certificate_error(err: &(dyn Error + 'static)) -> Option<String> {
+    // The innermost message, outer ones repeat it with prefixes
+    messages(err)
+        .into_iter()
+        .rev()
+        .find(|it| {
+            let lower = it.to_ascii_lowercase();
+            lower.contains("certificate") || lower.contains("hostname mismatch")
+        })
+        .map(|it| readable(&it))
+}
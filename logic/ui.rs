@@ -1,4 +1,5 @@
 use crate::logic::common::Message;
+use crate::logic::highlight::Token;
 use iced::Alignment::Center;
 use iced::Length::Shrink;
 use iced::advanced::text::{Paragraph, paragraph};
@@ -29,6 +30,9 @@ pub enum Icons {
     Check,
     Close,
     Format,
+    Wrap,
+    Minify,
+    Save,
 }
 
 pub enum ButtonType {
@@ -57,6 +61,20 @@ pub fn match_icon(ic: Icons) -> Vec<u8> {
         Icons::Check => include_bytes!("../res/icons/check.svg").to_vec(),
         Icons::Close => include_bytes!("../res/icons/close.svg").to_vec(),
         Icons::Format => include_bytes!("../res/icons/format.svg").to_vec(),
+        Icons::Wrap => include_bytes!("../res/icons/wrap.svg").to_vec(),
+        Icons::Minify => include_bytes!("../res/icons/compress.svg").to_vec(),
+        Icons::Save => include_bytes!("../res/icons/download.svg").to_vec(),
+    }
+}
+
+pub fn token_color(token: Token, palette: &Palette) -> Color {
+    match token {
+        Token::Key | Token::Tag => palette.primary,
+        Token::String => palette.success,
+        Token::Number | Token::Literal => palette.danger,
+        Token::Attribute => Color::parse("#7DCFFF").unwrap(),
+        Token::Punctuation | Token::Comment => palette.text.scale_alpha(0.6),
+        Token::Plain => palette.text,
     }
 }
 
@@ -520,6 +538,24 @@ pub fn card_clickable<'a, T: Paddable<'a>>(
     )
 }
 
+// A compact clickable line, for lists that would get too tall with cards
+pub fn line_clickable<'a>(
+    content: Row<'a, Message, Theme, Renderer>,
+    on_click: Option<Message>,
+) -> Button<'a, Message> {
+    button(content)
+        .padding([4, 8])
+        .on_press_maybe(on_click)
+        .style(|theme, status| button::Style {
+            border: border::rounded(0),
+            background: Some(iced::Background::Color(match status {
+                button::Status::Hovered => Color::parse("#32333D").unwrap(),
+                _ => Color::TRANSPARENT,
+            })),
+            ..button::text(theme, status)
+        })
+}
+
 pub fn ml<'a>(content: Element<'a, Message>, margin_left: f32) -> Container<'a, Message> {
     container(content).padding(Padding {
         top: 0.0,
@@ -546,3 +582,18 @@ pub fn mb<'a>(content: Element<'a, Message>, margin_bottom: f32) -> Container<'a
         bottom: margin_bottom,
     })
 }
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{Duration, SecondsFormat, Utc};
+use rand::Rng;
+use rand::distr::Alphanumeric;
+use regex::{Captures, Regex};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+static VARIABLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([^{}]+?)\s*\}\}").unwrap());
+
+// Replaces {{name}} with the value of the variable and {{$name args}} with a fresh dynamic
+// value, unknown names are left as they are. Every replacement is added to `resolved`.
+pub fn expand(
+    text: &str,
+    variables: &HashMap<String, String>,
+    resolved: &mut Vec<(String, String)>,
+) -> String {
+    if !text.contains("{{") {
+        return text.to_string();
+    }
+    VARIABLE
+        .replace_all(text, |captures: &Captures| {
+            let expression = &captures[1];
+            let value = match expression.strip_prefix('$') {
+                Some(dynamic) => evaluate(dynamic, variables),
+                None => variables.get(expression).cloned(),
+            };
+            match value {
+                Some(value) => {
+                    let entry = (captures[0].to_string(), value.clone());
+                    if expression.starts_with('$') || !resolved.contains(&entry) {
+                        resolved.push(entry);
+                    }
+                    value
+                }
+                None => captures[0].to_string(),
+            }
+        })
+        .to_string()
+}
+
+fn evaluate(expression: &str, variables: &HashMap<String, String>) -> Option<String> {
+    let (name, argument) = match expression.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (expression, ""),
+    };
+    let now = || Some(Utc::now() + offset(argument)?);
+    let mut rng = rand::rng();
+    Some(match name {
+        "uuid" => Uuid::new_v4().to_string(),
+        "uuidv7" => Uuid::now_v7().to_string(),
+        "timestamp" => now()?.timestamp().to_string(),
+        "timestampMs" => now()?.timestamp_millis().to_string(),
+        "isoTimestamp" => now()?.to_rfc3339_opts(SecondsFormat::Millis, true),
+        "randomInt" => {
+            let bounds: Vec<i64> = argument
+                .split_whitespace()
+                .filter_map(|it| it.parse().ok())
+                .collect();
+            let (min, max) = match bounds[..] {
+                [min, max] if min <= max => (min, max),
+                [max] if max >= 0 => (0, max),
+                _ => (0, 1000),
+            };
+            rng.random_range(min..=max).to_string()
+        }
+        "randomString" => {
+            let length = argument.parse().unwrap_or(16).min(4096);
+            (0..length)
+                .map(|_| rng.sample(Alphanumeric) as char)
+                .collect()
+        }
+        "randomEmail" => {
+            let user: String = (0..10)
+                .map(|_| (rng.sample(Alphanumeric) as char).to_ascii_lowercase())
+                .collect();
+            format!("{}@example.com", user)
+        }
+        // Another variable when one has that name, the text itself otherwise
+        "base64" => STANDARD.encode(variables.get(argument).map_or(argument, String::as_str)),
+        _ => return None,
+    })
+}
+
+// "", "+1h", "-30m", "2d" -> a duration, s/m/h/d/w are the units
+fn offset(argument: &str) -> Option<Duration> {
+    if argument.is_empty() {
+        return Some(Duration::zero());
+    }
+    let (number, unit) = argument.split_at(argument.char_indices().last()?.0);
+    let amount: i64 = number.trim_start_matches('+').parse().ok()?;
+    match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => None,
+    }
+}
+
+pub fn resolved_to_json(resolved: &[(String, String)]) -> String {
+    if resolved.is_empty() {
+        return String::new();
+    }
+    Value::Array(
+        resolved
+            .iter()
+            .map(|(name, value)| json!({"name": name, "value": value}))
+            .collect(),
+    )
+    .to_string()
+}
+
+pub fn resolved_from_json(text: &str) -> Vec<(String, String)> {
+    let value: Value = serde_json::from_str(text).unwrap_or_default();
+    value
+        .as_array()
+        .map(|resolved| {
+            resolved
+                .iter()
+                .map(|it| {
+                    (
+                        it["name"].as_str().unwrap_or_default().to_string(),
+                        it["value"].as_str().unwrap_or_default().to_string(),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> HashMap<String, String> {
+        HashMap::from([
+            ("host".to_string(), "api.example.com".to_string()),
+            ("user".to_string(), "ann:secret".to_string()),
+        ])
+    }
+
+    #[test]
+    fn variables_are_replaced_once_in_resolved() {
+        let mut resolved = vec![];
+        let text = expand(
+            "https://{{host}}/{{ host }}?q={{missing}}",
+            &variables(),
+            &mut resolved,
+        );
+        assert_eq!(
+            text,
+            "https://api.example.com/api.example.com?q={{missing}}"
+        );
+        assert_eq!(
+            resolved,
+            [
+                ("{{host}}".to_string(), "api.example.com".to_string()),
+                ("{{ host }}".to_string(), "api.example.com".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn dynamic_values() {
+        let mut resolved = vec![];
+        let text = expand("{{$uuid}} {{$uuid}}", &variables(), &mut resolved);
+        let (first, second) = text.split_once(' ').unwrap();
+        assert!(Uuid::parse_str(first).is_ok());
+        assert_ne!(first, second);
+        // Every dynamic value is listed, they differ each time
+        assert_eq!(resolved.len(), 2);
+
+        let value = |text: &str| expand(text, &variables(), &mut vec![]);
+        assert_eq!(value("{{$base64 user}}"), "YW5uOnNlY3JldA==");
+        assert_eq!(value("{{$base64 plain}}"), "cGxhaW4=");
+        assert_eq!(value("{{$randomString 5}}").len(), 5);
+        assert!(value("{{$randomEmail}}").ends_with("@example.com"));
+        assert_eq!(value("{{$nope}}"), "{{$nope}}");
+        assert_eq!(value("{{$timestamp soon}}"), "{{$timestamp soon}}");
+    }
+
+    #[test]
+    fn random_int_bounds() {
+        let value =
+            |text: &str| -> i64 { expand(text, &variables(), &mut vec![]).parse().unwrap() };
+        for _ in 0..100 {
+            assert!((5..=7).contains(&value("{{$randomInt 5 7}}")));
+            assert!((0..=3).contains(&value("{{$randomInt 3}}")));
+            assert!((0..=1000).contains(&value("{{$randomInt 9 1}}")));
+        }
+    }
+
+    #[test]
+    fn timestamp_offsets() {
+        let value =
+            |text: &str| -> i64 { expand(text, &variables(), &mut vec![]).parse().unwrap() };
+        let now = Utc::now().timestamp();
+        assert!((now..=now + 1).contains(&value("{{$timestamp}}")));
+        assert!((now + 3600..=now + 3601).contains(&value("{{$timestamp +1h}}")));
+        assert!((now - 1800..=now - 1799).contains(&value("{{$timestamp -30m}}")));
+        assert!((now + 172_800..=now + 172_801).contains(&value("{{$timestamp 2d}}")));
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(offset(""), Some(Duration::zero()));
+        assert_eq!(offset("+10s"), Some(Duration::seconds(10)));
+        assert_eq!(offset("1w"), Some(Duration::weeks(1)));
+        assert_eq!(offset("10"), None);
+        assert_eq!(offset("h"), None);
+        assert_eq!(offset("5y"), None);
+    }
+
+    #[test]
+    fn resolved_json_round_trip() {
+        let resolved = vec![("{{host}}".to_string(), "api.example.com".to_string())];
+        assert_eq!(resolved_from_json(&resolved_to_json(&resolved)), resolved);
+        assert_eq!(resolved_to_json(&[]), "");
+        assert!(resolved_from_json("").is_empty());
+    }
+}
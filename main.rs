@@ -2,49 +2,106 @@ mod logic;
 
 use crate::AppTheme;
 use arboard::Clipboard;
-use chrono::{Local, NaiveDateTime, TimeZone};
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
 use iced::Alignment::{self, Center};
 use iced::Length::{Fill, Shrink};
 use iced::font::Weight;
+use iced::futures::channel::mpsc::{self, Sender};
+use iced::futures::{SinkExt, Stream, StreamExt};
 use iced::keyboard::Modifiers;
 use iced::theme::Palette;
 use iced::widget::scrollable::Scrollbar;
+use iced::widget::text::{Span, Wrapping};
 use iced::widget::text_input::focus;
 use iced::widget::{
-    Button, Column, Container, Row, column, container, horizontal_space, row, scrollable, svg, text,
+    Button, Column, Container, PickList, Row, column, container, horizontal_space, image,
+    pick_list, progress_bar, rich_text, row, scrollable, span, svg, text, text_editor,
 };
 use iced::{
-    Background, Border, Color, Element, Font, Renderer, Subscription, Task, Theme, keyboard,
+    Background, Border, Color, Element, Font, Padding, Renderer, Subscription, Task, Theme,
+    keyboard,
 };
+use logic::auth::{Auth, AuthKind};
+use logic::client::ClientSettings;
 use logic::common::*;
-use logic::crud::endpoint::{create_endpoint_full, delete_endpoint};
-use logic::crud::header::create_header_with_tx;
-use logic::crud::query::create_query_param_with_tx;
+use logic::connection::{CertInfo, connection_info, probe_tls};
+use logic::crud::endpoint::{
+    create_endpoint_full, delete_endpoint, update_endpoint_response_filter,
+};
+use logic::crud::header::{create_header, create_header_with_tx, update_header_value};
+use logic::crud::query::{create_query_param, create_query_param_with_tx};
 use logic::crud::response::{
-    create_response, delete_response, response_count_by_endpoint_id, update_response,
+    create_response, delete_response, get_response_body, response_count_by_endpoint_id,
+    update_response, update_response_connection,
 };
+use logic::crud::setting::get_setting;
+use logic::crud::ws_frame::get_ws_frames;
+use logic::data::{Row as DataRow, parse_data};
 use logic::db::{get_db, init, load_endpoints};
-use logic::message_handlers::{message_header, message_query_param};
+use logic::filter::filter_response;
+use logic::format::{BodyLanguage, format_body};
+use logic::graphql::{
+    INTROSPECTION_QUERY, Schema, completions, operation_names, parse_request_body, parse_schema,
+    request_body, response_errors, validate,
+};
+use logic::grpc::{self, find_method, load_protos, method_names, split_url};
+use logic::hex::{hex_dump, hex_string};
+use logic::highlight::highlight_ranges;
+use logic::jsonrpc::{self, error_name, parse_replies};
+use logic::load::{LoadForm, LoadLimit, LoadProgress, LoadReport, LoadRequest};
+use logic::message_handlers::{
+    message_auth, message_body_search, message_data, message_download, message_graphql,
+    message_grpc, message_header, message_load, message_proxy, message_query_param,
+    message_redirect, message_retry, message_rpc, message_script, message_soap, message_sse,
+    message_tls, message_ws,
+};
+use logic::mime::{
+    decode_body, detect_content_type, essence, extension, is_binary, is_event_stream, is_image,
+};
+use logic::oauth::{Grant, OAuth, fetch_token};
+use logic::proxy::{ProxyField, ProxyMode, ProxyScheme, ProxyScope, ProxySettings};
+use logic::redirect::{Hop, RedirectMode, RedirectPolicy, execute};
+use logic::retry::{Attempt, Backoff, RetryPolicy};
+use logic::script::{ScriptRequest, run_post_response, run_pre_request};
+use logic::search::{mark_matches, refresh_matches};
+use logic::signing::{DEFAULT_CANONICAL, SigningField};
+use logic::soap::{Wsdl, parse_fault, parse_wsdl};
+use logic::sse::SseParser;
+use logic::tls::{MinTls, TlsSettings, certificate_error};
 use logic::ui::*;
-use markup_fmt::{Language, format_text};
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use reqwest::{StatusCode, Url};
+use logic::variables::expand;
+use prost_reflect::DescriptorPool;
+use reqwest::header::{
+    AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue, RETRY_AFTER,
+};
+use reqwest::{RequestBuilder, StatusCode, Url};
 use rusqlite::vtab::array::Array;
-use serde_json::Value;
 use std::cmp::max;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::{Connector, connect_async_tls_with_config};
 
 impl State {
     fn new() -> (Self, Task<Message>) {
+        let (proxy, tls) = {
+            let conn = get_db().lock().unwrap();
+            (
+                get_setting(&conn, "proxy").unwrap().unwrap_or_default(),
+                get_setting(&conn, "tls").unwrap().unwrap_or_default(),
+            )
+        };
         (
             Self {
                 draft: Default::default(),
                 copy_request: None,
-                draft_request: Request {
-                    query_params: vec![],
-                    headers: vec![],
-                },
+                draft_request: Request::default(),
                 draft_response: None,
                 draft_method: HttpMethod::GET,
                 endpoints: load_endpoints(&get_db().lock().unwrap(), None, None).unwrap(),
@@ -53,6 +110,48 @@ impl State {
                 selected_endpoint: None,
                 selected_response_index: 0,
                 formatted_response: None,
+                format_language: None,
+                response_filter: "".to_string(),
+                auth: Auth::default(),
+                show_secrets: false,
+                pre_script: text_editor::Content::new(),
+                post_script: text_editor::Content::new(),
+                variables: HashMap::new(),
+                data_run: None,
+                load_form: LoadForm::default(),
+                load: None,
+                selected_load_run: None,
+                retry: RetryPolicy::default(),
+                redirect: RedirectPolicy::default(),
+                proxy: ProxySettings::from_json(&proxy, ProxySettings::global_default()),
+                endpoint_proxy: ProxySettings::default(),
+                tls: TlsSettings::from_json(&tls),
+                filtered_response: None,
+                response_filter_edits: 0,
+                wrap_response: false,
+                show_certificates: false,
+                probing_tls: false,
+                body_search: BodySearch::default(),
+                shown_body: ShownBody::default(),
+                loaded_body: None,
+                download: None,
+                sse: None,
+                request_handle: None,
+                ws: None,
+                ws_composer: "".to_string(),
+                ws_binary: false,
+                graphql_query: text_editor::Content::new(),
+                graphql_variables: text_editor::Content::new(),
+                graphql_operation: None,
+                graphql_schemas: HashMap::new(),
+                grpc_message: text_editor::Content::new(),
+                grpc_pools: HashMap::new(),
+                proto_paths: vec![],
+                rpc_calls: vec![RpcCall::default()],
+                soap_wsdl_url: String::new(),
+                soap_services: HashMap::new(),
+                soap_operation: None,
+                soap_envelope: text_editor::Content::new(),
                 error_message: None,
                 ctrl_pressed: false,
                 filter_method: None,
@@ -72,8 +171,8 @@ impl State {
     }
 }
 
-fn create_new_endpoint(state: &mut State, parent_id: u64, text: &str, code: StatusCode) {
-    let resp_id = create_response(&get_db().lock().unwrap(), parent_id, &text, code).unwrap();
+fn create_new_endpoint(state: &mut State, parent_id: u64, response: &Response) {
+    let resp_id = create_response(&get_db().lock().unwrap(), parent_id, response).unwrap();
     match &state.copy_request {
         Some(d) => {
             let mut db = get_db().lock().unwrap();
@@ -96,6 +195,14 @@ fn create_new_endpoint(state: &mut State, parent_id: u64, text: &str, code: Stat
     );
 }
 
+// Derived state is brought up to date once per message, after the handlers ran
+fn update_and_refresh(state: &mut State, message: Message) -> Task<Message> {
+    let task = update(state, message);
+    load_response_body(state);
+    refresh_shown_body(state);
+    task
+}
+
 fn update(state: &mut State, message: Message) -> Task<Message> {
     match message {
         Message::SetCtrlPressed(pressed) => {
@@ -108,6 +215,10 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
                 Err(err) => update(state, Message::GotError(err.into())),
             }
         }
+        Message::OpenFile(path) => match open::that(path) {
+            Ok(_) => Task::none(),
+            Err(err) => update(state, Message::GotError(err.into())),
+        },
         Message::SetFilterMethod(method) => {
             state.filter_method = method;
             update(state, Message::RefetchDb)
@@ -121,10 +232,7 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
                     None => {}
                 }
             } else {
-                state.copy_request = Some(Request {
-                    query_params: Vec::new(),
-                    headers: Vec::new(),
-                });
+                state.copy_request = Some(Request::default());
             }
             Task::none()
         }
@@ -139,9 +247,10 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
             match current_response(state) {
                 Some(resp) => {
                     match state.clipboard.lock().unwrap().set_text(
-                        match &state.formatted_response {
-                            Some(it) => it,
-                            None => &resp.text,
+                        match (&state.filtered_response, &state.formatted_response) {
+                            (Some(Ok(it)), _) => it,
+                            (_, Some(it)) => it,
+                            _ => &resp.text,
                         }
                         .to_string(),
                     ) {
@@ -163,61 +272,62 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
                 state.filter_method,
             )
             .unwrap();
+            refresh_filtered_response(state);
             Task::none()
         }
         Message::SetSelectedResponseIndex(index) => {
             state.formatted_response = None;
+            state.format_language = None;
             state.selected_response_index = index;
             state.draft_response = None;
             state.copy_request = None;
+            refresh_filtered_response(state);
+            load_request_editors(state);
             Task::none()
         }
         Message::SetDraft(string) => {
             state.draft = string;
             Task::none()
         }
-        Message::Send => {
-            state.can_send = false;
-            let url = format_url_from_state(state);
-            let headers = headers_from_state(state);
-            let method = method_from_state(state);
-            Task::perform(send_request(url, headers, method), move |res| match res {
-                Ok((text, code)) => Message::GotResponse(text, code, method, false),
-                Err(err) => Message::GotError(err),
-            })
-        }
-        Message::SendDraft => {
-            state.can_send = false;
-            let url = format_url_from_state(state);
-            let headers = headers_from_state(state);
-            let method = method_from_state(state).clone();
-            Task::perform(send_request(url, headers, method), move |res| match res {
-                Ok((text, code)) => Message::GotResponse(text, code, method, true),
-                Err(err) => Message::GotError(err),
-            })
-        }
+        Message::Send => send(state, false),
+        Message::SendDraft => send(state, true),
         Message::Back => {
             state.error_message = None;
             if state.draft_response.is_none() {
                 state.draft_request.headers = vec![];
                 state.draft_request.query_params = vec![];
                 state.selected_endpoint = None;
+                state.response_filter = "".to_string();
+                state.auth = Auth::default();
+                state.pre_script = text_editor::Content::new();
+                state.post_script = text_editor::Content::new();
+                state.data_run = None;
+                state.selected_load_run = None;
+                state.retry = RetryPolicy::default();
+                state.redirect = RedirectPolicy::default();
+                state.endpoint_proxy = ProxySettings::default();
+                state.proto_paths = vec![];
+                state.filtered_response = None;
+                load_request_editors(state);
                 focus("main_urlbar")
             } else {
                 update(state, Message::DiscardDraftResponse)
             }
         }
-        Message::GotResponse(text, code, method, is_draft) => {
+        Message::GotResponse(mut response, method, is_draft) => {
             state.can_send = true;
+            let script = script_text(&state.post_script);
+            if !script.is_empty() && method != HttpMethod::GRPC {
+                response.tests = run_post_response(
+                    &script,
+                    response.code.as_u16(),
+                    &response.headers,
+                    &response.text,
+                    &mut state.variables,
+                );
+            }
             if is_draft {
-                match &mut state.draft_response {
-                    Some(d) => {
-                        d.0 = code;
-                        d.1 = text;
-                        d.2 = Local::now().naive_local();
-                    }
-                    None => state.draft_response = Some((code, text, Local::now().naive_local())),
-                }
+                state.draft_response = Some(*response);
             } else {
                 match state.selected_endpoint {
                     Some(id) => match current_response(state) {
@@ -226,17 +336,15 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
                                 update_response(
                                     &get_db().lock().unwrap(),
                                     current_response.id,
-                                    &text,
-                                    code,
-                                    Local::now().naive_utc(),
+                                    &response,
                                 )
                                 .unwrap();
                             } else {
-                                create_new_endpoint(state, id, &text, code);
+                                create_new_endpoint(state, id, &response);
                             }
                         }
                         None => {
-                            create_new_endpoint(state, id, &text, code);
+                            create_new_endpoint(state, id, &response);
                         }
                     },
                     None => {
@@ -246,18 +354,24 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
                                 id: 0,
                                 url: state.draft.clone(),
                                 responses: [Response {
-                                    id: 0,
-                                    parent_endpoint_id: 0,
-                                    text: text.to_string(),
-                                    code,
-                                    received_time: NaiveDateTime::default(),
                                     request: Request {
                                         query_params: state.draft_request.query_params.to_vec(),
                                         headers: state.draft_request.headers.to_vec(),
+                                        body: response.request.body.clone(),
                                     },
+                                    ..*response
                                 }]
                                 .to_vec(),
                                 method,
+                                response_filter: state.response_filter.clone(),
+                                auth: state.auth.clone(),
+                                pre_script: script_text(&state.pre_script),
+                                post_script: script_text(&state.post_script),
+                                load_runs: vec![],
+                                retry: state.retry.clone(),
+                                redirect: state.redirect.clone(),
+                                proxy: state.endpoint_proxy.clone(),
+                                proto_paths: state.proto_paths.clone(),
                             },
                         )
                         .unwrap();
@@ -286,21 +400,48 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
         }
         Message::QueryParam(message) => message_query_param(state, message),
         Message::Header(message) => message_header(state, message),
+        Message::Auth(message) => message_auth(state, message),
+        Message::Script(message) => message_script(state, message),
+        Message::Data(message) => message_data(state, message),
+        Message::Load(message) => message_load(state, message),
+        Message::Retry(message) => message_retry(state, message),
+        Message::Proxy(message) => message_proxy(state, message),
+        Message::Tls(message) => message_tls(state, message),
+        Message::Redirect(message) => message_redirect(state, message),
         Message::ClickEndpoint(id) => {
             state.formatted_response = None;
+            state.format_language = None;
             state.copy_request = None;
             state.draft_response = None;
             state.selected_endpoint = Some(id);
             state.error_message = None;
+            state.data_run = None;
+            state.selected_load_run = None;
             let count =
                 response_count_by_endpoint_id(&get_db().lock().unwrap(), id).unwrap() as usize;
             state.selected_response_index = max(count, 1) - 1;
-            Task::none()
+            if let Some(endpoint) = state.endpoints.iter().find(|it| it.id == id) {
+                state.response_filter = endpoint.response_filter.clone();
+                state.auth = endpoint.auth.clone();
+                state.pre_script = text_editor::Content::with_text(&endpoint.pre_script);
+                state.post_script = text_editor::Content::with_text(&endpoint.post_script);
+                state.retry = endpoint.retry.clone();
+                state.redirect = endpoint.redirect.clone();
+                state.endpoint_proxy = endpoint.proxy.clone();
+                state.proto_paths = endpoint.proto_paths.clone();
+            }
+            refresh_filtered_response(state);
+            load_request_editors(state);
+            load_saved_protos(state)
         }
         Message::ClickMethod => {
             state.draft_method = match state.draft_method {
                 HttpMethod::GET => HttpMethod::POST,
-                HttpMethod::POST => HttpMethod::GET,
+                HttpMethod::POST => HttpMethod::GQL,
+                HttpMethod::GQL => HttpMethod::GRPC,
+                HttpMethod::GRPC => HttpMethod::RPC,
+                HttpMethod::RPC => HttpMethod::SOAP,
+                HttpMethod::SOAP | HttpMethod::WS => HttpMethod::GET,
             };
             Task::none()
         }
@@ -347,30 +488,131 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
             state.endp_search = query;
             update(state, Message::RefetchDb)
         }
-        Message::FormatResponse => {
-            let current = state
-                .endpoints
-                .iter()
-                .find(|it| Some(it.id) == state.selected_endpoint);
-            match current {
-                Some(e) => {
-                    let current_response = e.responses.get(state.selected_response_index);
-                    match current_response {
-                        Some(resp) => {
-                            state.formatted_response = Some(format_response(&resp.text));
-                        }
-                        None => match &state.draft_response {
-                            Some(draft_resp) => {
-                                state.formatted_response = Some(format_response(&draft_resp.1))
-                            }
-                            None => {}
-                        },
-                    }
+        Message::FormatResponse => format_current_response(state, false),
+        Message::MinifyResponse => format_current_response(state, true),
+        Message::SaveResponseBody => match shown_response(state) {
+            Some(response) => Task::perform(
+                save_body(
+                    response.body.clone(),
+                    format!("response.{}", extension(&response.content_type)),
+                ),
+                |res| match res {
+                    Ok(()) => Message::ClearErrorMessage,
+                    Err(err) => Message::GotError(err),
+                },
+            ),
+            None => Task::none(),
+        },
+        Message::SetFormatLanguage(language) => {
+            state.format_language = Some(language);
+            format_current_response(state, false)
+        }
+        // Filtering parses the whole body, so it waits until typing pauses
+        Message::SetResponseFilter(expression) => {
+            state.response_filter = expression;
+            state.response_filter_edits += 1;
+            let edit = state.response_filter_edits;
+            Task::perform(tokio::time::sleep(Duration::from_millis(300)), move |_| {
+                Message::ApplyResponseFilter(edit)
+            })
+        }
+        Message::ApplyResponseFilter(edit) => {
+            if edit != state.response_filter_edits {
+                return Task::none();
+            }
+            // The pending delayed apply is not needed after Enter
+            state.response_filter_edits += 1;
+            if let Some(id) = state.selected_endpoint {
+                update_endpoint_response_filter(
+                    &get_db().lock().unwrap(),
+                    id,
+                    &state.response_filter,
+                )
+                .unwrap();
+                if let Some(endpoint) = state.endpoints.iter_mut().find(|it| it.id == id) {
+                    endpoint.response_filter = state.response_filter.clone();
                 }
-                None => {}
+            }
+            refresh_filtered_response(state);
+            Task::none()
+        }
+        Message::ToggleResponseWrap => {
+            state.wrap_response = !state.wrap_response;
+            Task::none()
+        }
+        Message::ToggleCertificates => {
+            state.show_certificates = !state.show_certificates;
+            Task::none()
+        }
+        Message::ProbeTls => {
+            let Some((id, server)) = shown_response(state).and_then(|resp| {
+                match resp.connection.as_ref()?.tls.as_ref()? {
+                    Ok(tls) => Some((resp.id, tls.server.clone())),
+                    Err(_) => None,
+                }
+            }) else {
+                return Task::none();
+            };
+            if !client_settings(state).proxy.is_direct() {
+                return update(
+                    state,
+                    Message::GotError(MyErr::Client(
+                        "The certificate chain can't be read through a proxy".to_string(),
+                    )),
+                );
+            }
+            state.probing_tls = true;
+            Task::perform(probe_tls(server, state.tls.clone()), move |res| {
+                Message::GotTlsDetails(id, res)
+            })
+        }
+        Message::GotTlsDetails(id, res) => {
+            state.probing_tls = false;
+            let details = match res {
+                Ok(details) => details,
+                Err(err) => {
+                    return update(
+                        state,
+                        Message::GotError(MyErr::Client(format!(
+                            "Could not read the certificate chain: {}",
+                            err
+                        ))),
+                    );
+                }
+            };
+            state.show_certificates = true;
+            // Drafts are never saved, so they are the only response without an id
+            let resp = match id {
+                0 => state.draft_response.as_mut(),
+                _ => state
+                    .endpoints
+                    .iter_mut()
+                    .flat_map(|it| it.responses.iter_mut())
+                    .find(|it| it.id == id),
             };
+            if let Some(connection) = resp.and_then(|it| it.connection.as_mut()) {
+                connection.tls = Some(Ok(details));
+                if id != 0 {
+                    update_response_connection(&get_db().lock().unwrap(), id, connection).unwrap();
+                }
+            }
             Task::none()
         }
+        Message::FocusFind => {
+            if current_response_text(state).is_some() {
+                focus("response_search")
+            } else {
+                focus("searchbar")
+            }
+        }
+        Message::BodySearch(message) => message_body_search(state, message),
+        Message::Download(message) => message_download(state, message),
+        Message::Sse(message) => message_sse(state, message),
+        Message::Ws(message) => message_ws(state, message),
+        Message::GraphQl(message) => message_graphql(state, message),
+        Message::Grpc(message) => message_grpc(state, message),
+        Message::Rpc(message) => message_rpc(state, message),
+        Message::Soap(message) => message_soap(state, message),
         Message::Focus(it) => focus(it),
         Message::DecrementSelectedResponseIndex => match state.copy_request {
             Some(_) => {
@@ -400,6 +642,8 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
         Message::DiscardDraftResponse => {
             state.draft_response = None;
             state.formatted_response = None;
+            state.format_language = None;
+            refresh_filtered_response(state);
             Task::none()
         }
         Message::IncrementSelectedEndpoint => update(
@@ -511,69 +755,128 @@ fn current_response(state: &State) -> Option<&Response> {
     }
 }
 
-fn current_endpoint(state: &State) -> Option<&EndpointDb> {
-    match state.selected_endpoint {
-        Some(endpoint_id) => state.endpoints.iter().find(|it| it.id == endpoint_id),
-        None => None,
+fn shown_response(state: &State) -> Option<&Response> {
+    match &state.draft_response {
+        Some(draft) => Some(draft),
+        None => current_response(state),
     }
 }
 
-fn detect_mime_type(content: &str) -> &'static str {
-    let trimmed = content.trim();
+fn current_response_text(state: &State) -> Option<&str> {
+    shown_response(state).map(|it| it.text.as_str())
+}
 
-    if trimmed.is_empty() {
-        return "text/plain";
+fn format_current_response(state: &mut State, minify: bool) -> Task<Message> {
+    let Some(response) = shown_response(state) else {
+        return Task::none();
+    };
+    let language = response_language(state, &response.content_type);
+    match format_body(&response.text, language, minify) {
+        Ok(formatted) => {
+            state.formatted_response = Some(formatted);
+            Task::none()
+        }
+        Err(error) => update(state, Message::GotError(MyErr::Format(language, error))),
     }
+}
+
+fn response_language(state: &State, content_type: &str) -> BodyLanguage {
+    state
+        .format_language
+        .unwrap_or_else(|| BodyLanguage::from_mime(&essence(content_type)))
+}
 
-    // JSON - validate properly
-    if let Ok(_) = serde_json::from_str::<serde_json::Value>(trimmed) {
-        return "application/json";
+fn displayed_response_text(state: &State) -> Option<&str> {
+    match (&state.filtered_response, &state.formatted_response) {
+        (Some(Ok(filtered)), _) => Some(filtered),
+        (Some(Err(_)), _) => None,
+        (None, Some(fmt)) => Some(fmt),
+        (None, None) => current_response_text(state),
     }
+}
 
-    // XML
-    if trimmed.starts_with("<?xml") || trimmed.starts_with("<") && trimmed.contains("</") {
-        if trimmed.contains("<!DOCTYPE html") || trimmed.contains("<html") {
-            return "text/html";
+// Saved responses are loaded without their bodies and WebSocket frames, the shown one reads
+// its own
+fn load_response_body(state: &mut State) {
+    let Some(resp) = current_response(state).filter(|it| it.body.is_empty()) else {
+        return;
+    };
+    let (id, received_time, is_image) = (resp.id, resp.received_time, is_image(&resp.content_type));
+    let loaded = match state.loaded_body.take() {
+        Some(loaded) if loaded.response_id == id && loaded.received_time == received_time => loaded,
+        _ => {
+            let conn = get_db().lock().unwrap();
+            let body = get_response_body(&conn, id).unwrap_or_default();
+            LoadedBody {
+                response_id: id,
+                received_time,
+                image: is_image.then(|| image::Handle::from_bytes(body.clone())),
+                body,
+                frames: get_ws_frames(&conn, id).unwrap_or_default(),
+            }
         }
-        if trimmed.contains("<svg") {
-            return "image/svg+xml";
+    };
+    if let Some(resp) = state
+        .endpoints
+        .iter_mut()
+        .flat_map(|it| it.responses.iter_mut())
+        .find(|it| it.id == id)
+    {
+        resp.body = loaded.body.clone();
+        resp.image = loaded.image.clone();
+        if resp.frames.is_empty() {
+            resp.frames = loaded.frames.clone();
         }
-        return "application/xml";
     }
+    state.loaded_body = Some(loaded);
+}
 
-    // YAML
-    if trimmed.starts_with("---") || (trimmed.contains(":\n") && !trimmed.contains('<')) {
-        return "text/yaml";
+// Highlights the body again when the response, its format or the filter changed
+fn refresh_shown_body(state: &mut State) {
+    let binary = shown_response(state)
+        .filter(|it| it.file_path.is_none() && it.image.is_none() && is_binary(&it.content_type));
+    let hex_of = binary.map(|it| (it.id, it.received_time));
+    if state.shown_body.hex_of != hex_of {
+        state.shown_body.hex = binary.map(|it| hex_dump(&it.body)).unwrap_or_default();
+        state.shown_body.hex_of = hex_of;
+    }
+    let shown = shown_response(state)
+        .filter(|it| it.file_path.is_none() && !is_binary(&it.content_type))
+        .map(|resp| response_language(state, &resp.content_type).mime_type());
+    let (text, mime_type) = match (shown, &state.filtered_response) {
+        (None, _) | (_, Some(Err(_))) => ("", ""),
+        (Some(_), Some(Ok(filtered))) => (filtered.as_str(), "application/json"),
+        (Some(mime_type), None) => (
+            displayed_response_text(state).unwrap_or_default(),
+            mime_type,
+        ),
+    };
+    if state.shown_body.text == text && state.shown_body.mime_type == mime_type {
+        return;
     }
+    let (lines, text, mime_type) = (
+        highlight_ranges(text, mime_type),
+        text.to_string(),
+        mime_type.to_string(),
+    );
+    state.shown_body.lines = lines;
+    state.shown_body.text = text;
+    state.shown_body.mime_type = mime_type;
+    refresh_matches(&mut state.body_search, &state.shown_body.text);
+}
 
-    "text/plain"
+fn refresh_filtered_response(state: &mut State) {
+    state.filtered_response = if state.response_filter.trim().is_empty() {
+        None
+    } else {
+        current_response_text(state).map(|body| filter_response(body, &state.response_filter))
+    };
 }
 
-fn format_html(html: &str) -> String {
-    format_text(html, Language::Html, &Default::default(), |s, _| {
-        Ok::<_, std::convert::Infallible>(s.into())
-    })
-    .unwrap_or_else(|_| html.to_string())
-}
-
-fn format_response(s: &str) -> String {
-    let t = detect_mime_type(s);
-    match t {
-        "text/html" => format_html(s),
-        "application/json" => {
-            let v: Result<Value, String> = serde_json::from_str(s).map_err(|it| it.to_string());
-            match v {
-                Ok(value) => {
-                    let res = serde_json::to_string_pretty(&value).map_err(|it| it.to_string());
-                    match res {
-                        Ok(value) => value,
-                        Err(error) => error,
-                    }
-                }
-                Err(error) => error,
-            }
-        }
-        _ => s.to_string(),
+fn current_endpoint(state: &State) -> Option<&EndpointDb> {
+    match state.selected_endpoint {
+        Some(endpoint_id) => state.endpoints.iter().find(|it| it.id == endpoint_id),
+        None => None,
     }
 }
 
@@ -585,9 +888,10 @@ fn view<'a>(state: &'a State) -> Element<'a, Message> {
         container(
             row![
                 text("Interfere v0.2").color(state.theme.palette.text),
-                horizontal_space(),
-                bt("Feedback", Some(Message::Feedback), ButtonType::Text)
+                horizontal_space()
             ]
+            .push_maybe(tls_warning(state))
+            .push(bt("Feedback", Some(Message::Feedback), ButtonType::Text))
             .align_y(Center)
             .padding([0, 8])
             .width(Fill)
@@ -604,7 +908,7 @@ fn view<'a>(state: &'a State) -> Element<'a, Message> {
 
 pub fn main() -> iced::Result {
     init().unwrap();
-    iced::application("Interfere", update, view)
+    iced::application("Interfere", update_and_refresh, view)
         .subscription(subscription)
         .theme(theme)
         .font(include_bytes!("./res/font/Geist-Regular.ttf").as_slice())
@@ -640,23 +944,35 @@ fn endpoint_list(state: &State) -> Element<'_, Message> {
                 8.0
             ),
             mb(
-                Row::from_iter([HttpMethod::GET, HttpMethod::POST].iter().map(|m| {
-                    let method = m.clone();
-                    if state.filter_method.is_some() && state.filter_method.unwrap() == method {
-                        bt(
-                            method.to_string(),
-                            Some(Message::SetFilterMethod(None)),
-                            ButtonType::Primary,
-                        )
-                    } else {
-                        bt(
-                            method.to_string(),
-                            Some(Message::SetFilterMethod(Some(method))),
-                            ButtonType::Outlined,
-                        )
-                    }
-                    .into()
-                }))
+                Row::from_iter(
+                    [
+                        HttpMethod::GET,
+                        HttpMethod::POST,
+                        HttpMethod::GQL,
+                        HttpMethod::GRPC,
+                        HttpMethod::RPC,
+                        HttpMethod::SOAP,
+                        HttpMethod::WS,
+                    ]
+                    .iter()
+                    .map(|m| {
+                        let method = m.clone();
+                        if state.filter_method.is_some() && state.filter_method.unwrap() == method {
+                            bt(
+                                method.to_string(),
+                                Some(Message::SetFilterMethod(None)),
+                                ButtonType::Primary,
+                            )
+                        } else {
+                            bt(
+                                method.to_string(),
+                                Some(Message::SetFilterMethod(Some(method))),
+                                ButtonType::Outlined,
+                            )
+                        }
+                        .into()
+                    })
+                )
                 .spacing(4)
                 .into(),
                 8.0
@@ -721,7 +1037,39 @@ fn endpoint_list(state: &State) -> Element<'_, Message> {
     .into()
 }
 
+fn download_button(state: &State) -> Button<'_, Message> {
+    bi(
+        Icons::Save,
+        if state.can_send {
+            Some(Message::Download(MDownload::Pick))
+        } else {
+            None
+        },
+        ButtonType::Outlined,
+    )
+    .height(Shrink)
+    .padding([14, 16])
+}
+
 fn send_button(state: &State) -> Button<'_, Message> {
+    if method_from_state(state) == HttpMethod::WS {
+        return match state.ws {
+            Some(_) => bti(
+                "Disconnect".to_string(),
+                Icons::Close,
+                Some(Message::Ws(MWs::Disconnect)),
+                ButtonType::Danger,
+            ),
+            None => bti(
+                "Connect".to_string(),
+                Icons::Enter,
+                state.can_send.then_some(Message::Send),
+                ButtonType::Primary,
+            ),
+        }
+        .height(Shrink)
+        .padding([14, 16]);
+    }
     bti(
         if state.copy_request.is_none() && current_response(state).is_some() {
             "Rerun"
@@ -752,9 +1100,14 @@ fn draft_urlbar<'a>(state: &'a State) -> Element<'a, Message> {
     .id("main_urlbar");
     mb(
         column![
-            row![urlbar, method_button(state), send_button(state)]
-                .spacing(8)
-                .align_y(Center)
+            row![
+                urlbar,
+                method_button(state),
+                send_button(state),
+                download_button(state)
+            ]
+            .spacing(8)
+            .align_y(Center)
         ]
         .into(),
         16.0,
@@ -763,9 +1116,10 @@ fn draft_urlbar<'a>(state: &'a State) -> Element<'a, Message> {
 }
 
 fn method_button<'a>(state: &'a State) -> Button<'a, Message> {
+    let method = method_from_state(state);
     bt(
-        state.draft_method.to_string(),
-        Some(Message::ClickMethod),
+        method.to_string(),
+        (method != HttpMethod::WS).then_some(Message::ClickMethod),
         ButtonType::Outlined,
     )
 }
@@ -788,7 +1142,7 @@ fn content<'a>(state: &'a State) -> Column<'a, Message> {
             column![
                 mb(
                     column![
-                        row![urlbar, send_button(state)]
+                        row![urlbar, send_button(state), download_button(state)]
                             .width(Fill)
                             .spacing(8)
                             .align_y(Center)
@@ -799,16 +1153,33 @@ fn content<'a>(state: &'a State) -> Column<'a, Message> {
                 row![
                     scrollable(
                         column![
+                            graphql_panel(state),
+                            grpc_panel(state),
+                            rpc_panel(state),
+                            soap_panel(state),
+                            auth_panel(state),
+                            script_panel(state),
+                            retry_panel(state),
+                            redirect_panel(state),
+                            proxy_panel(state),
+                            tls_panel(state),
+                            data_panel(state),
+                            load_panel(state),
                             query_param_panel(state, endpoint),
                             header_panel(state, endpoint)
                         ]
                         .spacing(16)
                     ),
-                    match state.draft_response {
-                        Some(_) => {
+                    match (live_panel(state), &state.draft_response) {
+                        _ if endpoint.method == HttpMethod::WS => container(column![
+                            response_pager(state, endpoint.responses.len()),
+                            ml(ws_panel(state, endpoint).into(), 16.0)
+                        ]),
+                        (Some(panel), _) => panel,
+                        (None, Some(_)) => {
                             draft_response_panel(state)
                         }
-                        None => {
+                        (None, None) => {
                             container(
                                 match &endpoint.responses.get(state.selected_response_index) {
                                     Some(resp) => {
@@ -828,44 +1199,120 @@ fn content<'a>(state: &'a State) -> Column<'a, Message> {
             draft_urlbar(state),
             row![
                 scrollable(
-                    column![draft_query_param_panel(state), draft_header_panel(state)].spacing(16)
+                    column![
+                        graphql_panel(state),
+                        grpc_panel(state),
+                        rpc_panel(state),
+                        soap_panel(state),
+                        auth_panel(state),
+                        script_panel(state),
+                        retry_panel(state),
+                        redirect_panel(state),
+                        proxy_panel(state),
+                        tls_panel(state),
+                        draft_query_param_panel(state),
+                        draft_header_panel(state)
+                    ]
+                    .spacing(16)
                 ),
-                match state.draft_response {
-                    Some(_) => {
+                match (live_panel(state), &state.draft_response) {
+                    (Some(panel), _) => panel,
+                    (None, Some(_)) => {
                         draft_response_panel(state)
                     }
-                    None => container(column![]),
+                    (None, None) => container(column![]),
                 },
             ],
         ],
     }
 }
 
-fn draft_query_param_panel<'a>(state: &'a State) -> Container<'a, Message> {
+fn graphql_panel(state: &State) -> Container<'_, Message> {
+    if method_from_state(state) != HttpMethod::GQL {
+        return container(column![]);
+    }
+    let query = state.graphql_query.text();
+    let operations = operation_names(&query);
+    let schema = state
+        .graphql_schemas
+        .get(&schema_key(&format_url_from_state(state)));
+    let (line, column) = state.graphql_query.cursor_position();
+    let before_cursor = query
+        .split('\n')
+        .take(line + 1)
+        .enumerate()
+        .map(|(index, it)| match index == line {
+            true => it.chars().take(column).collect(),
+            false => it.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let suggestions =
+        schema
+            .and_then(|it| completions(it, &before_cursor))
+            .map(|(prefix, fields)| {
+                Row::from_iter(fields.into_iter().map(|field| {
+                    let suffix = field[prefix.len()..].to_string();
+                    bt(
+                        field,
+                        Some(Message::GraphQl(MGraphQl::Complete(suffix))),
+                        ButtonType::Outlined,
+                    )
+                    .into()
+                }))
+                .spacing(4)
+                .wrap()
+            });
+    let errors = schema.map(|it| validate(it, &query)).unwrap_or_default();
     container(
         column![
             row![
-                text("Query params"),
+                text("GraphQL").width(Fill),
+                if operations.is_empty() {
+                    Element::from(row![])
+                } else {
+                    pick_list(operations, state.graphql_operation.clone(), |it| {
+                        Message::GraphQl(MGraphQl::SelectOperation(it))
+                    })
+                    .placeholder("Operation")
+                    .into()
+                },
                 bt(
-                    "Add",
-                    Some(Message::QueryParam(MQueryParam::AddQueryParam())),
+                    if schema.is_some() {
+                        "Refetch schema"
+                    } else {
+                        "Fetch schema"
+                    },
+                    Some(Message::GraphQl(MGraphQl::FetchSchema)),
                     ButtonType::Primary,
                 )
             ]
             .spacing(16)
             .padding([0, 8])
-            .align_y(Center)
-            .width(Fill),
+            .align_y(Center),
+            text_editor(&state.graphql_query)
+                .placeholder("query { ... }")
+                .on_action(|it| Message::GraphQl(MGraphQl::EditQuery(it)))
+                .font(Font::MONOSPACE)
+                .height(240),
+            match suggestions {
+                Some(suggestions) => Element::from(suggestions),
+                None => Element::from(row![]),
+            },
             Column::from_iter(
-                state
-                    .draft_request
-                    .query_params
-                    .iter()
-                    .map(|it| query_row(it, state, false).into()),
+                errors
+                    .into_iter()
+                    .map(|it| text(it).size(12).color(state.theme.palette.danger).into())
             )
-            .spacing(8)
+            .spacing(4),
+            text("Variables").size(14),
+            text_editor(&state.graphql_variables)
+                .placeholder("{ \"id\": 1 }")
+                .on_action(|it| Message::GraphQl(MGraphQl::EditVariables(it)))
+                .font(Font::MONOSPACE)
+                .height(120),
         ]
-        .spacing(16),
+        .spacing(8),
     )
     .style(|t| container::Style {
         border: Border::default().rounded(16),
@@ -874,217 +1321,130 @@ fn draft_query_param_panel<'a>(state: &'a State) -> Container<'a, Message> {
     })
 }
 
-fn draft_response_panel(state: &State) -> container::Container<'_, Message> {
-    match &state.draft_response {
-        Some(draft) => card(column![
-            mb(text("Temporary response").into(), 8.0),
+fn grpc_panel(state: &State) -> Container<'_, Message> {
+    if method_from_state(state) != HttpMethod::GRPC {
+        return container(column![]);
+    }
+    let (origin, path) = split_url(&format_url_from_state(state));
+    let pool = state.grpc_pools.get(&origin);
+    let methods = pool.map(method_names).unwrap_or_default();
+    let method = pool.and_then(|it| find_method(it, &path));
+    container(
+        column![
             row![
-                bi(
-                    Icons::Left,
-                    Some(Message::DiscardDraftResponse),
-                    ButtonType::Text
+                text("gRPC").width(Fill),
+                bt(
+                    "Reflect",
+                    Some(Message::Grpc(MGrpc::Reflect)),
+                    ButtonType::Primary,
                 ),
-                row![
-                    container(text!("{}", draft.0).style(|_| {
-                        text::Style {
-                            color: Some(Color::BLACK),
-                        }
-                    }))
-                    .padding([2, 4])
-                    .style(|_| {
-                        container::Style {
-                            background: Some(iced::Background::Color(color_for_status(draft.0))),
-                            ..container::Style::default()
-                        }
-                    }),
-                    column![
-                        text(draft.2.format("%H:%M:%S").to_string())
-                            .size(14)
-                            .line_height(1.0),
-                        text(draft.2.format("%d-%m-%Y").to_string())
-                            .size(10)
-                            .line_height(0.9)
-                    ]
-                ]
-                .align_y(Center)
-                .spacing(8)
-                .width(Fill),
-                bi(
-                    Icons::Format,
-                    Some(Message::FormatResponse),
-                    ButtonType::Text
+                bt(
+                    "Load .proto",
+                    Some(Message::Grpc(MGrpc::PickProtos)),
+                    ButtonType::Outlined,
                 )
             ]
-            .align_y(Center)
-            .spacing(8),
-            mb(
-                scrollable(match &state.formatted_response {
-                    Some(fmt) => text(fmt),
-                    None => text(&draft.1),
-                })
-                .direction(iced::widget::scrollable::Direction::Both {
-                    vertical: Scrollbar::default(),
-                    horizontal: Scrollbar::default()
+            .spacing(16)
+            .padding([0, 8])
+            .align_y(Center),
+            if methods.is_empty() {
+                Element::from(
+                    text("List the methods with server reflection or from .proto files")
+                        .size(14)
+                        .style(|t: &Theme| text::Style {
+                            color: Some(t.palette().text.scale_alpha(0.6)),
+                        }),
+                )
+            } else {
+                pick_list(methods, (!path.is_empty()).then_some(path), |it| {
+                    Message::Grpc(MGrpc::SelectMethod(it))
                 })
-                .height(Fill)
+                .placeholder("Method")
                 .width(Fill)
-                .into(),
-                16.0
-            )
-            .padding([16, 0])
-        ])
-        .width(Fill),
-        None => container(column![]),
-    }
+                .into()
+            },
+            match method {
+                Some(method) => text!(
+                    "{} → {}{}",
+                    method.input().full_name(),
+                    if method.is_server_streaming() {
+                        "stream "
+                    } else {
+                        ""
+                    },
+                    method.output().full_name()
+                )
+                .size(12),
+                None => text(""),
+            },
+            text_editor(&state.grpc_message)
+                .placeholder("{}")
+                .on_action(|it| Message::Grpc(MGrpc::EditMessage(it)))
+                .font(Font::MONOSPACE)
+                .height(240),
+        ]
+        .spacing(8),
+    )
+    .style(|t| container::Style {
+        border: Border::default().rounded(16),
+        background: Some(iced::Background::Color(t.palette().background)),
+        ..container::Style::default()
+    })
 }
 
-fn query_row<'a>(
-    it: &'a EndpointKvPair,
-    state: &'a State,
-    is_header: bool,
-) -> container::Container<'a, Message> {
+fn rpc_panel(state: &State) -> Container<'_, Message> {
+    if method_from_state(state) != HttpMethod::RPC {
+        return container(column![]);
+    }
+    let count = state.rpc_calls.len();
     container(
-        match state.selected_endpoint {
-            Some(_) => match &state.copy_request {
-                Some(_) => {
+        column![
+            row![
+                text(if count > 1 {
+                    format!("JSON-RPC batch · {} calls", count)
+                } else {
+                    "JSON-RPC".to_string()
+                })
+                .width(Fill),
+                bt(
+                    "Add call",
+                    Some(Message::Rpc(MRpc::AddCall)),
+                    ButtonType::Primary,
+                )
+            ]
+            .spacing(16)
+            .padding([0, 8])
+            .align_y(Center),
+            Column::from_iter(state.rpc_calls.iter().enumerate().map(|(index, call)| {
+                column![
                     row![
+                        text!("#{}", index + 1),
                         mytext_input(
-                            "Name",
-                            &it.key,
-                            {
-                                let it = it.clone();
-                                move |new_content| {
-                                    if is_header {
-                                        Message::Header(MHeader::SetHeaderKey(it.id, new_content))
-                                    } else {
-                                        Message::QueryParam(MQueryParam::SetQueryParamKey(
-                                            it.id,
-                                            new_content,
-                                        ))
-                                    }
-                                }
-                            },
+                            "Method",
+                            &call.method,
+                            move |it| Message::Rpc(MRpc::SetMethod(index, it)),
                             None
+                        ),
+                        bi(
+                            Icons::Delete,
+                            (count > 1).then_some(Message::Rpc(MRpc::DeleteCall(index))),
+                            ButtonType::Text,
                         )
-                        .id(format!("query_param_{}", it.id)),
-                        mytext_input(
-                            "Value",
-                            &it.value,
-                            {
-                                let it = it.clone();
-                                move |new_content| {
-                                    if is_header {
-                                        Message::Header(MHeader::SetHeaderContent(
-                                            it.id,
-                                            new_content,
-                                        ))
-                                    } else {
-                                        Message::QueryParam(MQueryParam::SetQueryParamContent(
-                                            it.id,
-                                            new_content,
-                                        ))
-                                    }
-                                }
-                            },
-                            None,
-                        ),
-                        bi(
-                            Icons::Check,
-                            Some(if is_header {
-                                Message::Header(MHeader::ToggleHeaderIsOn(it.id))
-                            } else {
-                                Message::QueryParam(MQueryParam::ToggleQueryParamIsOn(it.id))
-                            }),
-                            if it.on {
-                                ButtonType::Primary
-                            } else {
-                                ButtonType::Text
-                            },
-                        ),
-                        bi(
-                            Icons::Delete,
-                            Some(if is_header {
-                                Message::Header(MHeader::DeleteHeader(it.id))
-                            } else {
-                                Message::QueryParam(MQueryParam::DeleteQueryParam(it.id))
-                            }),
-                            ButtonType::Text,
-                        )
-                    ]
-                }
-                None => {
-                    row![
-                        Element::from(card(row![text(&it.key).width(Fill)])),
-                        Element::from(card(row![text(&it.value)].width(Fill))),
                     ]
-                }
-            },
-            None => {
-                row![
-                    mytext_input(
-                        "Name",
-                        &it.key,
-                        {
-                            let it = it.clone();
-                            move |new_content| {
-                                if is_header {
-                                    Message::Header(MHeader::SetHeaderKey(it.id, new_content))
-                                } else {
-                                    Message::QueryParam(MQueryParam::SetQueryParamKey(
-                                        it.id,
-                                        new_content,
-                                    ))
-                                }
-                            }
-                        },
-                        None
-                    )
-                    .id(format!("query_param_{}", it.id)),
-                    mytext_input(
-                        "Value",
-                        &it.value,
-                        {
-                            let it = it.clone();
-                            move |new_content| {
-                                if is_header {
-                                    Message::Header(MHeader::SetHeaderContent(it.id, new_content))
-                                } else {
-                                    Message::QueryParam(MQueryParam::SetQueryParamContent(
-                                        it.id,
-                                        new_content,
-                                    ))
-                                }
-                            }
-                        },
-                        None,
-                    ),
-                    bi(
-                        Icons::Check,
-                        Some(if is_header {
-                            Message::Header(MHeader::ToggleHeaderIsOn(it.id))
-                        } else {
-                            Message::QueryParam(MQueryParam::ToggleQueryParamIsOn(it.id))
-                        }),
-                        if it.on {
-                            ButtonType::Primary
-                        } else {
-                            ButtonType::Text
-                        },
-                    ),
-                    bi(
-                        Icons::Delete,
-                        Some(if is_header {
-                            Message::Header(MHeader::DeleteHeader(it.id))
-                        } else {
-                            Message::QueryParam(MQueryParam::DeleteQueryParam(it.id))
-                        }),
-                        ButtonType::Text,
-                    )
+                    .spacing(8)
+                    .align_y(Center),
+                    text_editor(&call.params)
+                        .placeholder("Params: [ ... ] or { ... }")
+                        .on_action(move |it| Message::Rpc(MRpc::EditParams(index, it)))
+                        .font(Font::MONOSPACE)
+                        .height(120),
                 ]
-            }
-        }
-        .align_y(Center)
-        .spacing(8),
+                .spacing(8)
+                .into()
+            }))
+            .spacing(16)
+        ]
+        .spacing(16),
     )
     .style(|t| container::Style {
         border: Border::default().rounded(16),
@@ -1093,62 +1453,225 @@ fn query_row<'a>(
     })
 }
 
-fn query_param_panel<'a>(
-    state: &'a State,
-    endpoint: &'a EndpointDb,
-) -> container::Container<'a, Message> {
-    container(
-        column![
-            if state.copy_request.is_none() && current_response(state).is_some() {
+fn auth_panel(state: &State) -> Container<'_, Message> {
+    let auth = &state.auth;
+    let secret = |placeholder: &str, value: &str, on_input: fn(String) -> MAuth| {
+        mytext_input(
+            placeholder,
+            value,
+            move |it| Message::Auth(on_input(it)),
+            None,
+        )
+        .secure(!state.show_secrets)
+    };
+    let fields = match auth.kind {
+        AuthKind::None => column![],
+        AuthKind::Basic | AuthKind::Digest => column![
+            mytext_input(
+                "Username",
+                &auth.username,
+                |it| Message::Auth(MAuth::SetUsername(it)),
+                None
+            ),
+            secret("Password", &auth.password, MAuth::SetPassword)
+        ],
+        AuthKind::Bearer => column![secret("Token", &auth.token, MAuth::SetToken)],
+        AuthKind::ApiKey => column![
+            row![
+                mytext_input(
+                    "Name, e.g. X-API-Key",
+                    &auth.key_name,
+                    |it| Message::Auth(MAuth::SetKeyName(it)),
+                    None
+                ),
+                bt(
+                    if auth.key_in_query {
+                        "Query param"
+                    } else {
+                        "Header"
+                    },
+                    Some(Message::Auth(MAuth::ToggleKeyInQuery)),
+                    ButtonType::Outlined,
+                )
+            ]
+            .spacing(8)
+            .align_y(Center),
+            secret("Value", &auth.key_value, MAuth::SetKeyValue)
+        ],
+        AuthKind::OAuth2 => {
+            let oauth = &auth.oauth;
+            let mut fields = column![
+                pick_list(Grant::ALL, Some(oauth.grant), |it| {
+                    Message::Auth(MAuth::SetGrant(it))
+                })
+                .width(Fill),
+                mytext_input(
+                    "Token URL",
+                    &oauth.token_url,
+                    |it| Message::Auth(MAuth::SetTokenUrl(it)),
+                    None
+                ),
+            ];
+            if oauth.grant == Grant::AuthorizationCode {
+                fields = fields.push(mytext_input(
+                    "Authorization URL",
+                    &oauth.authorize_url,
+                    |it| Message::Auth(MAuth::SetAuthorizeUrl(it)),
+                    None,
+                ));
+            }
+            fields = fields
+                .push(mytext_input(
+                    "Client ID",
+                    &oauth.client_id,
+                    |it| Message::Auth(MAuth::SetClientId(it)),
+                    None,
+                ))
+                .push(secret(
+                    "Client secret, empty for public clients",
+                    &oauth.client_secret,
+                    MAuth::SetClientSecret,
+                ))
+                .push(mytext_input(
+                    "Scope",
+                    &oauth.scope,
+                    |it| Message::Auth(MAuth::SetScope(it)),
+                    None,
+                ));
+            if oauth.grant == Grant::Password {
+                fields = fields
+                    .push(mytext_input(
+                        "Username",
+                        &auth.username,
+                        |it| Message::Auth(MAuth::SetUsername(it)),
+                        None,
+                    ))
+                    .push(secret("Password", &auth.password, MAuth::SetPassword));
+            }
+            fields.push(
                 row![
-                    column![
+                    text(token_status(oauth)).size(12).width(Fill),
+                    if oauth.token.is_some() {
                         bt(
-                            "Copy",
-                            Some(Message::SetDraftQuery(true)),
-                            ButtonType::Outlined,
-                        ),
-                        text("Query params"),
-                    ]
-                    .spacing(16)
+                            "Clear",
+                            Some(Message::Auth(MAuth::ClearToken)),
+                            ButtonType::Text,
+                        )
+                    } else {
+                        empty_b()
+                    },
+                    bt(
+                        "Get token",
+                        state.can_send.then_some(Message::Auth(MAuth::FetchToken)),
+                        ButtonType::Outlined,
+                    )
                 ]
-            } else {
+                .spacing(8)
+                .padding([0, 8])
+                .align_y(Center),
+            )
+        }
+        AuthKind::AwsSigV4 => {
+            let aws = &auth.aws;
+            let field = |placeholder, value, field| {
+                mytext_input(
+                    placeholder,
+                    value,
+                    move |it| Message::Auth(MAuth::SetSigning(field, it)),
+                    None,
+                )
+            };
+            column![
+                field("Access key ID", &aws.access_key, SigningField::AccessKey),
+                mytext_input(
+                    "Secret access key",
+                    &aws.secret_key,
+                    |it| Message::Auth(MAuth::SetSigning(SigningField::SecretKey, it)),
+                    None
+                )
+                .secure(!state.show_secrets),
                 row![
-                    text("Query params"),
+                    field("Region, e.g. us-east-1", &aws.region, SigningField::Region),
+                    field(
+                        "Service, e.g. execute-api",
+                        &aws.service,
+                        SigningField::Service
+                    ),
+                ]
+                .spacing(8),
+                mytext_input(
+                    "Session token, optional",
+                    &aws.session_token,
+                    |it| Message::Auth(MAuth::SetSigning(SigningField::SessionToken, it)),
+                    None
+                )
+                .secure(!state.show_secrets),
+            ]
+        }
+        AuthKind::Hmac => {
+            let hmac = &auth.hmac;
+            column![
+                mytext_input(
+                    "Secret",
+                    &hmac.secret,
+                    |it| Message::Auth(MAuth::SetSigning(SigningField::HmacSecret, it)),
+                    None
+                )
+                .secure(!state.show_secrets),
+                mytext_input(
+                    DEFAULT_CANONICAL,
+                    &hmac.canonical,
+                    |it| Message::Auth(MAuth::SetSigning(SigningField::HmacCanonical, it)),
+                    None
+                ),
+                text("Placeholders: {method} {url} {host} {path} {query} {timestamp} {body} {body_sha256}, \\n for a line break").size(12),
+                row![
+                    mytext_input(
+                        "Signature header, X-Signature",
+                        &hmac.header,
+                        |it| Message::Auth(MAuth::SetSigning(SigningField::HmacHeader, it)),
+                        None
+                    ),
                     bt(
-                        "Add",
-                        Some(Message::QueryParam(MQueryParam::AddQueryParam())),
-                        ButtonType::Primary,
+                        if hmac.base64 { "Base64" } else { "Hex" },
+                        Some(Message::Auth(MAuth::ToggleHmacBase64)),
+                        ButtonType::Outlined,
                     )
                 ]
-                .spacing(16)
-                .align_y(Center)
-            }
-            .padding([0, 8])
-            .width(Fill),
-            match &state.copy_request {
-                Some(drafts) => {
-                    Column::from_iter(
-                        drafts
-                            .query_params
-                            .iter()
-                            .map(|it| query_row(it, state, false).into()),
+                .spacing(8)
+                .align_y(Center),
+                mytext_input(
+                    "Timestamp header, optional",
+                    &hmac.timestamp_header,
+                    |it| Message::Auth(MAuth::SetSigning(SigningField::HmacTimestampHeader, it)),
+                    None
+                ),
+            ]
+        }
+    };
+    container(
+        column![
+            row![
+                text("Auth").width(Fill),
+                if auth.kind == AuthKind::None {
+                    empty_b()
+                } else {
+                    bt(
+                        if state.show_secrets { "Hide" } else { "Show" },
+                        Some(Message::Auth(MAuth::ToggleSecrets)),
+                        ButtonType::Text,
                     )
-                }
-                None => {
-                    match endpoint.responses.get(state.selected_response_index) {
-                        Some(resp) => Column::from_iter(
-                            resp.request
-                                .query_params
-                                .iter()
-                                .map(|it| query_row(it, state, false).into()),
-                        ),
-                        None => column![],
-                    }
-                }
-            }
-            .spacing(8)
+                },
+                pick_list(AuthKind::ALL, Some(auth.kind), |it| {
+                    Message::Auth(MAuth::SetKind(it))
+                })
+            ]
+            .spacing(16)
+            .padding([0, 8])
+            .align_y(Center),
+            fields.spacing(8),
         ]
-        .spacing(16),
+        .spacing(8),
     )
     .style(|t| container::Style {
         border: Border::default().rounded(16),
@@ -1157,85 +1680,734 @@ fn query_param_panel<'a>(
     })
 }
 
-fn draft_header_panel<'a>(state: &'a State) -> Container<'a, Message> {
+fn script_panel(state: &State) -> Container<'_, Message> {
+    if matches!(method_from_state(state), HttpMethod::GRPC | HttpMethod::WS) {
+        return container(column![]);
+    }
     container(
         column![
-            row![
-                text("Headers"),
-                bt(
-                    "Add",
-                    Some(Message::Header(MHeader::AddHeader())),
-                    ButtonType::Primary,
+            row![text("Scripts")].padding([0, 8]),
+            text("Pre-request, can change request.url, .params, .headers and .body").size(12),
+            text_editor(&state.pre_script)
+                .placeholder("request.headers[\"X-Request-Id\"] = vars.request_id;")
+                .on_action(|it| Message::Script(MScript::EditPreRequest(it)))
+                .font(Font::MONOSPACE)
+                .height(120),
+            text("Post-response, reads response.status, .headers, .body and .json").size(12),
+            text_editor(&state.post_script)
+                .placeholder(
+                    "test(\"status is 200\", response.status == 200);\nvars.token = response.json.token;"
                 )
-            ]
-            .spacing(16)
-            .padding([0, 8])
-            .align_y(Center)
-            .width(Fill),
-            Column::from_iter(
-                state
-                    .draft_request
-                    .headers
-                    .iter()
-                    .map(|it| query_row(it, state, true).into()),
-            )
-            .spacing(8)
+                .on_action(|it| Message::Script(MScript::EditPostResponse(it)))
+                .font(Font::MONOSPACE)
+                .height(120),
         ]
-        .spacing(16),
+        .spacing(8),
     )
-    .style(|t| container::Style {
+    .style(|t: &Theme| container::Style {
         border: Border::default().rounded(16),
         background: Some(iced::Background::Color(t.palette().background)),
         ..container::Style::default()
     })
 }
 
-fn header_panel<'a>(
-    state: &'a State,
-    endpoint: &'a EndpointDb,
-) -> container::Container<'a, Message> {
+fn redirect_panel(state: &State) -> Container<'_, Message> {
+    if matches!(method_from_state(state), HttpMethod::GRPC | HttpMethod::WS) {
+        return container(column![]);
+    }
+    let redirect = &state.redirect;
+    container(
+        row![
+            text("Redirects").width(Fill),
+            pick_list(RedirectMode::ALL, Some(redirect.mode), |it| {
+                Message::Redirect(MRedirect::SetMode(it))
+            }),
+        ]
+        .push_maybe((redirect.mode == RedirectMode::Limit).then(|| {
+            mytext_input(
+                "Hops",
+                &redirect.max_hops.to_string(),
+                |it| Message::Redirect(MRedirect::SetMaxHops(it)),
+                None,
+            )
+            .width(80)
+        }))
+        .push_maybe((redirect.mode != RedirectMode::DontFollow).then(|| {
+            bt(
+                "Keep auth across hosts",
+                Some(Message::Redirect(MRedirect::ToggleKeepAuth)),
+                if redirect.keep_auth {
+                    ButtonType::Primary
+                } else {
+                    ButtonType::Outlined
+                },
+            )
+        }))
+        .spacing(8)
+        .padding([0, 8])
+        .align_y(Center),
+    )
+}
+
+fn retry_panel(state: &State) -> Container<'_, Message> {
+    if matches!(method_from_state(state), HttpMethod::GRPC | HttpMethod::WS) {
+        return container(column![]);
+    }
+    let retry = &state.retry;
+    let toggle = |label: &'static str, on: bool, message: MRetry| {
+        bt(
+            label,
+            Some(Message::Retry(message)),
+            if on {
+                ButtonType::Primary
+            } else {
+                ButtonType::Outlined
+            },
+        )
+    };
+    let header = row![
+        text("Retries").width(Fill),
+        mytext_input(
+            "Max attempts",
+            &retry.max_attempts.to_string(),
+            |it| Message::Retry(MRetry::SetMaxAttempts(it)),
+            None
+        )
+        .width(120)
+    ]
+    .spacing(16)
+    .padding([0, 8])
+    .align_y(Center);
+    if !retry.is_on() {
+        return container(
+            column![
+                header,
+                text("Set more than 1 attempt to retry failed requests").size(12)
+            ]
+            .spacing(8),
+        );
+    }
     container(
         column![
+            header,
             row![
-                text("Headers"),
-                if state.copy_request.is_some() || current_response(state).is_none() {
-                    bt(
-                        "Add",
-                        Some(Message::Header(MHeader::AddHeader())),
-                        ButtonType::Primary,
-                    )
+                text("Retry on").size(12),
+                toggle("5xx", retry.on_5xx, MRetry::ToggleOn5xx),
+                toggle("429", retry.on_429, MRetry::ToggleOn429),
+                toggle(
+                    "Connection errors",
+                    retry.on_network,
+                    MRetry::ToggleOnNetwork
+                ),
+            ]
+            .spacing(8)
+            .align_y(Center),
+            row![
+                pick_list(Backoff::ALL, Some(retry.backoff), |it| {
+                    Message::Retry(MRetry::SetBackoff(it))
+                }),
+                mytext_input(
+                    "Delay, ms",
+                    &retry.delay_ms.to_string(),
+                    |it| Message::Retry(MRetry::SetDelay(it)),
+                    None
+                ),
+            ]
+            .spacing(8)
+            .align_y(Center),
+            row![
+                toggle("Jitter", retry.jitter, MRetry::ToggleJitter),
+                toggle(
+                    "Honor Retry-After",
+                    retry.retry_after,
+                    MRetry::ToggleRetryAfter
+                ),
+            ]
+            .spacing(8)
+            .align_y(Center),
+        ]
+        .spacing(8),
+    )
+}
+
+fn proxy_panel(state: &State) -> Container<'_, Message> {
+    if matches!(method_from_state(state), HttpMethod::GRPC | HttpMethod::WS) {
+        return container(column![]);
+    }
+    let endpoint = &state.endpoint_proxy;
+    let header = row![
+        text("Proxy").width(Fill),
+        if endpoint.effective(&state.proxy).mode == ProxyMode::Manual {
+            bt(
+                if state.show_secrets { "Hide" } else { "Show" },
+                Some(Message::Auth(MAuth::ToggleSecrets)),
+                ButtonType::Text,
+            )
+        } else {
+            empty_b()
+        },
+        pick_list(ProxyMode::ENDPOINT, Some(endpoint.mode), |it| {
+            Message::Proxy(MProxy::SetMode(ProxyScope::Endpoint, it))
+        })
+    ]
+    .spacing(16)
+    .padding([0, 8])
+    .align_y(Center);
+    if endpoint.mode != ProxyMode::Inherit {
+        return container(
+            column![header]
+                .push_maybe(proxy_fields(state, ProxyScope::Endpoint))
+                .push(text("Overrides the global proxy for this endpoint").size(12))
+                .spacing(8),
+        );
+    }
+    container(
+        column![
+            header,
+            row![
+                text("Global, for every endpoint without its own")
+                    .size(12)
+                    .width(Fill),
+                pick_list(ProxyMode::GLOBAL, Some(state.proxy.mode), |it| {
+                    Message::Proxy(MProxy::SetMode(ProxyScope::Global, it))
+                })
+            ]
+            .spacing(8)
+            .padding([0, 8])
+            .align_y(Center),
+        ]
+        .push_maybe(proxy_fields(state, ProxyScope::Global))
+        .push_maybe((state.proxy.mode == ProxyMode::System).then(|| {
+            text("Uses HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY from the environment")
+                .size(12)
+        }))
+        .spacing(8),
+    )
+}
+
+fn proxy_fields(state: &State, scope: ProxyScope) -> Option<Column<'_, Message>> {
+    let proxy = match scope {
+        ProxyScope::Global => &state.proxy,
+        ProxyScope::Endpoint => &state.endpoint_proxy,
+    };
+    if proxy.mode != ProxyMode::Manual {
+        return None;
+    }
+    let input = |placeholder: &str, field: ProxyField| {
+        mytext_input(
+            placeholder,
+            proxy.field(field),
+            move |it| Message::Proxy(MProxy::EditField(scope, field, it)),
+            None,
+        )
+    };
+    Some(
+        column![
+            row![
+                pick_list(ProxyScheme::ALL, Some(proxy.scheme), move |it| {
+                    Message::Proxy(MProxy::SetScheme(scope, it))
+                }),
+                input("Address, host:port", ProxyField::Address),
+            ]
+            .spacing(8)
+            .align_y(Center),
+            row![
+                input("Username, optional", ProxyField::Username),
+                input("Password", ProxyField::Password).secure(!state.show_secrets),
+            ]
+            .spacing(8),
+            input(
+                "No proxy for, e.g. localhost, .internal, 10.0.0.0/8",
+                ProxyField::NoProxy
+            ),
+        ]
+        .spacing(8),
+    )
+}
+
+// Shown while certificates are not checked, so it is not left on by accident
+fn tls_warning<'a>(state: &State) -> Option<Container<'a, Message>> {
+    let danger = state.theme.palette.danger;
+    state.tls.accept_invalid.then(|| {
+        container(
+            text("Certificates not verified")
+                .size(13)
+                .color(Color::BLACK),
+        )
+        .padding([0, 4])
+        .style(move |_| container::Style {
+            background: Some(Background::Color(danger)),
+            ..container::Style::default()
+        })
+    })
+}
+
+fn tls_panel(state: &State) -> Container<'_, Message> {
+    if matches!(method_from_state(state), HttpMethod::GRPC | HttpMethod::WS) {
+        return container(column![]);
+    }
+    let tls = &state.tls;
+    let header = row![text("TLS").width(Fill)]
+        .push_maybe(tls_warning(state))
+        .push(pick_list(MinTls::ALL, Some(tls.min_version), |it| {
+            Message::Tls(MTls::SetMinVersion(it))
+        }))
+        .spacing(16)
+        .padding([0, 8])
+        .align_y(Center);
+    let file_name = |path: &str| {
+        Path::new(path)
+            .file_name()
+            .map_or(path.to_string(), |it| it.to_string_lossy().to_string())
+    };
+    let ca_files = tls.ca_files.iter().enumerate().map(|(index, file)| {
+        row![
+            text(format!("Trusted CA {}", file_name(file)))
+                .size(12)
+                .width(Fill),
+            bi(
+                Icons::Delete,
+                Some(Message::Tls(MTls::RemoveCa(index))),
+                ButtonType::Inline,
+            ),
+        ]
+        .spacing(8)
+        .padding([0, 8])
+        .align_y(Center)
+        .into()
+    });
+    let client_certs = tls.client_certs.iter().enumerate().map(|(index, cert)| {
+        let secret: Element<'_, Message> = if cert.is_pkcs12() {
+            mytext_input(
+                "Password",
+                &cert.password,
+                move |it| Message::Tls(MTls::SetCertPassword(index, it)),
+                None,
+            )
+            .secure(!state.show_secrets)
+            .into()
+        } else {
+            bt(
+                if cert.key_file.is_empty() {
+                    "Pick key".to_string()
                 } else {
-                    empty_b()
-                }
+                    file_name(&cert.key_file)
+                },
+                Some(Message::Tls(MTls::PickKey(index))),
+                ButtonType::Outlined,
+            )
+            .into()
+        };
+        column![
+            row![
+                text(format!("Client certificate {}", file_name(&cert.cert_file)))
+                    .size(12)
+                    .width(Fill),
+                bi(
+                    Icons::Delete,
+                    Some(Message::Tls(MTls::RemoveClientCert(index))),
+                    ButtonType::Inline,
+                ),
             ]
-            .spacing(16)
+            .spacing(8)
             .padding([0, 8])
-            .align_y(Center)
+            .align_y(Center),
+            row![
+                mytext_input(
+                    "Host, api.example.com or *.example.com",
+                    &cert.host,
+                    move |it| Message::Tls(MTls::SetCertHost(index, it)),
+                    None
+                ),
+                secret,
+            ]
+            .spacing(8)
+            .align_y(Center),
+        ]
+        .spacing(8)
+        .into()
+    });
+    container(
+        column![header]
+            .extend(ca_files)
+            .extend(client_certs)
+            .push(
+                row![
+                    bt(
+                        "Add CA",
+                        Some(Message::Tls(MTls::PickCa)),
+                        ButtonType::Outlined
+                    ),
+                    bt(
+                        "Add client certificate",
+                        Some(Message::Tls(MTls::PickClientCert)),
+                        ButtonType::Outlined
+                    ),
+                    bt(
+                        "Accept invalid certificates",
+                        Some(Message::Tls(MTls::ToggleAcceptInvalid)),
+                        if tls.accept_invalid {
+                            ButtonType::Danger
+                        } else {
+                            ButtonType::Outlined
+                        }
+                    ),
+                ]
+                .spacing(8)
+                .wrap(),
+            )
+            .spacing(8),
+    )
+}
+
+fn data_panel(state: &State) -> Container<'_, Message> {
+    if state.selected_endpoint.is_none()
+        || matches!(method_from_state(state), HttpMethod::GRPC | HttpMethod::WS)
+    {
+        return container(column![]);
+    }
+    let header = row![
+        text("Data").width(Fill),
+        bt(
+            "Load CSV/JSON",
+            state.can_send.then_some(Message::Data(MData::PickFile)),
+            ButtonType::Outlined,
+        )
+    ]
+    .spacing(16)
+    .padding([0, 8])
+    .align_y(Center);
+    let Some(run) = &state.data_run else {
+        return container(
+            column![
+                header,
+                text("Send the request once per row, {{column}} is replaced with the row value")
+                    .size(12)
+            ]
+            .spacing(8),
+        );
+    };
+    let controls = row![
+        text(format!("{}, {} rows", run.file, run.rows.len()))
+            .size(12)
             .width(Fill),
-            match &state.copy_request {
-                Some(drafts) => {
-                    Column::from_iter(
-                        drafts
-                            .headers
-                            .iter()
-                            .map(|it| query_row(it, state, true).into()),
-                    )
-                }
-                None => {
-                    match endpoint.responses.get(state.selected_response_index) {
-                        Some(resp) => Column::from_iter(
-                            resp.request
-                                .headers
-                                .iter()
-                                .map(|it| query_row(it, state, true).into()),
-                        ),
-                        None => column![],
-                    }
-                }
+        if run.running {
+            bt("Stop", Some(Message::Data(MData::Stop)), ButtonType::Danger)
+        } else {
+            bt(
+                "Run",
+                state.can_send.then_some(Message::Data(MData::Run)),
+                ButtonType::Primary,
+            )
+        },
+        bt(
+            "Clear",
+            (!run.running).then_some(Message::Data(MData::Clear)),
+            ButtonType::Text,
+        )
+    ]
+    .spacing(8)
+    .align_y(Center);
+    let rows = run.rows.iter().enumerate().map(|(index, values)| {
+        let values = values
+            .iter()
+            .map(|(_, value)| value.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let values = match values.char_indices().nth(40) {
+            Some((end, _)) => format!("{}…", &values[..end]),
+            None => values,
+        };
+        let result = run.results.get(index);
+        let status = match result.map(|it| &it.status) {
+            Some(Ok(code)) => text(code.as_u16().to_string()).color(color_for_status(*code)),
+            Some(Err(_)) => text("Error").style(text::danger),
+            None if run.running && index == run.results.len() => text("…"),
+            None => text(""),
+        };
+        let details = match result {
+            Some(result) if result.tests_total > 0 => format!(
+                "{} ms, {}/{} passed",
+                result.duration.as_millis(),
+                result.tests_passed,
+                result.tests_total
+            ),
+            Some(result) if result.status.is_ok() => {
+                format!("{} ms", result.duration.as_millis())
             }
+            Some(result) => result.status.clone().err().unwrap_or_default(),
+            None => String::new(),
+        };
+        line_clickable(
+            row![
+                text(format!("#{}", index + 1)).size(12).width(36),
+                text(values).size(12).width(Fill),
+                status.size(12).width(40),
+                text(details).size(12),
+            ]
             .spacing(8)
+            .align_y(Center),
+            result
+                .and_then(|it| it.response_id)
+                .filter(|_| !run.running)
+                .map(|id| Message::Data(MData::ShowResponse(id))),
+        )
+        .width(Fill)
+        .into()
+    });
+    container(column![header, controls, Column::with_children(rows)].spacing(8))
+}
+
+fn load_panel(state: &State) -> Container<'_, Message> {
+    let Some(endpoint) = current_endpoint(state) else {
+        return container(column![]);
+    };
+    if matches!(endpoint.method, HttpMethod::GRPC | HttpMethod::WS) {
+        return container(column![]);
+    }
+    let form = &state.load_form;
+    let running = state
+        .load
+        .as_ref()
+        .filter(|it| it.endpoint_id == endpoint.id);
+    let header = row![
+        text("Load test").width(Fill),
+        match (&state.load, running) {
+            (_, Some(_)) => bt("Stop", Some(Message::Load(MLoad::Stop)), ButtonType::Danger,),
+            (Some(_), None) => bt("Start", None, ButtonType::Primary),
+            (None, None) => bt(
+                "Start",
+                state.can_send.then_some(Message::Load(MLoad::Start)),
+                ButtonType::Primary,
+            ),
+        }
+    ]
+    .spacing(16)
+    .padding([0, 8])
+    .align_y(Center);
+    let settings = column![
+        row![
+            pick_list(LoadLimit::ALL, Some(form.limit), |it| {
+                Message::Load(MLoad::SetLimit(it))
+            }),
+            mytext_input(
+                "Amount",
+                &form.amount,
+                |it| Message::Load(MLoad::SetAmount(it)),
+                None
+            ),
         ]
-        .spacing(16),
+        .spacing(8)
+        .align_y(Center),
+        row![
+            mytext_input(
+                "Concurrency",
+                &form.concurrency,
+                |it| Message::Load(MLoad::SetConcurrency(it)),
+                None
+            ),
+            mytext_input(
+                "Rate limit, req/s",
+                &form.rate,
+                |it| Message::Load(MLoad::SetRate(it)),
+                None
+            ),
+        ]
+        .spacing(8)
+        .align_y(Center),
+        text("Concurrency is the number of requests in flight, an empty rate sends as fast as possible")
+            .size(12),
+    ]
+    .spacing(8);
+    let report = match running {
+        Some(progress) => Some(load_report(&progress.report)),
+        None => endpoint
+            .load_runs
+            .iter()
+            .find(|it| Some(it.id) == state.selected_load_run)
+            .map(|run| load_report(&run.report)),
+    };
+    let runs = endpoint.load_runs.iter().map(|run| {
+        let time = Local::now().offset().from_utc_datetime(&run.started_time);
+        line_clickable(
+            row![
+                text(time.format("%d-%m-%Y %H:%M:%S").to_string())
+                    .size(12)
+                    .width(Fill),
+                text(format!(
+                    "{} req, {:.1} req/s, p99 {} ms, {} errors",
+                    run.report.sent,
+                    run.report.throughput(),
+                    millis(run.report.p99),
+                    run.report.errors
+                ))
+                .size(12),
+                bi(
+                    Icons::Delete,
+                    Some(Message::Load(MLoad::DeleteRun(run.id))),
+                    ButtonType::Inline,
+                ),
+            ]
+            .spacing(8)
+            .align_y(Center),
+            Some(Message::Load(MLoad::ShowRun(run.id))),
+        )
+        .width(Fill)
+        .into()
+    });
+    container(
+        column![header, settings]
+            .push_maybe(report)
+            .push(Column::with_children(runs))
+            .spacing(8),
+    )
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.1}", duration.as_secs_f64() * 1000.0)
+}
+
+fn load_report(report: &LoadReport) -> Column<'_, Message> {
+    let statuses = report.statuses.iter().map(|(status, count)| {
+        let label = text(format!("{} × {}", status, count)).size(12);
+        match status
+            .parse()
+            .ok()
+            .and_then(|it| StatusCode::from_u16(it).ok())
+        {
+            Some(code) => label.color(color_for_status(code)),
+            None => label.style(text::danger),
+        }
+        .into()
+    });
+    let highest = report.histogram.iter().map(|it| it.1).max().unwrap_or(1);
+    let bars = report.histogram.iter().map(|(upper, count)| {
+        row![
+            text(format!("≤ {} ms", millis(*upper))).size(12).width(90),
+            container("")
+                .width((*count as f32 / highest as f32 * 200.0).max(1.0))
+                .height(10)
+                .style(|t: &Theme| container::Style {
+                    background: Some(iced::Background::Color(t.palette().primary)),
+                    ..container::Style::default()
+                }),
+            text(count.to_string()).size(12),
+        ]
+        .spacing(8)
+        .align_y(Center)
+        .into()
+    });
+    column![
+        text(format!(
+            "{} requests in {:.1} s, {:.1} req/s, {} errors",
+            report.sent,
+            report.elapsed.as_secs_f64(),
+            report.throughput(),
+            report.errors
+        )),
+        text(format!(
+            "p50 {} ms, p90 {} ms, p99 {} ms, max {} ms",
+            millis(report.p50),
+            millis(report.p90),
+            millis(report.p99),
+            millis(report.max)
+        ))
+        .size(12),
+        Row::with_children(statuses).spacing(16).wrap(),
+        Column::with_children(bars).spacing(2),
+    ]
+    .spacing(8)
+    .padding([0, 8])
+}
+
+fn token_status(oauth: &OAuth) -> String {
+    let Some(token) = &oauth.token else {
+        return "No token yet, one is fetched on send".to_string();
+    };
+    match token.expires_at {
+        None => "Token cached, the server gave no expiry".to_string(),
+        Some(at) if token.is_fresh() => format!(
+            "Token valid for {} more min",
+            (at - Utc::now().timestamp()) / 60
+        ),
+        Some(_) if token.refresh_token.is_some() => {
+            "Token expired, it is refreshed on send".to_string()
+        }
+        Some(_) => "Token expired, a new one is fetched on send".to_string(),
+    }
+}
+
+fn soap_panel(state: &State) -> Container<'_, Message> {
+    if method_from_state(state) != HttpMethod::SOAP {
+        return container(column![]);
+    }
+    let wsdl = state
+        .soap_services
+        .get(&schema_key(&format_url_from_state(state)));
+    let operation = wsdl.and_then(|wsdl| {
+        wsdl.operations
+            .iter()
+            .find(|it| Some(&it.name) == state.soap_operation.as_ref())
+    });
+    container(
+        column![
+            row![
+                text("SOAP").width(Fill),
+                bt(
+                    "Load WSDL file",
+                    Some(Message::Soap(MSoap::PickWsdl)),
+                    ButtonType::Outlined,
+                )
+            ]
+            .spacing(16)
+            .padding([0, 8])
+            .align_y(Center),
+            row![
+                mytext_input(
+                    "WSDL URL, defaults to the endpoint with ?wsdl",
+                    &state.soap_wsdl_url,
+                    |it| Message::Soap(MSoap::SetWsdlUrl(it)),
+                    Some(Message::Soap(MSoap::FetchWsdl))
+                ),
+                bt(
+                    "Fetch",
+                    Some(Message::Soap(MSoap::FetchWsdl)),
+                    ButtonType::Primary,
+                )
+            ]
+            .spacing(8)
+            .align_y(Center),
+            match wsdl {
+                Some(wsdl) => Element::from(
+                    pick_list(
+                        wsdl.operations
+                            .iter()
+                            .map(|it| it.name.clone())
+                            .collect::<Vec<_>>(),
+                        state.soap_operation.clone(),
+                        |it| Message::Soap(MSoap::SelectOperation(it)),
+                    )
+                    .placeholder("Operation")
+                    .width(Fill),
+                ),
+                None => Element::from(row![]),
+            },
+            match operation {
+                Some(operation) => text!(
+                    "SOAP {} · action \"{}\"",
+                    if operation.soap12 { "1.2" } else { "1.1" },
+                    operation.action
+                )
+                .size(12),
+                None => text(""),
+            },
+            text_editor(&state.soap_envelope)
+                .placeholder("<soap:Envelope>...</soap:Envelope>")
+                .on_action(|it| Message::Soap(MSoap::EditEnvelope(it)))
+                .font(Font::MONOSPACE)
+                .height(280),
+        ]
+        .spacing(8),
     )
     .style(|t| container::Style {
         border: Border::default().rounded(16),
@@ -1244,249 +2416,2525 @@ fn header_panel<'a>(
     })
 }
 
-fn strip_url(url: &str) -> String {
-    let prefixes = ["https://", "http://"];
-    for p in &prefixes {
-        match url.strip_prefix(p) {
-            Some(it) => {
-                return it.to_string();
-            }
-            None => {}
-        }
+fn draft_query_param_panel<'a>(state: &'a State) -> Container<'a, Message> {
+    container(
+        column![
+            row![
+                text("Query params"),
+                bt(
+                    "Add",
+                    Some(Message::QueryParam(MQueryParam::AddQueryParam())),
+                    ButtonType::Primary,
+                )
+            ]
+            .spacing(16)
+            .padding([0, 8])
+            .align_y(Center)
+            .width(Fill),
+            Column::from_iter(
+                state
+                    .draft_request
+                    .query_params
+                    .iter()
+                    .map(|it| query_row(it, state, false).into()),
+            )
+            .spacing(8)
+        ]
+        .spacing(16),
+    )
+    .style(|t| container::Style {
+        border: Border::default().rounded(16),
+        background: Some(iced::Background::Color(t.palette().background)),
+        ..container::Style::default()
+    })
+}
+
+// Panels for requests that are still running take the place of the response
+fn live_panel(state: &State) -> Option<container::Container<'_, Message>> {
+    match (&state.download, &state.sse) {
+        (Some(download), _) => Some(download_panel(download)),
+        (None, Some(sse)) => Some(sse_panel(sse)),
+        (None, None) => None,
+    }
+}
+
+fn sse_panel(sse: &SseSession) -> container::Container<'_, Message> {
+    let status = match sse.status {
+        SseStatus::Live => "Live",
+        SseStatus::Reconnecting => "Reconnecting...",
+        SseStatus::Ended => "Closed by server",
+    };
+    card(column![
+        mb(
+            row![
+                text!("{} · {} events", status, sse.events.len()).width(Fill),
+                bt(
+                    "Stop and save",
+                    Some(Message::Sse(MSse::Stop)),
+                    ButtonType::Danger
+                )
+            ]
+            .align_y(Center)
+            .into(),
+            16.0
+        ),
+        scrollable(
+            Column::from_iter(sse.events.iter().map(|event| {
+                column![
+                    row![
+                        text(
+                            Local::now()
+                                .offset()
+                                .from_utc_datetime(&event.time)
+                                .format("%H:%M:%S%.3f")
+                                .to_string()
+                        )
+                        .size(12)
+                        .style(|t: &Theme| text::Style {
+                            color: Some(t.palette().text.scale_alpha(0.6))
+                        }),
+                        text(&event.event)
+                            .size(12)
+                            .color(Color::parse("#7DCFFF").unwrap()),
+                        text(
+                            event
+                                .id
+                                .as_deref()
+                                .map_or(String::new(), |it| format!("#{}", it))
+                        )
+                        .size(12)
+                    ]
+                    .spacing(8),
+                    text(&event.data).font(Font::MONOSPACE).size(13)
+                ]
+                .spacing(4)
+                .into()
+            }))
+            .spacing(12)
+        )
+        .anchor_bottom()
+        .height(Fill)
+        .width(Fill)
+    ])
+    .width(Fill)
+}
+
+fn ws_panel<'a>(state: &'a State, endpoint: &'a EndpointDb) -> Container<'a, Message> {
+    let session = endpoint.responses.get(state.selected_response_index);
+    let live = state
+        .ws
+        .as_ref()
+        .filter(|ws| session.is_some_and(|it| it.id == ws.response_id));
+    let frames = match (live, session) {
+        (Some(ws), _) => &ws.frames[..],
+        (None, Some(session)) => &session.frames[..],
+        (None, None) => &[],
+    };
+    card(column![
+        mb(
+            text(if live.is_some() {
+                "Connected"
+            } else {
+                "Disconnected"
+            })
+            .into(),
+            16.0
+        ),
+        scrollable(Column::from_iter(frames.iter().map(ws_frame_row)).spacing(12))
+            .anchor_bottom()
+            .height(Fill)
+            .width(Fill),
+        row![
+            mytext_input(
+                if state.ws_binary {
+                    "Hex bytes, e.g. 48 65 6c 6c 6f"
+                } else {
+                    "Message..."
+                },
+                &state.ws_composer,
+                |it| Message::Ws(MWs::SetComposer(it)),
+                live.map(|_| Message::Ws(MWs::Send))
+            )
+            .id("ws_composer"),
+            bt(
+                "Binary",
+                Some(Message::Ws(MWs::ToggleBinary)),
+                if state.ws_binary {
+                    ButtonType::Primary
+                } else {
+                    ButtonType::Outlined
+                }
+            ),
+            bti(
+                "Send".to_string(),
+                Icons::Enter,
+                live.map(|_| Message::Ws(MWs::Send)),
+                ButtonType::Primary
+            )
+            .height(Shrink)
+            .padding([14, 16])
+        ]
+        .spacing(8)
+        .align_y(Center)
+    ])
+    .width(Fill)
+}
+
+fn ws_frame_row(frame: &WsFrame) -> Element<'_, Message> {
+    let time = Local::now().offset().from_utc_datetime(&frame.time);
+    column![
+        row![
+            text(time.format("%H:%M:%S%.3f").to_string())
+                .size(12)
+                .style(|t: &Theme| text::Style {
+                    color: Some(t.palette().text.scale_alpha(0.6))
+                }),
+            text(if frame.outgoing { "SENT" } else { "RECEIVED" })
+                .size(12)
+                .style(move |t: &Theme| text::Style {
+                    color: Some(if frame.outgoing {
+                        t.palette().primary
+                    } else {
+                        t.palette().success
+                    })
+                }),
+            text(if frame.binary {
+                format!("binary, {}", format_size(frame.data.len() as u64))
+            } else {
+                String::new()
+            })
+            .size(12)
+        ]
+        .spacing(8),
+        text(if frame.binary {
+            hex_string(&frame.data)
+        } else {
+            String::from_utf8_lossy(&frame.data).into_owned()
+        })
+        .font(Font::MONOSPACE)
+        .size(13)
+    ]
+    .spacing(4)
+    .into()
+}
+
+fn download_panel(download: &Download) -> container::Container<'_, Message> {
+    let seconds = download.started.elapsed().as_secs_f64().max(0.001);
+    let rate = format_size((download.received as f64 / seconds) as u64);
+    let progress: Element<'_, Message> = match download.total {
+        Some(total) => column![
+            progress_bar(0.0..=total as f32, download.received as f32).height(8),
+            text!(
+                "{} of {} · {}/s",
+                format_size(download.received),
+                format_size(total),
+                rate
+            )
+        ]
+        .spacing(8)
+        .into(),
+        None => text!("{} · {}/s", format_size(download.received), rate).into(),
+    };
+    card(column![
+        mb(
+            row![
+                text!("Downloading to {}", download.path.display()).width(Fill),
+                bt(
+                    "Cancel",
+                    Some(Message::Download(MDownload::Cancel)),
+                    ButtonType::Danger
+                )
+            ]
+            .align_y(Center)
+            .into(),
+            16.0
+        ),
+        progress
+    ])
+    .width(Fill)
+}
+
+fn draft_response_panel(state: &State) -> container::Container<'_, Message> {
+    match &state.draft_response {
+        Some(draft) => card(column![
+            mb(text("Temporary response").into(), 8.0),
+            row![
+                bi(
+                    Icons::Left,
+                    Some(Message::DiscardDraftResponse),
+                    ButtonType::Text
+                ),
+                response_status(draft),
+                wrap_button(state),
+                language_picker(state, &draft.content_type),
+                bi(
+                    Icons::Format,
+                    Some(Message::FormatResponse),
+                    ButtonType::Text
+                ),
+                bi(
+                    Icons::Minify,
+                    Some(Message::MinifyResponse),
+                    ButtonType::Text
+                ),
+                bi(
+                    Icons::Save,
+                    Some(Message::SaveResponseBody),
+                    ButtonType::Text
+                )
+            ]
+            .align_y(Center)
+            .spacing(8),
+            response_filter_bar(state),
+            response_search_bar(state),
+            mb(response_body(state, draft), 16.0).padding([16, 0])
+        ])
+        .width(Fill),
+        None => container(column![]),
+    }
+}
+
+fn query_row<'a>(
+    it: &'a EndpointKvPair,
+    state: &'a State,
+    is_header: bool,
+) -> container::Container<'a, Message> {
+    container(
+        match state.selected_endpoint {
+            Some(_) => match &state.copy_request {
+                Some(_) => {
+                    row![
+                        mytext_input(
+                            "Name",
+                            &it.key,
+                            {
+                                let it = it.clone();
+                                move |new_content| {
+                                    if is_header {
+                                        Message::Header(MHeader::SetHeaderKey(it.id, new_content))
+                                    } else {
+                                        Message::QueryParam(MQueryParam::SetQueryParamKey(
+                                            it.id,
+                                            new_content,
+                                        ))
+                                    }
+                                }
+                            },
+                            None
+                        )
+                        .id(format!("query_param_{}", it.id)),
+                        mytext_input(
+                            "Value",
+                            &it.value,
+                            {
+                                let it = it.clone();
+                                move |new_content| {
+                                    if is_header {
+                                        Message::Header(MHeader::SetHeaderContent(
+                                            it.id,
+                                            new_content,
+                                        ))
+                                    } else {
+                                        Message::QueryParam(MQueryParam::SetQueryParamContent(
+                                            it.id,
+                                            new_content,
+                                        ))
+                                    }
+                                }
+                            },
+                            None,
+                        ),
+                        bi(
+                            Icons::Check,
+                            Some(if is_header {
+                                Message::Header(MHeader::ToggleHeaderIsOn(it.id))
+                            } else {
+                                Message::QueryParam(MQueryParam::ToggleQueryParamIsOn(it.id))
+                            }),
+                            if it.on {
+                                ButtonType::Primary
+                            } else {
+                                ButtonType::Text
+                            },
+                        ),
+                        bi(
+                            Icons::Delete,
+                            Some(if is_header {
+                                Message::Header(MHeader::DeleteHeader(it.id))
+                            } else {
+                                Message::QueryParam(MQueryParam::DeleteQueryParam(it.id))
+                            }),
+                            ButtonType::Text,
+                        )
+                    ]
+                }
+                None => {
+                    row![
+                        Element::from(card(row![text(&it.key).width(Fill)])),
+                        Element::from(card(row![text(&it.value)].width(Fill))),
+                    ]
+                }
+            },
+            None => {
+                row![
+                    mytext_input(
+                        "Name",
+                        &it.key,
+                        {
+                            let it = it.clone();
+                            move |new_content| {
+                                if is_header {
+                                    Message::Header(MHeader::SetHeaderKey(it.id, new_content))
+                                } else {
+                                    Message::QueryParam(MQueryParam::SetQueryParamKey(
+                                        it.id,
+                                        new_content,
+                                    ))
+                                }
+                            }
+                        },
+                        None
+                    )
+                    .id(format!("query_param_{}", it.id)),
+                    mytext_input(
+                        "Value",
+                        &it.value,
+                        {
+                            let it = it.clone();
+                            move |new_content| {
+                                if is_header {
+                                    Message::Header(MHeader::SetHeaderContent(it.id, new_content))
+                                } else {
+                                    Message::QueryParam(MQueryParam::SetQueryParamContent(
+                                        it.id,
+                                        new_content,
+                                    ))
+                                }
+                            }
+                        },
+                        None,
+                    ),
+                    bi(
+                        Icons::Check,
+                        Some(if is_header {
+                            Message::Header(MHeader::ToggleHeaderIsOn(it.id))
+                        } else {
+                            Message::QueryParam(MQueryParam::ToggleQueryParamIsOn(it.id))
+                        }),
+                        if it.on {
+                            ButtonType::Primary
+                        } else {
+                            ButtonType::Text
+                        },
+                    ),
+                    bi(
+                        Icons::Delete,
+                        Some(if is_header {
+                            Message::Header(MHeader::DeleteHeader(it.id))
+                        } else {
+                            Message::QueryParam(MQueryParam::DeleteQueryParam(it.id))
+                        }),
+                        ButtonType::Text,
+                    )
+                ]
+            }
+        }
+        .align_y(Center)
+        .spacing(8),
+    )
+    .style(|t| container::Style {
+        border: Border::default().rounded(16),
+        background: Some(iced::Background::Color(t.palette().background)),
+        ..container::Style::default()
+    })
+}
+
+fn query_param_panel<'a>(
+    state: &'a State,
+    endpoint: &'a EndpointDb,
+) -> container::Container<'a, Message> {
+    container(
+        column![
+            if state.copy_request.is_none() && current_response(state).is_some() {
+                row![
+                    column![
+                        bt(
+                            "Copy",
+                            Some(Message::SetDraftQuery(true)),
+                            ButtonType::Outlined,
+                        ),
+                        text("Query params"),
+                    ]
+                    .spacing(16)
+                ]
+            } else {
+                row![
+                    text("Query params"),
+                    bt(
+                        "Add",
+                        Some(Message::QueryParam(MQueryParam::AddQueryParam())),
+                        ButtonType::Primary,
+                    )
+                ]
+                .spacing(16)
+                .align_y(Center)
+            }
+            .padding([0, 8])
+            .width(Fill),
+            match &state.copy_request {
+                Some(drafts) => {
+                    Column::from_iter(
+                        drafts
+                            .query_params
+                            .iter()
+                            .map(|it| query_row(it, state, false).into()),
+                    )
+                }
+                None => {
+                    match endpoint.responses.get(state.selected_response_index) {
+                        Some(resp) => Column::from_iter(
+                            resp.request
+                                .query_params
+                                .iter()
+                                .map(|it| query_row(it, state, false).into()),
+                        ),
+                        None => column![],
+                    }
+                }
+            }
+            .spacing(8)
+        ]
+        .spacing(16),
+    )
+    .style(|t| container::Style {
+        border: Border::default().rounded(16),
+        background: Some(iced::Background::Color(t.palette().background)),
+        ..container::Style::default()
+    })
+}
+
+fn draft_header_panel<'a>(state: &'a State) -> Container<'a, Message> {
+    container(
+        column![
+            row![
+                text("Headers"),
+                bt(
+                    "Add",
+                    Some(Message::Header(MHeader::AddHeader())),
+                    ButtonType::Primary,
+                )
+            ]
+            .spacing(16)
+            .padding([0, 8])
+            .align_y(Center)
+            .width(Fill),
+            Column::from_iter(
+                state
+                    .draft_request
+                    .headers
+                    .iter()
+                    .map(|it| query_row(it, state, true).into()),
+            )
+            .spacing(8)
+        ]
+        .spacing(16),
+    )
+    .style(|t| container::Style {
+        border: Border::default().rounded(16),
+        background: Some(iced::Background::Color(t.palette().background)),
+        ..container::Style::default()
+    })
+}
+
+fn header_panel<'a>(
+    state: &'a State,
+    endpoint: &'a EndpointDb,
+) -> container::Container<'a, Message> {
+    container(
+        column![
+            row![
+                text("Headers"),
+                if state.copy_request.is_some() || current_response(state).is_none() {
+                    bt(
+                        "Add",
+                        Some(Message::Header(MHeader::AddHeader())),
+                        ButtonType::Primary,
+                    )
+                } else {
+                    empty_b()
+                }
+            ]
+            .spacing(16)
+            .padding([0, 8])
+            .align_y(Center)
+            .width(Fill),
+            match &state.copy_request {
+                Some(drafts) => {
+                    Column::from_iter(
+                        drafts
+                            .headers
+                            .iter()
+                            .map(|it| query_row(it, state, true).into()),
+                    )
+                }
+                None => {
+                    match endpoint.responses.get(state.selected_response_index) {
+                        Some(resp) => Column::from_iter(
+                            resp.request
+                                .headers
+                                .iter()
+                                .map(|it| query_row(it, state, true).into()),
+                        ),
+                        None => column![],
+                    }
+                }
+            }
+            .spacing(8)
+        ]
+        .spacing(16),
+    )
+    .style(|t| container::Style {
+        border: Border::default().rounded(16),
+        background: Some(iced::Background::Color(t.palette().background)),
+        ..container::Style::default()
+    })
+}
+
+fn strip_url(url: &str) -> String {
+    let prefixes = ["https://", "http://"];
+    for p in &prefixes {
+        match url.strip_prefix(p) {
+            Some(it) => {
+                return it.to_string();
+            }
+            None => {}
+        }
+    }
+    url.to_string()
+}
+
+fn method_from_state(state: &State) -> HttpMethod {
+    match current_endpoint(state) {
+        Some(endpoint) => endpoint.method,
+        None if is_ws_url(&state.draft) => HttpMethod::WS,
+        None => state.draft_method,
+    }
+}
+
+fn body_from_state(state: &State) -> Result<String, MyErr> {
+    match method_from_state(state) {
+        HttpMethod::GQL => request_body(
+            &state.graphql_query.text(),
+            &state.graphql_variables.text(),
+            state.graphql_operation.as_deref(),
+        )
+        .map_err(MyErr::Client),
+        HttpMethod::GRPC => Ok(state.grpc_message.text()),
+        HttpMethod::RPC => jsonrpc::request_body(
+            &state
+                .rpc_calls
+                .iter()
+                .map(|it| (it.method.clone(), it.params.text()))
+                .collect::<Vec<_>>(),
+        )
+        .map_err(MyErr::Client),
+        HttpMethod::SOAP => Ok(state.soap_envelope.text()),
+        _ => Ok(String::new()),
+    }
+}
+
+// Loads the body sent with the shown response back into the editors
+fn load_request_editors(state: &mut State) {
+    let (method, body) = match current_endpoint(state) {
+        Some(endpoint) => (
+            Some(endpoint.method),
+            current_response(state)
+                .map(|it| it.request.body.clone())
+                .unwrap_or_default(),
+        ),
+        None => (None, String::new()),
+    };
+    let (query, variables, operation) = match method {
+        Some(HttpMethod::GQL) => parse_request_body(&body),
+        _ => Default::default(),
+    };
+    state.graphql_query = text_editor::Content::with_text(&query);
+    state.graphql_variables = text_editor::Content::with_text(&variables);
+    state.graphql_operation = operation;
+    state.grpc_message = match method {
+        Some(HttpMethod::GRPC) => text_editor::Content::with_text(body.trim_end()),
+        _ => text_editor::Content::new(),
+    };
+    state.rpc_calls = match method {
+        Some(HttpMethod::RPC) => jsonrpc::parse_request_body(&body)
+            .into_iter()
+            .map(|(method, params)| RpcCall {
+                method,
+                params: text_editor::Content::with_text(&params),
+            })
+            .collect(),
+        _ => vec![],
+    };
+    if state.rpc_calls.is_empty() {
+        state.rpc_calls.push(RpcCall::default());
+    }
+    state.soap_operation = None;
+    state.soap_envelope = match method {
+        Some(HttpMethod::SOAP) => text_editor::Content::with_text(body.trim_end()),
+        _ => text_editor::Content::new(),
+    };
+}
+
+// Sets a header of the request being edited, replacing one with the same name.
+// Returns true when the header of a saved response was changed in the database.
+fn set_request_header(state: &mut State, key: &str, value: &str) -> bool {
+    if state.copy_request.is_none() && state.selected_endpoint.is_some() {
+        match current_response(state) {
+            Some(response) => {
+                let conn = get_db().lock().unwrap();
+                match response
+                    .request
+                    .headers
+                    .iter()
+                    .find(|it| it.key.eq_ignore_ascii_case(key))
+                {
+                    Some(header) => update_header_value(&conn, header.id, value).unwrap(),
+                    None => {
+                        create_header(&conn, response.id, key, value).unwrap();
+                    }
+                }
+                return true;
+            }
+            None => state.copy_request = Some(Request::default()),
+        }
+    }
+    let headers = match &mut state.copy_request {
+        Some(request) => &mut request.headers,
+        None => &mut state.draft_request.headers,
+    };
+    match headers
+        .iter_mut()
+        .find(|it| it.key.eq_ignore_ascii_case(key))
+    {
+        Some(header) => header.value = value.to_string(),
+        None => headers.push(EndpointKvPair {
+            id: headers.last().map(|it| it.id + 1).unwrap_or(0),
+            parent_response_id: 0,
+            key: key.to_string(),
+            value: value.to_string(),
+            on: true,
+        }),
+    }
+    false
+}
+
+pub fn schema_key(url: &str) -> String {
+    url.split('?').next().unwrap_or_default().trim().to_string()
+}
+
+fn is_ws_url(url: &str) -> bool {
+    let url = url.trim_start().to_ascii_lowercase();
+    url.starts_with("ws://") || url.starts_with("wss://")
+}
+
+fn request_from_state(state: &State) -> Request {
+    match (&state.copy_request, current_response(state)) {
+        (Some(request), _) => request.clone(),
+        (None, Some(response)) => response.request.clone(),
+        (None, None) if state.selected_endpoint.is_none() => state.draft_request.clone(),
+        (None, None) => Request::default(),
+    }
+}
+
+// Every connection is kept as a response of the endpoint, with the handshake
+// headers as its request and the exchanged frames attached to it
+fn create_ws_session(state: &State, code: StatusCode) -> (u64, u64) {
+    let request = request_from_state(state);
+    let conn = get_db().lock().unwrap();
+    let endpoint_id = match state.selected_endpoint {
+        Some(id) => id,
+        None => create_endpoint_full(
+            &conn,
+            &EndpointDb {
+                id: 0,
+                url: state.draft.clone(),
+                responses: vec![],
+                method: HttpMethod::WS,
+                response_filter: String::new(),
+                auth: state.auth.clone(),
+                pre_script: String::new(),
+                post_script: String::new(),
+                load_runs: vec![],
+                retry: RetryPolicy::default(),
+                redirect: RedirectPolicy::default(),
+                proxy: ProxySettings::default(),
+                proto_paths: vec![],
+            },
+        )
+        .unwrap(),
+    };
+    let response_id = create_response(
+        &conn,
+        endpoint_id,
+        &Response {
+            code,
+            received_time: Utc::now().naive_utc(),
+            ..Response::default()
+        },
+    )
+    .unwrap();
+    for q in request.query_params.iter().filter(|it| it.on) {
+        create_query_param(&conn, response_id, &q.key, &q.value).unwrap();
+    }
+    for h in request.headers.iter().filter(|it| it.on) {
+        create_header(&conn, response_id, &h.key, &h.value).unwrap();
+    }
+    (endpoint_id, response_id)
+}
+
+fn format_url_from_state(state: &State) -> String {
+    match current_endpoint(state) {
+        Some(endpoint) => {
+            let empty = [].to_vec().clone();
+            let query_param_vec: Vec<String> = (match &state.copy_request {
+                Some(drafts) => &drafts.query_params,
+                None => match endpoint.responses.get(state.selected_response_index) {
+                    Some(resp) => &resp.request.query_params,
+                    None => &empty,
+                },
+            })
+            .iter()
+            .filter(|it| !it.key.is_empty() && it.on)
+            .map(|it| {
+                format!(
+                    "{}={}",
+                    urlencoding::encode(&it.key),
+                    urlencoding::encode(&it.value)
+                )
+            })
+            .collect();
+            format!(
+                "{}{}{}",
+                endpoint.url,
+                if query_param_vec.is_empty() { "" } else { "?" },
+                query_param_vec.join("&")
+            )
+        }
+        None => {
+            let query_param_vec: Vec<String> = state
+                .draft_request
+                .query_params
+                .iter()
+                .filter(|it| !it.key.is_empty() && it.on)
+                .map(|it| {
+                    format!(
+                        "{}={}",
+                        urlencoding::encode(&it.key),
+                        urlencoding::encode(&it.value)
+                    )
+                })
+                .collect();
+            format!(
+                "{}{}{}",
+                state.draft,
+                if query_param_vec.is_empty() { "" } else { "?" },
+                query_param_vec.join("&")
+            )
+        }
+    }
+}
+
+fn headers_from_state(state: &State) -> HeaderMap {
+    let empty: Vec<EndpointKvPair> = [].to_vec().clone();
+    match current_endpoint(state) {
+        Some(endpoint) => HeaderMap::from_iter(
+            (match &state.copy_request {
+                Some(drafts) => &drafts.headers,
+                None => match endpoint.responses.get(state.selected_response_index) {
+                    Some(resp) => &resp.request.headers,
+                    None => &empty,
+                },
+            })
+            .iter()
+            .filter(|it| !it.key.is_empty() && it.on)
+            .map(|it| {
+                (
+                    HeaderName::from_str(&it.key).unwrap(),
+                    HeaderValue::from_str(&it.value).unwrap(),
+                )
+            }),
+        ),
+        None => HeaderMap::from_iter(
+            state
+                .draft_request
+                .headers
+                .iter()
+                .filter(|it| !it.key.is_empty() && it.on)
+                .map(|it| {
+                    (
+                        HeaderName::from_str(&it.key).unwrap(),
+                        HeaderValue::from_str(&it.value).unwrap(),
+                    )
+                }),
+        ),
+    }
+}
+
+// Url and headers with the credentials of the Auth section applied
+fn authorized_request(state: &State) -> (String, HeaderMap) {
+    let mut url = format_url_from_state(state);
+    let mut headers = headers_from_state(state);
+    state.auth.apply(&mut url, &mut headers);
+    (url, headers)
+}
+
+fn response_pager(state: &State, resp_count: usize) -> Element<'_, Message> {
+    mb(
+        ml(
+            row![
+                bi(
+                    Icons::Plus,
+                    Some(Message::SetDraftQuery(false)),
+                    if state.copy_request.is_none() {
+                        ButtonType::OutlinedInline
+                    } else {
+                        ButtonType::PrimaryInline
+                    }
+                ),
+                scrollable(
+                    Row::from_iter((1..resp_count + 1).rev().map(|index| {
+                        bt(
+                            index,
+                            Some(Message::SetSelectedResponseIndex(index - 1)),
+                            if index == state.selected_response_index + 1
+                                && state.copy_request.is_none()
+                            {
+                                ButtonType::Primary
+                            } else {
+                                ButtonType::Text
+                            },
+                        )
+                        .into()
+                    }))
+                    .spacing(8)
+                    .align_y(Center)
+                )
+                .direction(scrollable::Direction::Horizontal(
+                    Scrollbar::default().width(0).scroller_width(0)
+                ))
+                .width(Fill)
+            ]
+            .spacing(8)
+            .align_y(Center)
+            .into(),
+            16.0,
+        )
+        .into(),
+        16.0,
+    )
+    .into()
+}
+
+fn response_panels<'a>(
+    resp: &'a Response,
+    state: &'a State,
+    resp_count: usize,
+) -> Column<'a, Message, Theme, Renderer> {
+    column![
+        response_pager(state, resp_count),
+        ml(
+            card(column![
+                row![
+                    response_status(resp),
+                    wrap_button(state),
+                    language_picker(state, &resp.content_type),
+                    bi(
+                        Icons::Format,
+                        Some(Message::FormatResponse),
+                        ButtonType::Text
+                    ),
+                    bi(
+                        Icons::Minify,
+                        Some(Message::MinifyResponse),
+                        ButtonType::Text
+                    ),
+                    bi(
+                        Icons::Save,
+                        Some(Message::SaveResponseBody),
+                        ButtonType::Text
+                    ),
+                    bi(
+                        Icons::Duplicate,
+                        Some(Message::ClickCopyResponse),
+                        ButtonType::Text
+                    ),
+                    bi(
+                        Icons::Delete,
+                        Some(Message::ClickDeleteResponse(resp.id)),
+                        ButtonType::Text
+                    )
+                ]
+                .align_y(Center)
+                .spacing(8),
+                response_filter_bar(state),
+                response_search_bar(state),
+                mb(response_body(state, resp), 16.0).padding([16, 0])
+            ])
+            .width(Fill)
+            .into(),
+            16.0,
+        )
+    ]
+}
+
+fn wrap_button<'a>(state: &State) -> Button<'a, Message> {
+    bi(
+        Icons::Wrap,
+        Some(Message::ToggleResponseWrap),
+        if state.wrap_response {
+            ButtonType::Primary
+        } else {
+            ButtonType::Text
+        },
+    )
+}
+
+fn response_status(resp: &Response) -> Row<'_, Message> {
+    let time = Local::now().offset().from_utc_datetime(&resp.received_time);
+    // gRPC reports its own status next to the HTTP one, which is 200 for any finished call
+    let (code, color) = match &resp.grpc_status {
+        Some(status) if status.is_ok() => (status.name(), color_for_status(StatusCode::OK)),
+        Some(status) => (
+            format!("{} ({})", status.name(), status.code),
+            color_for_status(StatusCode::INTERNAL_SERVER_ERROR),
+        ),
+        None => (resp.code.to_string(), color_for_status(resp.code)),
+    };
+    row![
+        container(text(code).style(|_| text::Style {
+            color: Some(Color::BLACK)
+        }))
+        .padding([2, 4])
+        .style(move |_| {
+            container::Style {
+                background: Some(iced::Background::Color(color)),
+                ..container::Style::default()
+            }
+        }),
+        text(
+            resp.grpc_status
+                .as_ref()
+                .map(|it| it.message.as_str())
+                .unwrap_or_default()
+        )
+        .size(12),
+        column![
+            text(time.format("%H:%M:%S").to_string())
+                .size(14)
+                .line_height(1.0),
+            text(time.format("%d-%m-%Y").to_string())
+                .size(10)
+                .line_height(0.9)
+        ],
+        text(essence(&resp.content_type))
+            .size(12)
+            .style(|t: &Theme| text::Style {
+                color: Some(t.palette().text.scale_alpha(0.6))
+            })
+    ]
+    .align_y(Center)
+    .spacing(8)
+    .width(Fill)
+}
+
+fn language_picker<'a>(
+    state: &State,
+    content_type: &str,
+) -> PickList<'a, BodyLanguage, &'a [BodyLanguage], BodyLanguage, Message> {
+    pick_list(
+        &BodyLanguage::ALL[..],
+        Some(response_language(state, content_type)),
+        Message::SetFormatLanguage,
+    )
+    .style(|t, s| pick_list::Style {
+        border: Border {
+            color: Color::parse("#C0CAF5").unwrap(),
+            radius: 0.into(),
+            width: 1.0,
+        },
+        background: Background::Color(Color::TRANSPARENT),
+        ..pick_list::default(t, s)
+    })
+}
+
+fn response_filter_bar(state: &State) -> Container<'static, Message> {
+    container(mytext_input(
+        "Filter: $.items[*].id or .data | length",
+        &state.response_filter,
+        Message::SetResponseFilter,
+        Some(Message::ApplyResponseFilter(state.response_filter_edits)),
+    ))
+    .padding(Padding::ZERO.top(16))
+}
+
+fn response_search_bar(state: &State) -> Container<'_, Message> {
+    let search = &state.body_search;
+    let counter = if search.query.is_empty() {
+        text("")
+    } else {
+        match (&search.error, search.matches.len()) {
+            (Some(error), _) => text(error).color(state.theme.palette.danger),
+            (None, 0) => text("No matches"),
+            (None, count) => text!("{}/{}", search.current.min(count - 1) + 1, count),
+        }
+    };
+    container(
+        row![
+            mytext_input(
+                "Find in response...",
+                &search.query,
+                |query| Message::BodySearch(MBodySearch::SetQuery(query)),
+                Some(Message::BodySearch(MBodySearch::Next)),
+            )
+            .id("response_search"),
+            counter,
+            bt(
+                ".*",
+                Some(Message::BodySearch(MBodySearch::ToggleRegex)),
+                if search.regex {
+                    ButtonType::Primary
+                } else {
+                    ButtonType::Outlined
+                }
+            ),
+            bt(
+                "Aa",
+                Some(Message::BodySearch(MBodySearch::ToggleCaseSensitive)),
+                if search.case_sensitive {
+                    ButtonType::Primary
+                } else {
+                    ButtonType::Outlined
+                }
+            ),
+            bi(
+                Icons::Left,
+                Some(Message::BodySearch(MBodySearch::Previous)),
+                ButtonType::Text
+            ),
+            bi(
+                Icons::Right,
+                Some(Message::BodySearch(MBodySearch::Next)),
+                ButtonType::Text
+            )
+        ]
+        .spacing(8)
+        .align_y(Center),
+    )
+    .padding(Padding::ZERO.top(8))
+}
+
+fn response_body<'a>(state: &'a State, resp: &'a Response) -> Element<'a, Message> {
+    if let Some(path) = &resp.file_path {
+        return downloaded_body(resp, path);
+    }
+    if is_binary(&resp.content_type) {
+        return binary_body(resp, &state.shown_body.hex);
+    }
+    let details: Vec<Element<'a, Message>> = [
+        connection_panel(state, resp),
+        redirect_chain(resp),
+        retry_attempts(state, resp),
+        resolved_variables(resp),
+        test_results(state, resp),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let details: Option<Element<'a, Message>> =
+        (!details.is_empty()).then(|| Column::with_children(details).spacing(8).into());
+    let summary = match method_from_state(state) {
+        HttpMethod::GQL => graphql_errors(state, &resp.text),
+        HttpMethod::RPC => rpc_replies(state, resp),
+        HttpMethod::SOAP => soap_fault(state, &resp.text),
+        _ => None,
+    };
+    let body: Element<'a, Message> = match &state.filtered_response {
+        Some(Err(error)) => text(error).color(state.theme.palette.danger).into(),
+        _ => highlighted_body(state),
+    };
+    let body = scrollable(body)
+        .id(scrollable::Id::new("response_body"))
+        .direction(if state.wrap_response {
+            scrollable::Direction::Vertical(Scrollbar::default())
+        } else {
+            scrollable::Direction::Both {
+                vertical: Scrollbar::default(),
+                horizontal: Scrollbar::default(),
+            }
+        })
+        .height(Fill)
+        .width(Fill);
+    match (details, summary) {
+        (None, None) => body.into(),
+        (details, summary) => column![]
+            .push_maybe(details)
+            .push_maybe(summary)
+            .push(body)
+            .spacing(8)
+            .into(),
+    }
+}
+
+// One line about the connection, the certificate chain below it on demand
+fn connection_panel<'a>(state: &State, resp: &Response) -> Option<Element<'a, Message>> {
+    let info = resp.connection.as_ref()?;
+    let mut summary: Vec<String> = info.remote_addr.iter().cloned().collect();
+    summary.push(info.http_version.clone());
+    let mut panel = Column::new().spacing(8);
+    let mut probe = None;
+    let certificates = match &info.tls {
+        Some(Ok(tls)) => {
+            summary.extend(tls.version.clone());
+            summary.extend(tls.cipher.clone());
+            summary.extend(tls.alpn.as_ref().map(|it| format!("ALPN {}", it)));
+            // The probe connects directly, through a proxy it would reach a different server
+            let proxied = !state.endpoint_proxy.effective(&state.proxy).is_direct();
+            probe = (!tls.is_probed() && !tls.server.is_empty()).then(|| {
+                bt(
+                    if proxied {
+                        "Full chain not available through a proxy"
+                    } else {
+                        "Read full chain"
+                    }
+                    .to_string(),
+                    (!state.probing_tls && !proxied).then_some(Message::ProbeTls),
+                    ButtonType::Text,
+                )
+            });
+            if tls.is_probed() {
+                panel = panel.push(
+                    text!(
+                        "Version, cipher, ALPN and chain are from a second handshake with {}, \
+                         not from the request itself",
+                        tls.server
+                    )
+                    .size(13),
+                );
+            }
+            &tls.chain[..]
+        }
+        Some(Err(err)) => {
+            panel = panel.push(text!("TLS details unavailable: {}", err).size(13));
+            &[]
+        }
+        None => &[],
+    };
+    let header = row![
+        text("Connection"),
+        text(summary.join(", ")).size(13).width(Fill)
+    ]
+    .push_maybe((!certificates.is_empty()).then(|| {
+        bt(
+            if state.show_certificates {
+                "Hide certificates".to_string()
+            } else {
+                format!("Certificates {}", certificates.len())
+            },
+            Some(Message::ToggleCertificates),
+            ButtonType::Text,
+        )
+    }))
+    .push_maybe(probe)
+    .spacing(8)
+    .align_y(Center);
+    if state.show_certificates {
+        panel = panel.extend(
+            certificates
+                .iter()
+                .enumerate()
+                .map(|(index, cert)| certificate(state, index, cert)),
+        );
+    }
+    Some(column![header, panel].spacing(8).into())
+}
+
+fn certificate<'a>(state: &State, index: usize, cert: &CertInfo) -> Element<'a, Message> {
+    let date = |it: Option<NaiveDateTime>| {
+        it.map_or("?".to_string(), |it| {
+            it.format("%d-%m-%Y %H:%M:%S").to_string()
+        })
+    };
+    let now = Utc::now().naive_utc();
+    let validity = text!(
+        "Valid {} to {}",
+        date(cert.not_before),
+        date(cert.not_after)
+    )
+    .size(13);
+    // Expired or not valid yet
+    let validity = if cert.not_after.is_some_and(|it| it < now)
+        || cert.not_before.is_some_and(|it| it > now)
+    {
+        validity.color(state.theme.palette.danger)
+    } else {
+        validity
+    };
+    column![
+        text!("#{} {}", index + 1, cert.subject).size(13),
+        text!("Issuer {}", cert.issuer).size(13),
+    ]
+    .push_maybe((!cert.sans.is_empty()).then(|| text!("SANs {}", cert.sans.join(", ")).size(13)))
+    .push(validity)
+    .push(
+        text!("SHA-256 {}", cert.sha256)
+            .size(12)
+            .font(Font::MONOSPACE),
+    )
+    .push(text!("SHA-1 {}", cert.sha1).size(12).font(Font::MONOSPACE))
+    .spacing(2)
+    .into()
+}
+
+fn redirect_chain<'a>(resp: &Response) -> Option<Element<'a, Message>> {
+    if resp.redirects.is_empty() {
+        return None;
+    }
+    Some(
+        column![
+            text!("Redirects {}", resp.redirects.len()),
+            Column::from_iter(resp.redirects.iter().map(|hop| {
+                let status = text(hop.status.to_string()).size(13).font(Font::MONOSPACE);
+                row![
+                    match StatusCode::from_u16(hop.status) {
+                        Ok(code) => status.color(color_for_status(code)),
+                        Err(_) => status,
+                    },
+                    text!("{} → {}", hop.url, hop.location).size(13).width(Fill),
+                    text!("{} ms", hop.duration.as_millis()).size(13)
+                ]
+                .spacing(8)
+                .into()
+            }))
+            .spacing(4),
+        ]
+        .spacing(8)
+        .into(),
+    )
+}
+
+// Shown once a request needed more than one try
+fn retry_attempts<'a>(state: &State, resp: &Response) -> Option<Element<'a, Message>> {
+    if resp.attempts.len() < 2 {
+        return None;
+    }
+    Some(
+        column![
+            text!("Attempts {}", resp.attempts.len()),
+            Column::from_iter(resp.attempts.iter().enumerate().map(|(index, attempt)| {
+                let outcome = match &attempt.outcome {
+                    Ok(code) => {
+                        let label = text(code.to_string()).size(13).font(Font::MONOSPACE);
+                        match StatusCode::from_u16(*code) {
+                            Ok(code) => label.color(color_for_status(code)),
+                            Err(_) => label,
+                        }
+                    }
+                    Err(err) => text(err.clone()).size(13).color(state.theme.palette.danger),
+                };
+                let timing = if attempt.wait.is_zero() {
+                    format!("{} ms", attempt.duration.as_millis())
+                } else {
+                    format!(
+                        "{} ms, waited {} ms",
+                        attempt.duration.as_millis(),
+                        attempt.wait.as_millis()
+                    )
+                };
+                row![
+                    text!("#{}", index + 1).size(13).font(Font::MONOSPACE),
+                    outcome,
+                    text(timing).size(13)
+                ]
+                .spacing(8)
+                .into()
+            }))
+            .spacing(4),
+        ]
+        .spacing(8)
+        .into(),
+    )
+}
+
+fn resolved_variables<'a>(resp: &Response) -> Option<Element<'a, Message>> {
+    if resp.variables.is_empty() {
+        return None;
+    }
+    Some(
+        column![
+            text("Variables"),
+            Column::from_iter(resp.variables.iter().map(|(name, value)| {
+                row![
+                    text(name.clone()).size(13).font(Font::MONOSPACE),
+                    text(value.clone()).size(13)
+                ]
+                .spacing(8)
+                .into()
+            }))
+            .spacing(4),
+        ]
+        .spacing(8)
+        .into(),
+    )
+}
+
+fn test_results<'a>(state: &State, resp: &Response) -> Option<Element<'a, Message>> {
+    if resp.tests.is_empty() {
+        return None;
+    }
+    let (success, danger) = (state.theme.palette.success, state.theme.palette.danger);
+    let passed = resp.tests.iter().filter(|it| it.passed).count();
+    Some(
+        column![
+            text!("Tests {}/{} passed", passed, resp.tests.len()).color(
+                if passed == resp.tests.len() {
+                    success
+                } else {
+                    danger
+                }
+            ),
+            Column::from_iter(resp.tests.iter().map(|it| {
+                let line = match &it.message {
+                    Some(message) => format!("{}: {}", it.name, message),
+                    None => it.name.clone(),
+                };
+                row![
+                    text(if it.passed { "PASS" } else { "FAIL" })
+                        .size(13)
+                        .font(Font::MONOSPACE)
+                        .color(if it.passed { success } else { danger }),
+                    text(line).size(13)
+                ]
+                .spacing(8)
+                .into()
+            }))
+            .spacing(4),
+        ]
+        .spacing(8)
+        .into(),
+    )
+}
+
+fn graphql_errors<'a>(state: &State, body: &str) -> Option<Element<'a, Message>> {
+    let errors = response_errors(body);
+    if errors.is_empty() {
+        return None;
+    }
+    let danger = state.theme.palette.danger;
+    Some(
+        column![
+            text!("Errors ({})", errors.len()).color(danger),
+            Column::from_iter(
+                errors
+                    .into_iter()
+                    .map(|it| text(it).size(13).color(danger).into())
+            )
+            .spacing(4),
+        ]
+        .spacing(8)
+        .into(),
+    )
+}
+
+fn soap_fault<'a>(state: &State, body: &str) -> Option<Element<'a, Message>> {
+    let fault = parse_fault(body)?;
+    let danger = state.theme.palette.danger;
+    Some(
+        container(
+            column![
+                row![
+                    container(text("SOAP Fault").size(13).color(Color::BLACK))
+                        .padding([0, 4])
+                        .style(move |_| container::Style {
+                            background: Some(iced::Background::Color(danger)),
+                            ..container::Style::default()
+                        }),
+                    text(fault.code).size(13).color(danger),
+                ]
+                .spacing(8),
+                text(fault.reason).color(danger),
+                match fault.detail {
+                    Some(detail) => text(detail).size(13),
+                    None => text(""),
+                }
+            ]
+            .spacing(4),
+        )
+        .padding(8)
+        .style(move |_| container::Style {
+            border: Border::default().color(danger).width(1).rounded(8),
+            ..container::Style::default()
+        })
+        .into(),
+    )
+}
+
+// One line per call of the request, with its result or the error code
+fn rpc_replies<'a>(state: &State, resp: &Response) -> Option<Element<'a, Message>> {
+    let replies = parse_replies(&resp.request.body, &resp.text);
+    if replies.is_empty() {
+        return None;
+    }
+    let palette = state.theme.palette;
+    let rows = Column::from_iter(replies.into_iter().map(|reply| {
+        let outcome: Element<'a, Message> = match reply.result {
+            Ok(result) => text(result.chars().take(200).collect::<String>())
+                .size(13)
+                .color(palette.success)
+                .into(),
+            Err(error) => row![
+                container(text(error.code).size(13).color(Color::BLACK))
+                    .padding([0, 4])
+                    .style(move |_| container::Style {
+                        background: Some(iced::Background::Color(palette.danger)),
+                        ..container::Style::default()
+                    }),
+                text!(
+                    "{}: {}{}",
+                    error_name(error.code),
+                    error.message,
+                    error
+                        .data
+                        .map(|it| format!(" ({})", it))
+                        .unwrap_or_default()
+                )
+                .size(13)
+                .color(palette.danger)
+            ]
+            .spacing(8)
+            .into(),
+        };
+        row![
+            text!("#{}", reply.id)
+                .size(13)
+                .color(palette.text.scale_alpha(0.6)),
+            text(reply.method.unwrap_or_default()).size(13),
+            outcome
+        ]
+        .spacing(8)
+        .into()
+    }))
+    .spacing(4);
+    Some(container(scrollable(rows)).max_height(200).into())
+}
+
+fn downloaded_body<'a>(resp: &Response, path: &'a str) -> Element<'a, Message> {
+    column![
+        text!("Saved {} to {}", format_size(resp.file_size), path),
+        bt(
+            "Open",
+            Some(Message::OpenFile(path.to_string())),
+            ButtonType::Outlined
+        )
+    ]
+    .spacing(16)
+    .padding([16, 0])
+    .into()
+}
+
+fn binary_body<'a>(resp: &'a Response, hex: &'a str) -> Element<'a, Message> {
+    let body: Element<'a, Message> = match &resp.image {
+        Some(handle) => image(handle).into(),
+        None => text(hex)
+            .font(Font::MONOSPACE)
+            .size(13)
+            .wrapping(Wrapping::None)
+            .into(),
+    };
+    scrollable(body)
+        .direction(scrollable::Direction::Both {
+            vertical: Scrollbar::default(),
+            horizontal: Scrollbar::default(),
+        })
+        .height(Fill)
+        .width(Fill)
+        .into()
+}
+
+// A single rich text for the whole body, line numbers included, so long bodies stay cheap to draw
+fn highlighted_body(state: &State) -> Element<'_, Message> {
+    let palette = &state.theme.palette;
+    let body = &state.shown_body;
+    let matches = &state.body_search.matches;
+    let current = state.body_search.current.min(max(matches.len(), 1) - 1);
+    let gutter = body.lines.len().to_string().len();
+    let mut spans: Vec<Span<'_, Message>> = Vec::new();
+    for (index, line) in body.lines.iter().enumerate() {
+        if index > 0 {
+            spans.push(span("\n"));
+        }
+        spans.push(
+            span(format!("{:>gutter$}  ", index + 1))
+                .font(Font::MONOSPACE)
+                .color(palette.text.scale_alpha(0.4)),
+        );
+        let line = line
+            .iter()
+            .map(|(token, range)| (*token, &body.text[range.clone()]))
+            .collect();
+        spans.extend(mark_matches(&body.text, line, matches).into_iter().map(
+            |(token, segment, found)| {
+                match found {
+                    Some(it) if it == current => span(segment)
+                        .color(Color::BLACK)
+                        .background(palette.primary),
+                    Some(_) => span(segment)
+                        .color(token_color(token, palette))
+                        .background(palette.primary.scale_alpha(0.3)),
+                    None => span(segment).color(token_color(token, palette)),
+                }
+            },
+        ));
+    }
+    if state.wrap_response {
+        rich_text(spans).wrapping(Wrapping::Word).width(Fill).into()
+    } else {
+        rich_text(spans).wrapping(Wrapping::None).into()
+    }
+}
+
+fn send(state: &mut State, is_draft: bool) -> Task<Message> {
+    if state.auth.kind == AuthKind::OAuth2 && state.auth.oauth.fresh_token().is_none() {
+        return fetch_oauth_token(state, Some(is_draft));
+    }
+    send_now(state, is_draft)
+}
+
+// Refreshes or acquires the OAuth token, then sends if a request was waiting for it
+fn fetch_oauth_token(state: &mut State, resend: Option<bool>) -> Task<Message> {
+    state.can_send = false;
+    let auth = state.auth.clone();
+    let endpoint_id = state.selected_endpoint;
+    Task::perform(
+        fetch_token(
+            auth.oauth,
+            auth.username,
+            auth.password,
+            client_settings(state),
+        ),
+        move |res| match res {
+            Ok(token) => Message::Auth(MAuth::GotToken(endpoint_id, token, resend)),
+            Err(err) => Message::GotError(MyErr::Client(err)),
+        },
+    )
+}
+
+fn script_text(content: &text_editor::Content) -> String {
+    content.text().trim_end().to_string()
+}
+
+// Url, headers, body and the variables expanded in them
+type PreparedRequest = (String, HeaderMap, String, Vec<(String, String)>);
+
+// Runs with the variables plus the values of a data row, changes to the row values are dropped
+fn with_row<T>(
+    state: &mut State,
+    row: &[(String, String)],
+    run: impl FnOnce(&mut HashMap<String, String>) -> T,
+) -> T {
+    let mut variables = state.variables.clone();
+    variables.extend(row.iter().cloned());
+    let result = run(&mut variables);
+    for (key, _) in row {
+        match state.variables.get(key) {
+            Some(value) => variables.insert(key.clone(), value.clone()),
+            None => variables.remove(key),
+        };
+    }
+    state.variables = variables;
+    result
+}
+
+// What prepare_request reads from the state, a load run prepares every request from it anew
+#[derive(Clone)]
+struct RequestTemplate {
+    method: String,
+    url: String,
+    query_params: Vec<EndpointKvPair>,
+    headers: Vec<EndpointKvPair>,
+    body: String,
+    script: String,
+    auth: Auth,
+}
+
+fn request_template(state: &State, body: String) -> RequestTemplate {
+    let request = request_from_state(state);
+    RequestTemplate {
+        method: http_method(method_from_state(state)).to_string(),
+        url: match current_endpoint(state) {
+            Some(endpoint) => endpoint.url.clone(),
+            None => state.draft.clone(),
+        },
+        query_params: request.query_params,
+        headers: request.headers,
+        body,
+        script: script_text(&state.pre_script),
+        auth: state.auth.clone(),
+    }
+}
+
+// Expands variables and runs the pre-request script, then applies auth like authorized_request.
+// The values of a data row are recorded with the response even when the request doesn't use them.
+fn prepare_request(
+    state: &mut State,
+    body: String,
+    row: &[(String, String)],
+) -> Result<PreparedRequest, MyErr> {
+    let template = request_template(state, body);
+    with_row(state, row, |variables| {
+        prepare_template(&template, variables, row)
+    })
+}
+
+// `variables` already hold the row values, the pre-request script may change them
+fn prepare_template(
+    template: &RequestTemplate,
+    variables: &mut HashMap<String, String>,
+    row: &[(String, String)],
+) -> Result<PreparedRequest, MyErr> {
+    let mut resolved: Vec<(String, String)> = row
+        .iter()
+        .map(|(key, value)| (format!("{{{{{}}}}}", key), value.clone()))
+        .collect();
+    let mut prepared = {
+        let variables = &*variables;
+        let mut pairs = |list: &[EndpointKvPair]| -> Vec<(String, String)> {
+            list.iter()
+                .filter(|it| !it.key.is_empty() && it.on)
+                .map(|it| {
+                    (
+                        expand(&it.key, variables, &mut resolved),
+                        expand(&it.value, variables, &mut resolved),
+                    )
+                })
+                .collect()
+        };
+        let params = pairs(&template.query_params);
+        let headers = pairs(&template.headers);
+        ScriptRequest {
+            method: template.method.clone(),
+            url: expand(&template.url, variables, &mut resolved),
+            params,
+            headers,
+            body: expand(&template.body, variables, &mut resolved),
+        }
+    };
+    if !template.script.is_empty() {
+        prepared = run_pre_request(&template.script, prepared, variables)
+            .map_err(|it| MyErr::Client(format!("Pre-request script failed: {}", it)))?;
+    }
+    let params: Vec<String> = prepared
+        .params
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{}={}",
+                urlencoding::encode(key),
+                urlencoding::encode(value)
+            )
+        })
+        .collect();
+    let mut url = format!(
+        "{}{}{}",
+        prepared.url,
+        if params.is_empty() { "" } else { "?" },
+        params.join("&")
+    );
+    let mut headers = HeaderMap::from_iter(prepared.headers.iter().filter_map(|(key, value)| {
+        Some((
+            HeaderName::from_str(key).ok()?,
+            HeaderValue::from_str(value).ok()?,
+        ))
+    }));
+    template.auth.apply(&mut url, &mut headers);
+    Ok((url, headers, prepared.body, resolved))
+}
+
+fn send_now(state: &mut State, is_draft: bool) -> Task<Message> {
+    if method_from_state(state) == HttpMethod::WS {
+        return match state.ws {
+            Some(_) => Task::none(),
+            None => message_ws(state, MWs::Connect),
+        };
+    }
+    if method_from_state(state) == HttpMethod::GRPC {
+        return send_grpc(state, is_draft);
+    }
+    let body = match body_from_state(state) {
+        Ok(body) => body,
+        Err(err) => return update(state, Message::GotError(err)),
+    };
+    let (url, mut headers, body, variables) = match prepare_request(state, body, &[]) {
+        Ok(request) => request,
+        Err(err) => return update(state, Message::GotError(err)),
+    };
+    state.can_send = false;
+    let method = method_from_state(state);
+    let settings = send_settings(state);
+    settings
+        .auth
+        .sign(http_method(method).as_str(), &url, &mut headers, &body);
+    let (task, handle) = Task::run(
+        iced::stream::channel(16, move |mut output| async move {
+            let result = send_request(
+                &url,
+                headers,
+                &settings,
+                method,
+                body,
+                is_draft,
+                &mut output,
+            )
+            .await;
+            let message = match result {
+                Ok(Some(response)) => Message::GotResponse(
+                    Box::new(Response {
+                        variables,
+                        ..response
+                    }),
+                    method,
+                    is_draft,
+                ),
+                Ok(None) => Message::Sse(MSse::Ended),
+                Err(err) => Message::GotError(err),
+            };
+            let _ = output.send(message).await;
+        }),
+        |message| message,
+    )
+    .abortable();
+    state.request_handle = Some(handle);
+    task
+}
+
+// Sends the request with the values of one data row, the response is saved by MData::RowDone
+fn send_row(state: &mut State, index: usize) -> Task<Message> {
+    let Some(row) = state
+        .data_run
+        .as_ref()
+        .and_then(|it| it.rows.get(index))
+        .cloned()
+    else {
+        return Task::none();
+    };
+    let failed = |err| {
+        Task::done(Message::Data(MData::RowDone(
+            index,
+            Box::new(Err(err)),
+            Duration::ZERO,
+        )))
+    };
+    let body = match body_from_state(state) {
+        Ok(body) => body,
+        Err(err) => return failed(err),
+    };
+    let (url, mut headers, body, variables) = match prepare_request(state, body, &row) {
+        Ok(request) => request,
+        Err(err) => return failed(err),
+    };
+    let method = method_from_state(state);
+    let settings = send_settings(state);
+    settings
+        .auth
+        .sign(http_method(method).as_str(), &url, &mut headers, &body);
+    let (task, handle) =
+        Task::run(
+            iced::stream::channel(16, move |mut output| async move {
+                let start = Instant::now();
+                let result =
+                    match send_request(&url, headers, &settings, method, body, false, &mut output)
+                        .await
+                    {
+                        Ok(Some(response)) => Ok(Response {
+                            variables,
+                            ..response
+                        }),
+                        Ok(None) => Err(MyErr::Client(
+                            "Event streams can't be part of a data run".to_string(),
+                        )),
+                        Err(err) => Err(err),
+                    };
+                let message = MData::RowDone(index, Box::new(result), start.elapsed());
+                let _ = output.send(Message::Data(message)).await;
+            }),
+            |message| message,
+        )
+        .abortable();
+    state.request_handle = Some(handle);
+    task
+}
+
+// Every request of the run is prepared and signed from the same template, changes the
+// pre-request script makes to variables are dropped
+fn start_load_test(state: &mut State) -> Task<Message> {
+    let (Some(endpoint_id), Ok(settings)) = (state.selected_endpoint, state.load_form.settings())
+    else {
+        let err = state.load_form.settings().err().unwrap_or_default();
+        return update(state, Message::GotError(MyErr::Client(err)));
+    };
+    if state.auth.kind == AuthKind::OAuth2 && state.auth.oauth.fresh_token().is_none() {
+        return update(
+            state,
+            Message::GotError(MyErr::Client(
+                "Get an OAuth token before starting a load test".to_string(),
+            )),
+        );
+    }
+    // A Digest answer needs the challenge of every single request
+    if state.auth.kind == AuthKind::Digest {
+        return update(
+            state,
+            Message::GotError(MyErr::Client(
+                "Digest auth is not supported in load tests".to_string(),
+            )),
+        );
+    }
+    let body = match body_from_state(state) {
+        Ok(body) => body,
+        Err(err) => return update(state, Message::GotError(err)),
+    };
+    let template = request_template(state, body);
+    let variables = state.variables.clone();
+    let method = method_from_state(state);
+    let send = send_settings(state);
+    let client = match prepare_template(&template, &mut variables.clone(), &[])
+        .and_then(|(url, ..)| http_client(&send, &url))
+    {
+        Ok(client) => client,
+        Err(err) => return update(state, Message::GotError(err)),
+    };
+    let prepare = move |client: &reqwest::Client| -> Result<reqwest::Request, MyErr> {
+        let (url, mut headers, body, _) = prepare_template(&template, &mut variables.clone(), &[])?;
+        template
+            .auth
+            .sign(&template.method, &url, &mut headers, &body);
+        Ok(build_request(client, &url, method, headers, &body)?)
+    };
+    if let Err(err) = prepare(&client) {
+        return update(state, Message::GotError(err));
+    }
+    let stop = Arc::new(AtomicBool::new(false));
+    state.load = Some(LoadProgress {
+        endpoint_id,
+        settings: settings.clone(),
+        stop: stop.clone(),
+        report: LoadReport::default(),
+        started_time: Utc::now().naive_utc(),
+    });
+    state.selected_load_run = None;
+    state.error_message = None;
+    state.can_send = false;
+    Task::run(
+        iced::stream::channel(16, move |mut output| async move {
+            let request = LoadRequest {
+                prepare: Box::new({
+                    let client = client.clone();
+                    move || prepare(&client).ok()
+                }),
+                redirect: send.redirect,
+                credentials: send.auth.credential_headers(),
+            };
+            let mut progress = output.clone();
+            let report = logic::load::run(client, request, settings, stop, |report| {
+                // A snapshot that doesn't fit is dropped, the next one follows shortly
+                let _ = progress.try_send(Message::Load(MLoad::Progress(report)));
+            })
+            .await;
+            let _ = output.send(Message::Load(MLoad::Done(report))).await;
+        }),
+        |message| message,
+    )
+}
+
+fn send_grpc(state: &mut State, is_draft: bool) -> Task<Message> {
+    // Without loaded descriptors the call asks the server by reflection first
+    let pool = state
+        .grpc_pools
+        .get(&split_url(&format_url_from_state(state)).0)
+        .cloned();
+    let body = state.grpc_message.text();
+    let (url, headers, body, variables) = match prepare_request(state, body, &[]) {
+        Ok(request) => request,
+        Err(err) => return update(state, Message::GotError(err)),
+    };
+    // Credentials are sent as metadata, an API key query param has no place in a gRPC call
+    let url = url.split('?').next().unwrap_or_default().to_string();
+    state.can_send = false;
+    let tls = state.tls.clone();
+    Task::perform(
+        async move {
+            let (text, status) = grpc::call(&url, pool, &body, headers, &tls).await?;
+            Ok(Response {
+                body: text.as_bytes().to_vec(),
+                text,
+                code: StatusCode::OK,
+                content_type: "application/json".to_string(),
+                grpc_status: Some(status),
+                received_time: Utc::now().naive_utc(),
+                request: Request {
+                    body,
+                    ..Request::default()
+                },
+                variables,
+                ..Response::default()
+            })
+        },
+        move |res: Result<Response, MyErr>| match res {
+            Ok(response) => Message::GotResponse(Box::new(response), HttpMethod::GRPC, is_draft),
+            Err(err) => Message::GotError(err),
+        },
+    )
+}
+
+fn http_method(method: HttpMethod) -> reqwest::Method {
+    match method {
+        HttpMethod::GET | HttpMethod::WS => reqwest::Method::GET,
+        HttpMethod::POST
+        | HttpMethod::GQL
+        | HttpMethod::GRPC
+        | HttpMethod::RPC
+        | HttpMethod::SOAP => reqwest::Method::POST,
+    }
+}
+
+fn request_builder(client: &reqwest::Client, url: &str, method: HttpMethod) -> RequestBuilder {
+    client.request(http_method(method), url)
+}
+
+fn default_content_type(method: HttpMethod) -> &'static str {
+    match method {
+        HttpMethod::SOAP => "text/xml; charset=utf-8",
+        _ => "application/json",
+    }
+}
+
+fn content_type_header(resp: &reqwest::Response) -> Option<String> {
+    resp.headers()
+        .get(CONTENT_TYPE)
+        .and_then(|it| it.to_str().ok())
+        .map(str::to_string)
+}
+
+// The endpoint settings send_request needs besides the request itself
+struct SendSettings {
+    auth: Auth,
+    retry: RetryPolicy,
+    redirect: RedirectPolicy,
+    client: ClientSettings,
+}
+
+fn send_settings(state: &State) -> SendSettings {
+    SendSettings {
+        auth: state.auth.clone(),
+        retry: state.retry.clone(),
+        redirect: state.redirect.clone(),
+        client: client_settings(state),
     }
-    url.to_string()
 }
 
-fn method_from_state(state: &State) -> HttpMethod {
-    match current_endpoint(state) {
-        Some(endpoint) => endpoint.method,
-        None => state.draft_method,
+fn client_settings(state: &State) -> ClientSettings {
+    ClientSettings {
+        proxy: state.endpoint_proxy.effective(&state.proxy).clone(),
+        tls: state.tls.clone(),
     }
 }
 
-fn format_url_from_state(state: &State) -> String {
-    match current_endpoint(state) {
-        Some(endpoint) => {
-            let empty = [].to_vec().clone();
-            let query_param_vec: Vec<String> = (match &state.copy_request {
-                Some(drafts) => &drafts.query_params,
-                None => match endpoint.responses.get(state.selected_response_index) {
-                    Some(resp) => &resp.request.query_params,
-                    None => &empty,
-                },
-            })
-            .iter()
-            .filter(|it| !it.key.is_empty() && it.on)
-            .map(|it| {
-                format!(
-                    "{}={}",
-                    urlencoding::encode(&it.key),
-                    urlencoding::encode(&it.value)
-                )
-            })
-            .collect();
-            format!(
-                "{}{}{}",
-                endpoint.url,
-                if query_param_vec.is_empty() { "" } else { "?" },
-                query_param_vec.join("&")
+// Redirects are followed by logic::redirect::execute, which records them
+fn http_client(settings: &SendSettings, url: &str) -> Result<reqwest::Client, MyErr> {
+    Ok(settings
+        .client
+        .builder(url)?
+        .redirect(reqwest::redirect::Policy::none())
+        .tls_info(true)
+        .build()?)
+}
+
+// Every request gets the same defaults, whether it is sent once or by a load test
+fn build_request(
+    client: &reqwest::Client,
+    url: &str,
+    method: HttpMethod,
+    headers: HeaderMap,
+    body: &str,
+) -> reqwest::Result<reqwest::Request> {
+    let mut builder = request_builder(client, url, method);
+    if !body.is_empty() && !headers.contains_key(CONTENT_TYPE) {
+        builder = builder.header(CONTENT_TYPE, default_content_type(method));
+    }
+    builder.headers(headers).body(body.to_string()).build()
+}
+
+// Sends the request the way the endpoint asks: redirect policy, digest challenge and retries.
+// `headers` gets the digest credentials, so later requests of an event stream reuse them.
+async fn send_with_policies(
+    client: &reqwest::Client,
+    url: &str,
+    headers: &mut HeaderMap,
+    settings: &SendSettings,
+    method: HttpMethod,
+    body: &str,
+) -> Result<(reqwest::Response, Vec<Attempt>, Vec<Hop>), MyErr> {
+    let request = |headers: HeaderMap| build_request(client, url, method, headers, body);
+    let (auth, retry, redirect) = (&settings.auth, &settings.retry, &settings.redirect);
+    let wire_method = http_method(method);
+    let mut attempts = Vec::new();
+    let mut redirects = Vec::new();
+    let credentials = auth.credential_headers();
+    let result = loop {
+        let started = Instant::now();
+        let mut result = execute(
+            client,
+            request(headers.clone())?,
+            redirect,
+            &credentials,
+            &mut redirects,
+        )
+        .await;
+        // Digest credentials can only be computed from the server's challenge, so the request is sent again
+        if let Ok(resp) = &result
+            && resp.status() == StatusCode::UNAUTHORIZED
+            && let Some(authorization) =
+                auth.digest_response(resp.headers(), wire_method.as_str(), resp.url(), body)
+        {
+            // The challenge came from where the redirects ended, so the answer goes there
+            let url = resp.url().to_string();
+            headers.insert(AUTHORIZATION, authorization);
+            result = execute(
+                client,
+                build_request(client, &url, method, headers.clone(), body)?,
+                redirect,
+                &credentials,
+                &mut redirects,
             )
+            .await;
         }
-        None => {
-            let query_param_vec: Vec<String> = state
-                .draft_request
-                .query_params
-                .iter()
-                .filter(|it| !it.key.is_empty() && it.on)
-                .map(|it| {
-                    format!(
-                        "{}={}",
-                        urlencoding::encode(&it.key),
-                        urlencoding::encode(&it.value)
-                    )
-                })
-                .collect();
-            format!(
-                "{}{}{}",
-                state.draft,
-                if query_param_vec.is_empty() { "" } else { "?" },
-                query_param_vec.join("&")
+        let attempt = attempts.len() as u32 + 1;
+        let wait = match &result {
+            Ok(resp) if retry.retries_status(resp.status()) => {
+                Some(retry.delay(attempt, resp.headers().get(RETRY_AFTER)))
+            }
+            Err(err) if retry.retries_error(err) => Some(retry.delay(attempt, None)),
+            _ => None,
+        }
+        .filter(|_| attempt < retry.max_attempts);
+        if retry.is_on() {
+            attempts.push(Attempt {
+                outcome: match &result {
+                    Ok(resp) => Ok(resp.status().as_u16()),
+                    Err(err) => Err(err.to_string()),
+                },
+                duration: started.elapsed(),
+                wait: wait.unwrap_or_default(),
+            });
+        }
+        match wait {
+            Some(wait) => tokio::time::sleep(wait).await,
+            None => break result,
+        }
+    };
+    match result {
+        Ok(resp) => Ok((resp, attempts, redirects)),
+        Err(err) if attempts.len() > 1 && certificate_error(&err).is_none() => Err(MyErr::Client(
+            format!("Gave up after {} attempts: {}", attempts.len(), err),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// Event streams never finish, so they are reported event by event and Ok(None) is returned
+async fn send_request(
+    url: &str,
+    mut headers: HeaderMap,
+    settings: &SendSettings,
+    method: HttpMethod,
+    body: String,
+    is_draft: bool,
+    output: &mut Sender<Message>,
+) -> Result<Option<Response>, MyErr> {
+    let client = http_client(settings, url)?;
+    let (resp, attempts, redirects) =
+        send_with_policies(&client, url, &mut headers, settings, method, &body).await?;
+    let status = resp.status();
+    let header = content_type_header(&resp);
+    let response_headers = resp
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).to_string(),
             )
+        })
+        .collect();
+    if header.as_deref().is_some_and(is_event_stream) {
+        let _ = output
+            .send(Message::Sse(MSse::Opened(
+                status,
+                method,
+                is_draft,
+                body.clone(),
+            )))
+            .await;
+        stream_events(resp, url, headers, settings, method, &body, output).await?;
+        return Ok(None);
+    }
+    let connection = connection_info(&resp);
+    let data = resp.bytes().await?.to_vec();
+    let content_type = detect_content_type(header.as_deref(), url, &data);
+    let text = if is_binary(&content_type) {
+        String::new()
+    } else {
+        decode_body(&data, &content_type)
+    };
+    Ok(Some(Response {
+        // SOAP responses usually come as a single line
+        text: match method {
+            HttpMethod::SOAP => format_body(&text, BodyLanguage::Xml, false).unwrap_or(text),
+            _ => text,
+        },
+        code: status,
+        image: is_image(&content_type).then(|| image::Handle::from_bytes(data.clone())),
+        body: data,
+        content_type,
+        headers: response_headers,
+        attempts,
+        redirects,
+        connection: Some(connection),
+        received_time: Utc::now().naive_utc(),
+        request: Request {
+            body,
+            ..Request::default()
+        },
+        ..Response::default()
+    }))
+}
+
+// Reads events until the server closes the stream, then reconnects with Last-Event-ID
+// like a browser EventSource would. Runs until the task is aborted or the server opts out.
+async fn stream_events(
+    mut resp: reqwest::Response,
+    url: &str,
+    headers: HeaderMap,
+    settings: &SendSettings,
+    method: HttpMethod,
+    body: &str,
+    output: &mut Sender<Message>,
+) -> Result<(), MyErr> {
+    let client = http_client(settings, url)?;
+    let mut parser = SseParser::default();
+    loop {
+        while let Ok(Some(chunk)) = resp.chunk().await {
+            for event in parser.feed(&chunk) {
+                let _ = output.send(Message::Sse(MSse::Event(event))).await;
+            }
         }
+        let _ = output.send(Message::Sse(MSse::Disconnected)).await;
+        let mut headers = headers.clone();
+        if let Some(id) = parser
+            .last_event_id
+            .as_deref()
+            .and_then(|it| HeaderValue::from_str(it).ok())
+        {
+            headers.insert("last-event-id", id);
+        }
+        // A server that is down or restarting is tried again after the same delay. The
+        // reconnect follows redirects and answers auth challenges like the first request did.
+        resp = loop {
+            tokio::time::sleep(Duration::from_millis(parser.retry.unwrap_or(3000))).await;
+            let mut headers = headers.clone();
+            if let Ok((resp, ..)) =
+                send_with_policies(&client, url, &mut headers, settings, method, body).await
+            {
+                break resp;
+            }
+        };
+        let status = resp.status();
+        if status == StatusCode::NO_CONTENT
+            || !content_type_header(&resp).is_some_and(|it| is_event_stream(&it))
+        {
+            return Ok(());
+        }
+        parser.reset();
+        let _ = output
+            .send(Message::Sse(MSse::Opened(
+                status,
+                method,
+                false,
+                body.to_string(),
+            )))
+            .await;
     }
 }
 
-fn headers_from_state(state: &State) -> HeaderMap {
-    let empty: Vec<EndpointKvPair> = [].to_vec().clone();
-    match current_endpoint(state) {
-        Some(endpoint) => HeaderMap::from_iter(
-            (match &state.copy_request {
-                Some(drafts) => &drafts.headers,
-                None => match endpoint.responses.get(state.selected_response_index) {
-                    Some(resp) => &resp.request.headers,
-                    None => &empty,
-                },
-            })
-            .iter()
-            .filter(|it| !it.key.is_empty() && it.on)
-            .map(|it| {
-                (
-                    HeaderName::from_str(&it.key).unwrap(),
-                    HeaderValue::from_str(&it.value).unwrap(),
-                )
-            }),
-        ),
-        None => HeaderMap::from_iter(
-            state
-                .draft_request
-                .headers
-                .iter()
-                .filter(|it| !it.key.is_empty() && it.on)
-                .map(|it| {
-                    (
-                        HeaderName::from_str(&it.key).unwrap(),
-                        HeaderValue::from_str(&it.value).unwrap(),
-                    )
-                }),
-        ),
+fn connect_ws(url: String, headers: HeaderMap, tls: TlsSettings) -> impl Stream<Item = Message> {
+    iced::stream::channel(16, move |mut output| async move {
+        let err = ws_session(&url, headers, &tls, &mut output).await.err();
+        let _ = output.send(Message::Ws(MWs::Closed(err))).await;
+    })
+}
+
+async fn ws_session(
+    url: &str,
+    headers: HeaderMap,
+    tls: &TlsSettings,
+    output: &mut Sender<Message>,
+) -> Result<(), MyErr> {
+    let mut request = url.into_client_request()?;
+    request.headers_mut().extend(headers);
+    // Plain ws:// never uses the connector
+    let connector = tls
+        .native_connector(request.uri().host().unwrap_or_default())
+        .map_err(MyErr::Certificate)?;
+    let (socket, response) =
+        connect_async_tls_with_config(request, None, false, Some(Connector::NativeTls(connector)))
+            .await?;
+    let (commands, mut outgoing) = mpsc::unbounded();
+    let _ = output
+        .send(Message::Ws(MWs::Connected(response.status(), commands)))
+        .await;
+
+    let (mut sink, mut incoming) = socket.split();
+    loop {
+        tokio::select! {
+            command = outgoing.next() => match command {
+                Some(message) => sink.send(message).await?,
+                None => return Ok(()),
+            },
+            message = incoming.next() => {
+                let (binary, data) = match message {
+                    Some(Ok(WsMessage::Text(text))) => (false, text.as_bytes().to_vec()),
+                    Some(Ok(WsMessage::Binary(data))) => (true, data.to_vec()),
+                    Some(Ok(WsMessage::Close(_))) | None => return Ok(()),
+                    Some(Ok(_)) => continue,
+                    Some(Err(err)) => return Err(err.into()),
+                };
+                let frame = WsFrame {
+                    outgoing: false,
+                    binary,
+                    data,
+                    time: Utc::now().naive_utc(),
+                };
+                let _ = output.send(Message::Ws(MWs::Received(frame))).await;
+            }
+        }
     }
 }
 
-fn response_panels<'a>(
-    resp: &'a Response,
-    state: &'a State,
-    resp_count: usize,
-) -> Column<'a, Message, Theme, Renderer> {
-    let time = Local::now().offset().from_utc_datetime(&resp.received_time);
-    column![
-        mb(
-            ml(
-                row![
-                    bi(
-                        Icons::Plus,
-                        Some(Message::SetDraftQuery(false)),
-                        if state.copy_request.is_none() {
-                            ButtonType::OutlinedInline
-                        } else {
-                            ButtonType::PrimaryInline
-                        }
-                    ),
-                    scrollable(
-                        Row::from_iter((1..resp_count + 1).rev().map(|index| {
-                            bt(
-                                index,
-                                Some(Message::SetSelectedResponseIndex(index - 1)),
-                                if index == state.selected_response_index + 1
-                                    && state.copy_request.is_none()
-                                {
-                                    ButtonType::Primary
-                                } else {
-                                    ButtonType::Text
-                                },
-                            )
-                            .into()
-                        }))
-                        .spacing(8)
-                        .align_y(Center)
-                    )
-                    .direction(scrollable::Direction::Horizontal(
-                        Scrollbar::default().width(0).scroller_width(0)
-                    ))
-                    .width(Fill)
-                ]
-                .spacing(8)
-                .align_y(Center)
-                .into(),
-                16.0
-            )
-            .into(),
-            16.0
-        ),
-        ml(
-            card(column![
-                row![
-                    row![
-                        container(text!("{}", resp.code).style(|_| text::Style {
-                            color: Some(Color::BLACK)
-                        }))
-                        .padding([2, 4])
-                        .style(|_| {
-                            container::Style {
-                                background: Some(iced::Background::Color(color_for_status(
-                                    resp.code,
-                                ))),
-                                ..container::Style::default()
-                            }
-                        }),
-                        column![
-                            text(time.format("%H:%M:%S").to_string())
-                                .size(14)
-                                .line_height(1.0),
-                            text(time.format("%d-%m-%Y").to_string())
-                                .size(10)
-                                .line_height(0.9)
-                        ]
-                    ]
-                    .align_y(Center)
-                    .spacing(8)
-                    .width(Fill),
-                    bi(
-                        Icons::Format,
-                        Some(Message::FormatResponse),
-                        ButtonType::Text
-                    ),
-                    bi(
-                        Icons::Duplicate,
-                        Some(Message::ClickCopyResponse),
-                        ButtonType::Text
-                    ),
-                    bi(
-                        Icons::Delete,
-                        Some(Message::ClickDeleteResponse(resp.id)),
-                        ButtonType::Text
-                    )
-                ]
-                .align_y(Center)
-                .spacing(8),
-                mb(
-                    scrollable(match &state.formatted_response {
-                        Some(fmt) => text(fmt),
-                        None => text(&resp.text),
-                    })
-                    .direction(iced::widget::scrollable::Direction::Both {
-                        vertical: Scrollbar::default(),
-                        horizontal: Scrollbar::default()
-                    })
-                    .height(Fill)
-                    .width(Fill)
-                    .into(),
-                    16.0
-                )
-                .padding([16, 0])
-            ])
-            .width(Fill)
-            .into(),
-            16.0,
-        )
-    ]
+pub async fn fetch_schema(
+    url: String,
+    headers: HeaderMap,
+    settings: ClientSettings,
+) -> Result<Schema, MyErr> {
+    let body = request_body(INTROSPECTION_QUERY, "", None).map_err(MyErr::Client)?;
+    let text = settings
+        .client(&url)?
+        .post(&url)
+        .header(CONTENT_TYPE, "application/json")
+        .headers(headers)
+        .body(body)
+        .send()
+        .await?
+        .text()
+        .await?;
+    parse_schema(&text).map_err(MyErr::Client)
 }
 
-async fn send_request(
+async fn fetch_wsdl(
+    url: String,
+    headers: HeaderMap,
+    settings: ClientSettings,
+) -> Result<Wsdl, MyErr> {
+    let text = settings
+        .client(&url)?
+        .get(&url)
+        .headers(headers)
+        .send()
+        .await?
+        .text()
+        .await?;
+    parse_wsdl(&text).map_err(MyErr::Client)
+}
+
+async fn pick_data_file() -> Option<Result<(String, Vec<DataRow>), MyErr>> {
+    let file = rfd::AsyncFileDialog::new()
+        .add_filter("CSV or JSON", &["csv", "json"])
+        .pick_file()
+        .await?;
+    Some(match tokio::fs::read_to_string(file.path()).await {
+        Ok(text) => parse_data(file.path(), &text)
+            .map(|rows| (file.file_name(), rows))
+            .map_err(MyErr::Client),
+        Err(err) => Err(err.into()),
+    })
+}
+
+async fn pick_tls_file(name: &str, extensions: &[&str]) -> Option<String> {
+    let file = rfd::AsyncFileDialog::new()
+        .add_filter(name, extensions)
+        .pick_file()
+        .await?;
+    Some(file.path().to_string_lossy().to_string())
+}
+
+async fn pick_wsdl() -> Option<Result<Wsdl, MyErr>> {
+    let file = rfd::AsyncFileDialog::new()
+        .add_filter("WSDL", &["wsdl", "xml"])
+        .pick_file()
+        .await?;
+    Some(match tokio::fs::read_to_string(file.path()).await {
+        Ok(text) => parse_wsdl(&text).map_err(MyErr::Client),
+        Err(err) => Err(err.into()),
+    })
+}
+
+pub async fn pick_protos() -> Option<Result<(DescriptorPool, Vec<String>), String>> {
+    let files = rfd::AsyncFileDialog::new()
+        .add_filter("Protocol Buffers", &["proto"])
+        .pick_files()
+        .await?;
+    let paths: Vec<PathBuf> = files.iter().map(|it| it.path().to_path_buf()).collect();
+    Some(load_protos(&paths).map(|pool| {
+        let paths = paths.iter().map(|it| it.display().to_string()).collect();
+        (pool, paths)
+    }))
+}
+
+// The .proto files of an endpoint are parsed again when it is opened, unless its server
+// already has descriptors
+fn load_saved_protos(state: &State) -> Task<Message> {
+    let (origin, _) = split_url(&format_url_from_state(state));
+    if state.proto_paths.is_empty() || state.grpc_pools.contains_key(&origin) {
+        return Task::none();
+    }
+    let paths = state.proto_paths.clone();
+    Task::perform(
+        async move {
+            let files: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+            (load_protos(&files), paths)
+        },
+        move |(res, paths)| match res {
+            Ok(pool) => Message::Grpc(MGrpc::GotPool(origin.clone(), pool, paths)),
+            Err(err) => Message::GotError(MyErr::Client(err)),
+        },
+    )
+}
+
+async fn pick_download_file(url: String) -> Option<PathBuf> {
+    let file_name = Url::parse(&url)
+        .ok()
+        .and_then(|it| {
+            it.path_segments()
+                .and_then(|mut segments| segments.next_back().map(str::to_string))
+        })
+        .filter(|it| !it.is_empty())
+        .unwrap_or_else(|| "download".to_string());
+    rfd::AsyncFileDialog::new()
+        .set_file_name(file_name)
+        .save_file()
+        .await
+        .map(|it| it.path().to_path_buf())
+}
+
+// Streams the body straight to disk, so large downloads never sit in memory or in the db
+fn download_to_file(
     url: String,
     headers: HeaderMap,
     method: HttpMethod,
-) -> Result<(String, StatusCode), MyErr> {
-    let client = reqwest::Client::new();
-    let resp = match method {
-        HttpMethod::GET => client.get(url),
-        HttpMethod::POST => client.post(url),
-    }
-    .headers(headers)
-    .send()
-    .await?;
+    body: String,
+    variables: Vec<(String, String)>,
+    settings: SendSettings,
+    path: PathBuf,
+) -> impl Stream<Item = MDownload> {
+    iced::stream::channel(16, move |mut output| async move {
+        let result = stream_to_file(&url, headers, method, body, &settings, &path, &mut output)
+            .await
+            .map(|response| {
+                Box::new(Response {
+                    variables,
+                    ..response
+                })
+            });
+        let _ = output.send(MDownload::Done(result)).await;
+    })
+}
+
+async fn stream_to_file(
+    url: &str,
+    mut headers: HeaderMap,
+    method: HttpMethod,
+    body: String,
+    settings: &SendSettings,
+    path: &Path,
+    output: &mut Sender<MDownload>,
+) -> Result<Response, MyErr> {
+    let client = http_client(settings, url)?;
+    let (mut resp, attempts, redirects) =
+        send_with_policies(&client, url, &mut headers, settings, method, &body).await?;
     let status = resp.status();
-    let text = resp.text().await?;
-    Ok((text, status))
+    let header = content_type_header(&resp);
+    let total = resp.content_length();
+
+    let mut file = tokio::fs::File::create(path).await?;
+    let mut head = Vec::new();
+    let mut received = 0;
+    let mut reported = Instant::now();
+    let written: Result<(), MyErr> = async {
+        while let Some(chunk) = resp.chunk().await? {
+            file.write_all(&chunk).await?;
+            if head.len() < 512 {
+                head.extend_from_slice(&chunk[..chunk.len().min(512 - head.len())]);
+            }
+            received += chunk.len() as u64;
+            if reported.elapsed() >= Duration::from_millis(100) {
+                reported = Instant::now();
+                let _ = output.send(MDownload::Progress(received, total)).await;
+            }
+        }
+        Ok(file.flush().await?)
+    }
+    .await;
+    // A partial file looks like a complete one, so it doesn't stay behind
+    if let Err(err) = written {
+        drop(file);
+        let _ = tokio::fs::remove_file(path).await;
+        return Err(err);
+    }
+
+    Ok(Response {
+        code: status,
+        content_type: detect_content_type(header.as_deref(), url, &head),
+        file_path: Some(path.display().to_string()),
+        file_size: received,
+        attempts,
+        redirects,
+        received_time: Utc::now().naive_utc(),
+        request: Request {
+            body,
+            ..Request::default()
+        },
+        ..Response::default()
+    })
+}
+
+async fn save_body(body: Vec<u8>, file_name: String) -> Result<(), MyErr> {
+    let Some(file) = rfd::AsyncFileDialog::new()
+        .set_file_name(file_name)
+        .save_file()
+        .await
+    else {
+        return Ok(());
+    };
+    file.write(&body).await?;
+    Ok(())
 }
 
 fn subscription(_state: &State) -> Subscription<Message> {
@@ -1529,8 +4977,12 @@ fn subscription(_state: &State) -> Subscription<Message> {
                 }
             }
             keyboard::key::Key::Character(key) => {
-                if mods.contains(Modifiers::CTRL) && key == "f" {
-                    Some(Message::Focus("searchbar"))
+                if mods.contains(Modifiers::CTRL) && (key == "f" || key == "F") {
+                    if mods.contains(Modifiers::SHIFT) {
+                        Some(Message::Focus("searchbar"))
+                    } else {
+                        Some(Message::FocusFind)
+                    }
                 } else {
                     None
                 }
@@ -1560,7 +5012,7 @@ fn theme(state: &State) -> Theme {
 fn color_for_status(code: StatusCode) -> Color {
     match code {
         StatusCode::OK => Color::parse("#9ECE6A"),
-        StatusCode::FOUND => Color::parse("#414868"),
+        code if code.is_redirection() => Color::parse("#414868"),
         _ => Color::parse("#F7768E"),
     }
     .unwrap()
@@ -1570,6 +5022,11 @@ fn color_for_method(method: HttpMethod) -> Color {
     match method {
         HttpMethod::GET => Color::parse("#9ECE6A"),
         HttpMethod::POST => Color::parse("#e8de6d"),
+        HttpMethod::GQL => Color::parse("#E535AB"),
+        HttpMethod::GRPC => Color::parse("#00B4AB"),
+        HttpMethod::RPC => Color::parse("#BB9AF7"),
+        HttpMethod::SOAP => Color::parse("#FF9E64"),
+        HttpMethod::WS => Color::parse("#7DCFFF"),
         _ => Color::parse("#414868"),
     }
     .unwrap()